@@ -2,9 +2,9 @@ source neardev/dev-account.env
 source .env
 
 near call $CONTRACT_NAME pembrock_create_strategy '{
-    "strategy_fee": 5,
-    "strat_creator": { "account_id": "'$username'", "fee_percentage": 5, "current_amount" : 0 },
-    "sentry_fee": 10,
+    "strategy_fee": 500,
+    "strat_creator": { "account_id": "'$username'", "fee_percentage": 500, "current_amount" : 0 },
+    "sentry_fee": 1000,
     "exchange_contract_id": "'$exchange_contract_id'", 
     "pembrock_contract_id": "'$pembrock_contract_id'",
     "pembrock_reward_id": "'$pembrock_reward_id'",
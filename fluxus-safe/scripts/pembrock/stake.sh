@@ -11,4 +11,4 @@ near call $CONTRACT_NAME storage_deposit '{"account_id": "'$username'", "registr
 near call $token_address ft_transfer_call '{
     "receiver_id": "'$CONTRACT_NAME'",
     "amount": "100000000000000000000000",
-    "msg": "deposit"}' --accountId $username --gas 300000000000000 --depositYocto 1
\ No newline at end of file
+    "msg": "{\"strat_name\": \"pembrock@'$token_address'\"}"}' --accountId $username --gas 300000000000000 --depositYocto 1
\ No newline at end of file
@@ -1,4 +1,4 @@
 source neardev/dev-account.env
 source .env
 
-near call $CONTRACT_NAME pembrock_unstake '{ "token_address": "'$token_address'" }' --accountId $username --gas 300000000000000 
+near call $CONTRACT_NAME unstake '{ "seed_id": "pembrock@'$token_address'" }' --accountId $username --gas 300000000000000 
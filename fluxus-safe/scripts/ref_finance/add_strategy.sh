@@ -8,9 +8,9 @@ echo $reward_token
 #### Create first strategy
 near call $CONTRACT_NAME create_strategy '{
     "_strategy": "",
-    "strategy_fee": 5,
-    "strat_creator": { "account_id": "'$username'", "fee_percentage": 5, "current_amount" : 0 },
-    "sentry_fee": 10,
+    "strategy_fee": 500,
+    "strat_creator": { "account_id": "'$username'", "fee_percentage": 500, "current_amount" : 0 },
+    "sentry_fee": 1000,
     "exchange_contract_id": "'$exchange_contract_id'", 
     "farm_contract_id": "'$farm_contract_id'",
     "token1_address": "'$token1_address'", 
@@ -11,9 +11,9 @@ near view ref-finance-101.testnet get_pool '{ "pool_id": '$pool_id' }'
 #### Create first strategy
 near call $CONTRACT_NAME create_stable_strategy '{
     "_strategy": "",
-    "strategy_fee": 5,
-    "strat_creator": { "account_id": "'$username'", "fee_percentage": 5, "current_amount" : 0 },
-    "sentry_fee": 10,
+    "strategy_fee": 500,
+    "strat_creator": { "account_id": "'$username'", "fee_percentage": 500, "current_amount" : 0 },
+    "sentry_fee": 1000,
     "exchange_contract_id": "'$exchange_contract_id'", 
     "farm_contract_id": "'$farm_contract_id'",
     "pool_id": '$pool_id', 
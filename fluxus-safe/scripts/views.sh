@@ -136,13 +136,13 @@ near view $CONTRACT_NAME get_strategies_info_for_stable_ref_finance  '{}'
 near view $CONTRACT_NAME get_strategies_info_for_pembrock  '{}'
 # {
 #     admin_fees: {
-#       strategy_fee: 5,
+#       strategy_fee: 500,
 #       strat_creator: {
 #         account_id: 'mesto.testnet',
-#         fee_percentage: 5,
+#         fee_percentage: 500,
 #         current_amount: 0
 #       },
-#       sentries_fee: 10,
+#       sentries_fee: 1000,
 #       sentries: {}
 #     },
 #     exchange_contract_id: 'ref-finance-101.testnet',
@@ -4,6 +4,7 @@ use crate::*;
 #[near_bindgen]
 impl Contract {
     // TODO: thi&s method should register in the correct pool/farm
+    #[payable]
     pub fn create_strategy(
         &mut self,
         _strategy: String,
@@ -17,14 +18,14 @@ impl Contract {
         pool_id: u64,
         seed_min_deposit: U128,
     ) -> String {
-        self.is_owner_or_guardians();
+        assert!(self.is_owner_or_guardians(), "ERR: not allowed");
 
         let token_id = wrap_mft_token_id(&pool_id.to_string());
 
         let seed_id: String = format!("{}@{}", exchange_contract_id, pool_id);
 
         // TODO: update to seed
-        return if self.data().strategies.contains_key(&seed_id) {
+        return if self.data().strategies.get(&seed_id).is_some() {
             format!("VersionedStrategy for {} already exist", token_id)
         } else {
             // OK
@@ -48,9 +49,8 @@ impl Contract {
             if let Some(share_id) = uxu_share_id {
                 log!("Registering {} to {}", share_id, seed_id);
                 //Registering id for the specific seed
-                data_mut
-                    .fft_share_by_seed_id
-                    .insert(seed_id.clone(), share_id.clone());
+                data_mut.fft_share_by_seed_id.insert(&seed_id, &share_id);
+                data_mut.seed_id_by_fft_share.insert(&share_id, &seed_id);
 
                 //Registering id in the users balance map
                 let temp = LookupMap::new(StorageKey::Strategy {
@@ -68,7 +68,10 @@ impl Contract {
             }
 
             // TODO: update to seed id
-            data_mut.strategies.insert(seed_id, strat);
+            data_mut.strategies.insert(&seed_id, &strat);
+
+            self.emit_strategy_created(&seed_id);
+            self.deploy_share_token_on_create(&seed_id);
 
             format!("VersionedStrategy for {} created successfully", token_id)
         };
@@ -82,8 +85,10 @@ impl Contract {
         reward_token: AccountId,
         farm_id: String,
     ) -> String {
-        self.is_owner_or_guardians();
-        let compounder = self.get_strat_mut(&seed_id).get_compounder_mut();
+        assert!(self.is_owner_or_guardians(), "ERR: not allowed");
+        self.assert_not_emergency_exited(&seed_id);
+        let mut strat = self.get_strat(&seed_id);
+        let compounder = strat.get_compounder_mut();
 
         for farm in compounder.farms.clone() {
             if farm.id == farm_id {
@@ -95,6 +100,7 @@ impl Contract {
             state: AutoCompounderState::Running,
             cycle_stage: AutoCompounderCycle::ClaimReward,
             slippage: 99u128,
+            max_slippage: MAX_SLIPPAGE_ALLOWED,
             last_reward_amount: 0u128,
             last_fee_amount: 0u128,
             pool_id_token1_reward,
@@ -106,12 +112,15 @@ impl Contract {
 
         compounder.farms.push(farm_info);
 
+        self.save_strat(&seed_id, &strat);
+
         format!(
             "Farm with index {} for {} created successfully",
             farm_id, seed_id
         )
     }
 
+    #[payable]
     pub fn create_stable_strategy(
         &mut self,
         _strategy: String,
@@ -125,14 +134,14 @@ impl Contract {
     ) -> String {
         // TODO: is stable available on jumbo?
 
-        self.is_owner_or_guardians();
+        assert!(self.is_owner_or_guardians(), "ERR: not allowed");
 
         let token_id = wrap_mft_token_id(&pool_id.to_string());
 
         let seed_id: String = format!("{}@{}", exchange_contract_id, pool_id);
 
         // TODO: update to seed
-        return if self.data().strategies.contains_key(&seed_id) {
+        return if self.data().strategies.get(&seed_id).is_some() {
             format!("VersionedStrategy for {} already exist", token_id)
         } else {
             let uxu_share_id = self.new_fft_share(seed_id.clone());
@@ -154,9 +163,8 @@ impl Contract {
             if let Some(share_id) = uxu_share_id {
                 log!("Registering {} to {}", share_id, seed_id);
                 //Registering id for the specific seed
-                data_mut
-                    .fft_share_by_seed_id
-                    .insert(seed_id.clone(), share_id.clone());
+                data_mut.fft_share_by_seed_id.insert(&seed_id, &share_id);
+                data_mut.seed_id_by_fft_share.insert(&share_id, &seed_id);
 
                 //Registering id in the users balance map
                 let temp = LookupMap::new(StorageKey::Strategy {
@@ -174,7 +182,10 @@ impl Contract {
             }
 
             // TODO: update to seed id
-            data_mut.strategies.insert(seed_id, strat);
+            data_mut.strategies.insert(&seed_id, &strat);
+
+            self.emit_strategy_created(&seed_id);
+            self.deploy_share_token_on_create(&seed_id);
 
             format!("VersionedStrategy for {} created successfully", token_id)
         };
@@ -190,8 +201,10 @@ impl Contract {
         available_balance: Vec<Balance>,
         farm_id: String,
     ) -> String {
-        self.is_owner_or_guardians();
-        let stable_compounder = self.get_strat_mut(&seed_id).get_stable_compounder_mut();
+        assert!(self.is_owner_or_guardians(), "ERR: not allowed");
+        self.assert_not_emergency_exited(&seed_id);
+        let mut strat = self.get_strat(&seed_id);
+        let stable_compounder = strat.get_stable_compounder_mut();
 
         for farm in stable_compounder.farms.clone() {
             if farm.id == farm_id {
@@ -215,12 +228,15 @@ impl Contract {
 
         stable_compounder.farms.push(farm_info);
 
+        self.save_strat(&seed_id, &strat);
+
         format!(
             "Farm with index {} for {} created successfully",
             farm_id, seed_id
         )
     }
 
+    #[payable]
     pub fn create_jumbo_strategy(
         &mut self,
         _strategy: String,
@@ -234,11 +250,11 @@ impl Contract {
         pool_id: u64,
         seed_min_deposit: U128,
     ) -> String {
-        self.is_owner_or_guardians();
+        assert!(self.is_owner_or_guardians(), "ERR: not allowed");
 
         let token_id = wrap_mft_token_id(&pool_id.to_string());
 
-        return if self.data().strategies.contains_key(&token_id) {
+        return if self.data().strategies.get(&token_id).is_some() {
             format!("VersionedStrategy for {} already exist", token_id)
         } else {
             let seed_id: String = format!("{}@{}", exchange_contract_id, pool_id);
@@ -263,9 +279,8 @@ impl Contract {
             if let Some(share_id) = uxu_share_id {
                 log!("Registering {} to {}", share_id, seed_id);
                 //Registering id for the specific seed
-                data_mut
-                    .fft_share_by_seed_id
-                    .insert(seed_id.clone(), share_id.clone());
+                data_mut.fft_share_by_seed_id.insert(&seed_id, &share_id);
+                data_mut.seed_id_by_fft_share.insert(&share_id, &seed_id);
 
                 //Registering id in the users balance map
                 let temp = LookupMap::new(StorageKey::Strategy {
@@ -282,7 +297,10 @@ impl Contract {
                     .insert(&share_id, &0_u128);
             }
 
-            data_mut.strategies.insert(seed_id.clone(), strat);
+            data_mut.strategies.insert(&seed_id, &strat);
+
+            self.emit_strategy_created(&seed_id);
+            self.deploy_share_token_on_create(&seed_id);
 
             format!("VersionedStrategy for {} created successfully", seed_id)
         };
@@ -296,8 +314,10 @@ impl Contract {
         reward_token: AccountId,
         farm_id: String,
     ) -> String {
-        self.is_owner_or_guardians();
-        let compounder = self.get_strat_mut(&seed_id).get_jumbo_mut();
+        assert!(self.is_owner_or_guardians(), "ERR: not allowed");
+        self.assert_not_emergency_exited(&seed_id);
+        let mut strat = self.get_strat(&seed_id);
+        let compounder = strat.get_jumbo_mut();
 
         for farm in compounder.farms.clone() {
             if farm.id == farm_id {
@@ -321,6 +341,8 @@ impl Contract {
 
         compounder.farms.push(farm_info);
 
+        self.save_strat(&seed_id, &strat);
+
         format!(
             "Farm with index {} for {} created successfully",
             farm_id, seed_id
@@ -329,13 +351,12 @@ impl Contract {
 
     #[private]
     fn new_fft_share(&mut self, seed_id: String) -> Option<String> {
-        let already_has = self.data_mut().fft_share_by_seed_id.get(&seed_id).is_some();
+        let already_has = self.data().fft_share_by_seed_id.get(&seed_id).is_some();
         let fft_share_id;
         if already_has {
             fft_share_id = None
         } else {
-            let num: u128 =
-                u128::try_from(self.data_mut().fft_share_by_seed_id.keys().len()).unwrap() + 1_u128;
+            let num: u128 = u128::from(self.data().fft_share_by_seed_id.len()) + 1_u128;
             fft_share_id = Some(format!("fft_share_{num}"));
             log!(
                 "new fft_share created: {} for seed_id {}",
@@ -347,59 +368,92 @@ impl Contract {
         fft_share_id
     }
 
+    fn emit_strategy_created(&self, seed_id: &str) {
+        Event::StrategyCreated(&[StrategyCreatedData {
+            seed_id,
+            kind: self.get_strat(seed_id).kind(),
+            fft_share: &self.get_fft_share_id_from_seed(seed_id.to_string()),
+        }])
+        .emit();
+    }
+
     pub fn harvest(&mut self, farm_id_str: String, strat_name: String) -> PromiseOrValue<u128> {
         let treasury = self.data().treasury.clone();
 
-        let strat = if !strat_name.is_empty() {
-            self.pemb_get_strat_mut(&strat_name)
+        let seed_id = if !strat_name.is_empty() {
+            strat_name.clone()
         } else {
             let (seed_id, _, _) = get_ids_from_farm(farm_id_str.to_string());
-            self.get_strat_mut(&seed_id)
+            seed_id
         };
 
-        strat.harvest_proxy(farm_id_str, strat_name, treasury)
+        self.assert_not_emergency_exited(&seed_id);
+
+        let mut strat = self.get_strat(&seed_id);
+        let result = strat.harvest_proxy(farm_id_str, strat_name, treasury);
+        self.save_strat(&seed_id, &strat);
+
+        result
     }
 
+    /// Removes a single farm from its strategy.
+    /// Refused while the farm is in the middle of a harvest, its rewards and balances would be lost.
     pub fn delete_strategy_by_farm_id(&mut self, farm_id_str: String) {
-        self.is_owner_or_guardians();
+        assert!(self.is_owner_or_guardians(), "ERR: not allowed");
         let (seed_id, _, farm_id) = get_ids_from_farm(farm_id_str.clone());
-        let strat = self.get_strat_mut(&seed_id);
-
-        match strat {
-            VersionedStrategy::AutoCompounder(compounder) => {
-                for (i, farm) in compounder.farms.iter().enumerate() {
-                    println!("{} - {}", farm_id_str, farm.id);
-                    if farm_id == farm.id {
-                        compounder.farms.remove(i);
-                        break;
-                    }
-                }
-            }
-            VersionedStrategy::StableAutoCompounder(compounder) => {
-                for (i, farm) in compounder.farms.iter().enumerate() {
-                    println!("{} - {}", farm_id_str, farm.id);
-                    if farm_id == farm.id {
-                        compounder.farms.remove(i);
-                        break;
-                    }
-                }
-            }
-            VersionedStrategy::JumboAutoCompounder(compounder) => {
-                for (i, farm) in compounder.farms.iter().enumerate() {
-                    println!("{} - {}", farm_id_str, farm.id);
-                    if farm_id == farm.id {
-                        compounder.farms.remove(i);
-                        break;
-                    }
-                }
-            }
-            _ => unimplemented!(),
-        }
+
+        let mut strat = self.get_strat(&seed_id);
+        assert!(
+            !strat.strategy().farm_cycle_in_progress(&farm_id),
+            "ERR: {} is in the middle of a harvest",
+            farm_id_str
+        );
+
+        let removed = strat.strategy_mut().remove_farm(&farm_id);
+
+        require!(removed, format!("ERR: {} does not exist", farm_id_str));
+
+        self.save_strat(&seed_id, &strat);
+
+        Event::StrategyDeleted(&[StrategyDeletedData {
+            seed_id: &seed_id,
+            farm_id: Some(&farm_id),
+        }])
+        .emit();
     }
 
+    /// Deletes a strategy, live or archived, once every fft_share of it was withdrawn.
+    /// Strategies still holding user funds should be retired with `archive_strategy` instead.
     pub fn delete_strategy_by_strat_name(&mut self, strat_name: String) {
-        self.is_owner_or_guardians();
+        assert!(self.is_owner_or_guardians(), "ERR: not allowed");
+
+        if self.data().strategies.get(&strat_name).is_none()
+            && !self.is_strategy_archived(strat_name.clone())
+        {
+            return;
+        }
+
+        self.assert_no_user_funds(&strat_name);
+        assert!(
+            !self.get_strat(&strat_name).strategy().cycle_in_progress(),
+            "ERR: {} is in the middle of a harvest",
+            strat_name
+        );
+
         self.data_mut().strategies.remove(&strat_name);
+        self.data_mut().archived_strategies.remove(&strat_name);
+
+        if let Some(fft_share) = self.data_mut().fft_share_by_seed_id.remove(&strat_name) {
+            self.data_mut().seed_id_by_fft_share.remove(&fft_share);
+        }
+        self.data_mut().seed_id_amount.remove(&strat_name);
+        self.internal_remove_share_price_history(&strat_name);
+
+        Event::StrategyDeleted(&[StrategyDeletedData {
+            seed_id: &strat_name,
+            farm_id: None,
+        }])
+        .emit();
     }
 
     pub fn pembrock_create_strategy(
@@ -419,7 +473,7 @@ impl Contract {
         // pembrock@usdt
         let strat_name: String = format!("pembrock@{}", token_address);
 
-        return if self.data().strategies.contains_key(&strat_name) {
+        return if self.data().strategies.get(&strat_name).is_some() {
             format!("VersionedStrategy for {} already exist", token_address)
         } else {
             let uxu_share_id = self.new_fft_share(strat_name.clone());
@@ -442,9 +496,8 @@ impl Contract {
             if let Some(share_id) = uxu_share_id {
                 log!("Registering {} to {}", share_id, &strat_name);
                 //Registering id for the specific seed
-                data_mut
-                    .fft_share_by_seed_id
-                    .insert(strat_name.clone(), share_id.clone());
+                data_mut.fft_share_by_seed_id.insert(&strat_name, &share_id);
+                data_mut.seed_id_by_fft_share.insert(&share_id, &strat_name);
 
                 //Registering id in the users balance map
                 let temp = LookupMap::new(StorageKey::Strategy {
@@ -461,9 +514,9 @@ impl Contract {
                     .insert(&share_id, &0_u128);
             }
 
-            data_mut
-                .strategies
-                .insert(strat_name.clone(), strat.clone());
+            data_mut.strategies.insert(&strat_name, &strat);
+
+            self.emit_strategy_created(&strat_name);
 
             // let farm_info: PembStratFarmInfo = PembStratFarmInfo {
             //     state: PembAutoCompounderState::Running,
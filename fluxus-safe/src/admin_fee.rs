@@ -3,14 +3,19 @@ use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::{env, AccountId};
 use std::collections::HashMap;
 
-const MAX_STRAT_CREATOR_FEE: u128 = 20;
+use crate::U256;
+
+/// Fees are expressed in basis points, 10_000 is 100%
+pub const FEE_DENOMINATOR: u128 = 10_000;
+
+const MAX_STRAT_CREATOR_FEE: u128 = 2_000;
 
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, PartialEq, Clone)]
 #[serde(crate = "near_sdk::serde")]
 pub struct AccountFee {
     /// address id
     pub account_id: AccountId,
-    /// fee percentage
+    /// fee in basis points
     pub fee_percentage: u128,
     /// current amount earned, stored to be used if tx fails
     pub current_amount: u128,
@@ -31,19 +36,24 @@ impl AccountFee {
     }
 }
 
-const MAX_CONTRIBUTOR_FEE: u128 = 20;
-const MAX_PROTOCOL_FEE: u128 = 20;
+const MAX_CONTRIBUTOR_FEE: u128 = 2_000;
+const MAX_PROTOCOL_FEE: u128 = 2_000;
+
+/// Returns fee_bps of amount, rounded down
+pub fn apply_fee(amount: u128, fee_bps: u128) -> u128 {
+    (U256::from(amount) * U256::from(fee_bps) / U256::from(FEE_DENOMINATOR)).as_u128()
+}
 
-/// Maintain information about fees.
+/// Maintain information about fees, in basis points.
 /// Maps receiver address to percentage
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, PartialEq, Clone)]
 #[serde(crate = "near_sdk::serde")]
 pub struct AdminFees {
-    /// Protocol Total fees of the running strategy
+    /// Protocol Total fees of the running strategy, taken from the rewards
     pub strategy_fee: u128,
-    /// Fees earned by the creator of the running strategy
+    /// Fees earned by the creator of the running strategy, taken from the strategy fees
     pub strat_creator: AccountFee,
-    /// Fee percentage earned by sentries
+    /// Fees earned by sentries, taken from the strategy fees
     pub sentries_fee: u128,
     /// Fees earned by users that interact with the harvest method - TODO: is this really needed seems a bit of storage waste
     pub sentries: HashMap<AccountId, u128>,
@@ -63,4 +73,80 @@ impl AdminFees {
             sentries: HashMap::new(),
         }
     }
+
+    /// Splits reward_amount into (remaining, treasury, sentry, strat_creator), which add up to reward_amount.
+    /// Every fee is rounded down, the dust of the strategy fee is left to the users
+    /// and the dust of the sentry and creator fees goes to the treasury.
+    pub fn compute_fees(&self, reward_amount: u128) -> (u128, u128, u128, u128) {
+        let all_fees_amount = apply_fee(reward_amount, self.strategy_fee);
+
+        let sentry_amount = apply_fee(all_fees_amount, self.sentries_fee);
+        let strat_creator_amount = apply_fee(all_fees_amount, self.strat_creator.fee_percentage);
+        let treasury_amount = all_fees_amount - sentry_amount - strat_creator_amount;
+
+        let remaining_amount = reward_amount - all_fees_amount;
+
+        (
+            remaining_amount,
+            treasury_amount,
+            sentry_amount,
+            strat_creator_amount,
+        )
+    }
+
+    /// Splits reward_amount like `compute_fees`, for strategies charging a performance fee.
+    /// Sentries keep their cut and the rest of the reward is compounded,
+    /// the treasury and the creator are paid in fft_shares instead.
+    pub fn compute_performance_mode_fees(&self, reward_amount: u128) -> (u128, u128, u128, u128) {
+        let (_, _, sentry_amount, _) = self.compute_fees(reward_amount);
+
+        (reward_amount - sentry_amount, 0, sentry_amount, 0)
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+
+    pub fn to_account_id(value: &str) -> AccountId {
+        value.parse().unwrap()
+    }
+
+    fn admin_fees(strategy_fee: u128, sentries_fee: u128, creator_fee: u128) -> AdminFees {
+        AdminFees::new(
+            AccountFee::new(to_account_id("creator.near"), creator_fee),
+            sentries_fee,
+            strategy_fee,
+        )
+    }
+
+    #[test]
+    fn test_compute_fees() {
+        // 10% of the rewards, 10% of it to sentries and 5% to the creator
+        let fees = admin_fees(1_000, 1_000, 500);
+        assert_eq!(
+            fees.compute_fees(1_000_000),
+            (900_000, 85_000, 10_000, 5_000)
+        );
+
+        // fees below 1%
+        let fees = admin_fees(50, 1_000, 1_000);
+        assert_eq!(fees.compute_fees(1_000_000), (995_000, 4_000, 500, 500));
+    }
+
+    #[test]
+    fn test_compute_fees_adds_up_to_reward() {
+        let fees = admin_fees(1_234, 1_111, 777);
+
+        for reward_amount in [0, 1, 7, 999, 10_001, 123_456_789, u128::MAX / 10_000] {
+            let (remaining, treasury, sentry, creator) = fees.compute_fees(reward_amount);
+            assert_eq!(remaining + treasury + sentry + creator, reward_amount);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_FEE_NOT_VALID")]
+    fn test_creator_fee_too_high() {
+        AccountFee::new(to_account_id("creator.near"), 2_001);
+    }
 }
@@ -94,7 +94,18 @@ pub trait RefExchangeAutoCompound {
         account_id: AccountId,
         amount: Balance,
         fft_shares: Balance,
-    );
+        withdrawn_from_farm: Balance,
+    ) -> PromiseOrValue<()>;
+    fn callback_post_unlock_seed(
+        &self,
+        #[callback_result] unlock_result: Result<bool, PromiseError>,
+        token_id: String,
+        seed_id: String,
+        receiver_id: AccountId,
+        withdraw_amount: u128,
+        user_fft_shares: u128,
+        withdrawn_from_farm: u128,
+    ) -> Promise;
     fn callback_get_deposits(&self) -> Promise;
     fn callback_post_add_liquidity(
         &mut self,
@@ -112,7 +123,7 @@ pub trait RefExchangeAutoCompound {
         seed_id: String,
         account_id: AccountId,
         shares: u128,
-    );
+    ) -> U128;
 
     fn stake_and_liquidity_auto(
         &mut self,
@@ -244,7 +255,18 @@ pub trait RefExchangeStableAutoCompound {
         account_id: AccountId,
         amount: Balance,
         fft_shares: Balance,
-    );
+        withdrawn_from_farm: Balance,
+    ) -> PromiseOrValue<()>;
+    fn stable_callback_post_unlock_seed(
+        &self,
+        #[callback_result] unlock_result: Result<bool, PromiseError>,
+        token_id: String,
+        seed_id: String,
+        receiver_id: AccountId,
+        withdraw_amount: u128,
+        user_fft_shares: u128,
+        withdrawn_from_farm: u128,
+    ) -> Promise;
     fn stable_callback_get_deposits(&self) -> Promise;
     fn stable_callback_post_add_stable_liquidity(
         &mut self,
@@ -262,7 +284,7 @@ pub trait RefExchangeStableAutoCompound {
         seed_id: String,
         account_id: AccountId,
         shares: u128,
-    );
+    ) -> U128;
 
     fn stable_stake_and_liquidity_auto(
         &mut self,
@@ -328,7 +350,17 @@ pub trait JumboCallbacks {
         account_id: AccountId,
         amount: Balance,
         fft_shares: Balance,
-    );
+        withdrawn_from_farm: Balance,
+    ) -> PromiseOrValue<()>;
+    fn callback_jumbo_post_withdraw_seed(
+        &self,
+        token_id: String,
+        seed_id: String,
+        receiver_id: AccountId,
+        withdraw_amount: u128,
+        user_fft_shares: u128,
+        withdrawn_from_farm: u128,
+    ) -> Promise;
     fn callback_jumbo_get_token1_return(
         &self,
         #[callback_result] min_amount_out: Result<U128, PromiseError>,
@@ -357,7 +389,7 @@ pub trait JumboCallbacks {
         seed_id: String,
         account_id: AccountId,
         shares: u128,
-    );
+    ) -> U128;
     // fn swap_to_auto(
     //     &mut self,
     //     farm_id_str: String,
@@ -463,6 +495,7 @@ pub trait JumboCallbacks {
         &mut self,
         #[callback_result] stake_result: Result<U128, PromiseError>,
         farm_id_str: String,
+        pending: U128,
     );
 }
 
@@ -474,6 +507,14 @@ pub trait PembrockAutoCompound {
         seed_id: String,
         account_id: AccountId,
         shares: u128,
+    ) -> U128;
+    fn callback_pembrock_post_withdraw(
+        &mut self,
+        #[callback_result] withdraw_result: Result<U128, PromiseError>,
+        strat_name: String,
+        receiver_id: AccountId,
+        amount: u128,
+        fft_shares: u128,
     ) -> Promise;
     fn callback_pembrock_rewards(&mut self, strat_name: String) -> PromiseOrValue<u128>;
     fn callback_pembrock_swap(
@@ -487,7 +528,7 @@ pub trait PembrockAutoCompound {
         #[callback_result] swap_result: Result<U128, PromiseError>,
         strat_name: String,
         // pembrock_reward_id: String
-    ) -> Promise;
+    ) -> PromiseOrValue<U128>;
     fn callback_pembrock_post_lend(
         &mut self,
         #[callback_result] post_lend_result: Result<U128, PromiseError>,
@@ -518,3 +559,74 @@ pub trait PembrockAutoCompound {
         amount_earned: u128,
     );
 }
+
+#[ext_contract(callback_share_token)]
+pub trait ShareTokenCallbacks {
+    fn callback_post_share_token_deploy(
+        &mut self,
+        #[callback_result] deploy_result: Result<(), PromiseError>,
+        fft_share: String,
+        share_token_id: AccountId,
+    );
+    fn callback_post_share_token_balance(
+        &mut self,
+        #[callback_result] balance_result: Result<U128, PromiseError>,
+        fft_share: String,
+        account_id: AccountId,
+    );
+}
+
+#[ext_contract(callback_share_price)]
+pub trait SharePriceCallbacks {
+    fn callback_post_harvest_stake(
+        &mut self,
+        #[callback_result] stake_result: Result<U128, PromiseError>,
+        seed_id: String,
+        pending: U128,
+    ) -> U128;
+}
+
+#[ext_contract(callback_tvl_cap)]
+pub trait TvlCapCallbacks {
+    fn callback_post_capped_stake(
+        &mut self,
+        #[callback_result] stake_result: Result<U128, PromiseError>,
+        over_cap: U128,
+        accepted: U128,
+    ) -> U128;
+}
+
+#[ext_contract(callback_emergency_exit)]
+pub trait EmergencyExitCallbacks {
+    fn callback_post_emergency_exit(&mut self, seed_id: String, amount: U128) -> bool;
+}
+
+#[ext_contract(callback_claimable_shares)]
+pub trait ClaimableSharesCallbacks {
+    fn callback_post_unstake_restake(
+        &mut self,
+        #[callback_result] restake_result: Result<U128, PromiseError>,
+        seed_id: String,
+        account_id: AccountId,
+        amount: U128,
+        fft_shares: U128,
+        restaked: U128,
+    );
+    fn callback_post_claim_shares(
+        &mut self,
+        #[callback_result] transfer_result: Result<(), PromiseError>,
+        seed_id: String,
+        account_id: AccountId,
+        amount: U128,
+    ) -> bool;
+}
+
+#[ext_contract(callback_pending_deposit)]
+pub trait PendingDepositCallbacks {
+    fn callback_post_pending_restake(
+        &mut self,
+        #[callback_result] restake_result: Result<U128, PromiseError>,
+        seed_id: String,
+        refund: U128,
+    ) -> U128;
+}
@@ -0,0 +1,288 @@
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    /// Returns the shares of seed_id that account_id can claim with `claim_shares`
+    pub fn get_claimable_shares(&self, seed_id: String, account_id: AccountId) -> U128 {
+        U128(
+            self.data()
+                .claimable_shares_by_account
+                .get(&(seed_id, account_id))
+                .unwrap_or_default(),
+        )
+    }
+
+    /// Sends the claimable shares of seed_id to the caller.
+    /// They are claimable again if the transfer fails.
+    #[payable]
+    pub fn claim_shares(&mut self, seed_id: String) -> Promise {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+
+        let amount = self
+            .get_claimable_shares(seed_id.clone(), account_id.clone())
+            .0;
+        assert!(
+            amount > 0,
+            "ERR: {} has no shares of {} to claim",
+            account_id,
+            seed_id
+        );
+
+        self.internal_remove_claimable_shares(&seed_id, &account_id, amount);
+
+        self.get_strat(&seed_id)
+            .strategy()
+            .transfer_held(account_id.clone(), amount)
+            .then(callback_claimable_shares::callback_post_claim_shares(
+                seed_id,
+                account_id,
+                U128(amount),
+                env::current_account_id(),
+                0,
+                Gas(10_000_000_000_000),
+            ))
+    }
+
+    /// Returns false and gives the shares back to account_id if the transfer failed
+    #[private]
+    pub fn callback_post_claim_shares(
+        &mut self,
+        #[callback_result] transfer_result: Result<(), PromiseError>,
+        seed_id: String,
+        account_id: AccountId,
+        amount: U128,
+    ) -> bool {
+        if transfer_result.is_err() {
+            log!(
+                "ERR: failed to transfer {} claimable shares of {} to {}",
+                amount.0,
+                seed_id,
+                account_id
+            );
+            self.internal_add_claimable_shares(&seed_id, &account_id, amount.0);
+            return false;
+        }
+
+        true
+    }
+
+    /// Keeps the position of account_id once the shares withdrawn from the farm for its failed
+    /// unstake are staked back. The shares the farm did not take back stay on the exchange:
+    /// they are withdrawn from the position and account_id can claim them.
+    #[private]
+    pub fn callback_post_unstake_restake(
+        &mut self,
+        #[callback_result] restake_result: Result<U128, PromiseError>,
+        seed_id: String,
+        account_id: AccountId,
+        amount: U128,
+        fft_shares: U128,
+        restaked: U128,
+    ) {
+        let used = match restake_result {
+            Ok(used) => std::cmp::min(used.0, restaked.0),
+            Err(_) => 0,
+        };
+
+        let unstaked = restaked.0 - used;
+        if unstaked == 0 {
+            log!("Staked back {} shares into {}", used, seed_id);
+            return;
+        }
+
+        log!(
+            "ERR: failed to stake back {} shares into {}, {} can claim them",
+            unstaked,
+            seed_id,
+            account_id
+        );
+
+        // the fft_shares of the unstaked part, rounded up in favor of the strategy
+        let burnt_fft_shares = std::cmp::min(
+            ((U256::from(fft_shares.0) * U256::from(unstaked) + U256::from(amount.0 - 1))
+                / U256::from(amount.0))
+            .as_u128(),
+            fft_shares.0,
+        );
+
+        self.internal_record_unstake(&seed_id, &account_id, unstaked, burnt_fft_shares);
+        self.internal_add_claimable_shares(&seed_id, &account_id, unstaked);
+    }
+}
+
+impl Contract {
+    /// Returns the shares of seed_id held on the exchange for their accounts to claim
+    pub(crate) fn get_total_claimable_shares(&self, seed_id: &str) -> u128 {
+        self.data()
+            .claimable_shares_by_seed_id
+            .get(&seed_id.to_string())
+            .unwrap_or_default()
+    }
+
+    fn internal_add_claimable_shares(
+        &mut self,
+        seed_id: &str,
+        account_id: &AccountId,
+        amount: u128,
+    ) {
+        let claimable = self
+            .get_claimable_shares(seed_id.to_string(), account_id.clone())
+            .0;
+        let total = self.get_total_claimable_shares(seed_id);
+
+        let data = self.data_mut();
+        data.claimable_shares_by_account.insert(
+            &(seed_id.to_string(), account_id.clone()),
+            &(claimable + amount),
+        );
+        data.claimable_shares_by_seed_id
+            .insert(&seed_id.to_string(), &(total + amount));
+    }
+
+    fn internal_remove_claimable_shares(
+        &mut self,
+        seed_id: &str,
+        account_id: &AccountId,
+        amount: u128,
+    ) {
+        let claimable = self
+            .get_claimable_shares(seed_id.to_string(), account_id.clone())
+            .0;
+        let total = self.get_total_claimable_shares(seed_id);
+
+        let data = self.data_mut();
+        if claimable == amount {
+            data.claimable_shares_by_account
+                .remove(&(seed_id.to_string(), account_id.clone()));
+        } else {
+            data.claimable_shares_by_account.insert(
+                &(seed_id.to_string(), account_id.clone()),
+                &(claimable - amount),
+            );
+        }
+        data.claimable_shares_by_seed_id
+            .insert(&seed_id.to_string(), &(total - amount));
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use near_sdk::testing_env;
+
+    /// Running ref strategy, the 100 fft_shares of user.near are worth 100 shares of SEED_ID
+    fn create_contract() -> Contract {
+        create_contract_with_farm(10, 100, 100)
+    }
+
+    fn balance(contract: &Contract) -> u128 {
+        contract.users_fft_share_amount("fft_share_1".to_string(), to_account_id("user.near"))
+    }
+
+    fn claimable(contract: &Contract) -> u128 {
+        contract
+            .get_claimable_shares(SEED_ID.to_string(), to_account_id("user.near"))
+            .0
+    }
+
+    /// user.near failed to receive 100 shares, 60 of them were withdrawn from the farm
+    fn restake(contract: &mut Contract, restake_result: Result<U128, PromiseError>) {
+        testing_env!(get_context("auto_compounder.near").build());
+        assert!(matches!(
+            contract.internal_resolve_unstake(
+                false,
+                SEED_ID.to_string(),
+                to_account_id("user.near"),
+                100,
+                100,
+                60,
+            ),
+            PromiseOrValue::Promise(_)
+        ));
+
+        contract.callback_post_unstake_restake(
+            restake_result,
+            SEED_ID.to_string(),
+            to_account_id("user.near"),
+            U128(100),
+            U128(100),
+            U128(60),
+        );
+    }
+
+    #[test]
+    fn test_restake_after_failed_transfer() {
+        let mut contract = create_contract();
+        restake(&mut contract, Ok(U128(60)));
+
+        assert_eq!(balance(&contract), 100);
+        assert_eq!(contract.seed_total_amount(&SEED_ID.to_string()), 100);
+        assert_eq!(claimable(&contract), 0);
+    }
+
+    #[test]
+    fn test_failed_restake_is_claimable() {
+        let mut contract = create_contract();
+        restake(&mut contract, Err(PromiseError::Failed));
+
+        // the shares withdrawn from the farm left the strategy, the rest of the position is kept
+        assert_eq!(balance(&contract), 40);
+        assert_eq!(contract.seed_total_amount(&SEED_ID.to_string()), 40);
+        assert_eq!(claimable(&contract), 60);
+        assert_eq!(contract.get_total_claimable_shares(SEED_ID), 60);
+    }
+
+    #[test]
+    fn test_partial_restake_is_claimable() {
+        let mut contract = create_contract();
+        restake(&mut contract, Ok(U128(45)));
+
+        assert_eq!(balance(&contract), 85);
+        assert_eq!(contract.seed_total_amount(&SEED_ID.to_string()), 85);
+        assert_eq!(claimable(&contract), 15);
+    }
+
+    #[test]
+    fn test_claim_shares() {
+        let mut contract = create_contract();
+        restake(&mut contract, Err(PromiseError::Failed));
+
+        testing_env!(get_context("user.near").build());
+        contract.claim_shares(SEED_ID.to_string());
+        assert_eq!(claimable(&contract), 0);
+        assert_eq!(contract.get_total_claimable_shares(SEED_ID), 0);
+
+        // the transfer failed, the shares can be claimed again
+        testing_env!(get_context("auto_compounder.near").build());
+        assert!(!contract.callback_post_claim_shares(
+            Err(PromiseError::Failed),
+            SEED_ID.to_string(),
+            to_account_id("user.near"),
+            U128(60),
+        ));
+        assert_eq!(claimable(&contract), 60);
+        assert_eq!(contract.get_total_claimable_shares(SEED_ID), 60);
+
+        testing_env!(get_context("user.near").build());
+        contract.claim_shares(SEED_ID.to_string());
+        testing_env!(get_context("auto_compounder.near").build());
+        assert!(contract.callback_post_claim_shares(
+            Ok(()),
+            SEED_ID.to_string(),
+            to_account_id("user.near"),
+            U128(60),
+        ));
+        assert_eq!(claimable(&contract), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR: user.near has no shares of exchange.near@1 to claim")]
+    fn test_claim_without_claimable_shares() {
+        let mut contract = create_contract();
+
+        testing_env!(get_context("user.near").build());
+        contract.claim_shares(SEED_ID.to_string());
+    }
+}
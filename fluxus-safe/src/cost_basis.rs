@@ -0,0 +1,318 @@
+use crate::*;
+use near_sdk::json_types::I128;
+
+/// Amounts of seed_id put in and taken out of a strategy by an account
+#[derive(BorshSerialize, BorshDeserialize, Default, Clone, Debug, PartialEq)]
+pub struct CostBasis {
+    /// Shares of seed_id deposited, cumulative
+    pub deposited: u128,
+
+    /// Shares of seed_id withdrawn, cumulative
+    pub withdrawn: u128,
+
+    /// Shares of seed_id paid for the fft_shares still held
+    pub principal: u128,
+
+    /// Withdrawn shares minus the principal of the fft_shares burnt to withdraw them
+    pub realized: i128,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct UserProfit {
+    pub deposited: U128,
+    pub withdrawn: U128,
+    pub principal: U128,
+
+    /// Shares of seed_id owned by the fft_shares held
+    pub current_value: U128,
+
+    pub realized_profit: I128,
+
+    /// current_value minus principal
+    pub unrealized_profit: I128,
+
+    /// realized_profit plus unrealized_profit
+    pub earned: I128,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns what account_id put in seed_id and what it earned from it, in shares of seed_id.
+    /// Positions held before the tracking started count their value at that time as principal.
+    pub fn get_user_profit(&self, seed_id: String, account_id: AccountId) -> UserProfit {
+        let fft_share_id = self.fft_token_seed_id(seed_id.clone());
+        let cost_basis = self.internal_get_cost_basis(&fft_share_id, &account_id);
+
+        let fft_shares = self.users_fft_share_amount(fft_share_id, account_id);
+        let current_value = self.seed_amount_for_fft_shares(&seed_id, fft_shares);
+        let unrealized = current_value as i128 - cost_basis.principal as i128;
+
+        UserProfit {
+            deposited: U128(cost_basis.deposited),
+            withdrawn: U128(cost_basis.withdrawn),
+            principal: U128(cost_basis.principal),
+            current_value: U128(current_value),
+            realized_profit: I128(cost_basis.realized),
+            unrealized_profit: I128(unrealized),
+            earned: I128(cost_basis.realized + unrealized),
+        }
+    }
+}
+
+impl Contract {
+    /// Records a deposit of amount shares, must be called before the fft_shares are minted
+    pub(crate) fn internal_record_deposit(
+        &mut self,
+        fft_share: &str,
+        account_id: &AccountId,
+        amount: u128,
+    ) {
+        let mut cost_basis = self.internal_get_cost_basis(fft_share, account_id);
+
+        cost_basis.deposited += amount;
+        cost_basis.principal += amount;
+
+        self.internal_save_cost_basis(fft_share, account_id, &cost_basis);
+    }
+
+    /// Records a withdrawal of amount shares, paid with fft_shares.
+    /// Must be called before the fft_shares are burnt.
+    pub(crate) fn internal_record_withdraw(
+        &mut self,
+        fft_share: &str,
+        account_id: &AccountId,
+        amount: u128,
+        fft_shares: u128,
+    ) {
+        let mut cost_basis = self.internal_get_cost_basis(fft_share, account_id);
+        let cost = self.internal_principal_of(fft_share, account_id, &cost_basis, fft_shares);
+
+        cost_basis.withdrawn += amount;
+        cost_basis.principal -= cost;
+        cost_basis.realized += amount as i128 - cost as i128;
+
+        self.internal_save_cost_basis(fft_share, account_id, &cost_basis);
+    }
+
+    /// Moves the principal of fft_shares from sender_id to receiver_id.
+    /// Must be called before the fft_shares are transferred.
+    pub(crate) fn internal_transfer_cost_basis(
+        &mut self,
+        fft_share: &str,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        fft_shares: u128,
+    ) {
+        let mut sender_cost_basis = self.internal_get_cost_basis(fft_share, sender_id);
+        let mut receiver_cost_basis = self.internal_get_cost_basis(fft_share, receiver_id);
+        let cost = self.internal_principal_of(fft_share, sender_id, &sender_cost_basis, fft_shares);
+
+        sender_cost_basis.principal -= cost;
+        receiver_cost_basis.principal += cost;
+
+        self.internal_save_cost_basis(fft_share, sender_id, &sender_cost_basis);
+        self.internal_save_cost_basis(fft_share, receiver_id, &receiver_cost_basis);
+    }
+
+    /// Returns the cost basis of account_id.
+    /// Positions held before the tracking started take their current value as principal.
+    fn internal_get_cost_basis(&self, fft_share: &str, account_id: &AccountId) -> CostBasis {
+        if let Some(cost_basis) = self
+            .data()
+            .cost_basis_by_fft_share
+            .get(&(fft_share.to_string(), account_id.clone()))
+        {
+            return cost_basis;
+        }
+
+        let balance = self.users_fft_share_amount(fft_share.to_string(), account_id.clone());
+        if balance == 0 {
+            return CostBasis::default();
+        }
+
+        let principal = self
+            .seed_id_by_fft_share(fft_share)
+            .map(|seed_id| self.seed_amount_for_fft_shares(&seed_id, balance))
+            .unwrap_or_default();
+
+        CostBasis {
+            principal,
+            ..CostBasis::default()
+        }
+    }
+
+    fn internal_save_cost_basis(
+        &mut self,
+        fft_share: &str,
+        account_id: &AccountId,
+        cost_basis: &CostBasis,
+    ) {
+        self.data_mut()
+            .cost_basis_by_fft_share
+            .insert(&(fft_share.to_string(), account_id.clone()), cost_basis);
+    }
+
+    /// Returns the part of the principal of account_id paid for fft_shares of its balance
+    fn internal_principal_of(
+        &self,
+        fft_share: &str,
+        account_id: &AccountId,
+        cost_basis: &CostBasis,
+        fft_shares: u128,
+    ) -> u128 {
+        let balance = self.users_fft_share_amount(fft_share.to_string(), account_id.clone());
+        if balance == 0 {
+            return 0;
+        }
+
+        (U256::from(cost_basis.principal) * U256::from(std::cmp::min(fft_shares, balance))
+            / U256::from(balance))
+        .as_u128()
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::testing_env;
+
+    const SEED_ID: &str = "exchange.near@1";
+
+    fn get_context(predecessor_id: &str) -> VMContextBuilder {
+        let mut builder = VMContextBuilder::new();
+        builder
+            .current_account_id(to_account_id("auto_compounder.near"))
+            .predecessor_account_id(to_account_id(predecessor_id))
+            .attached_deposit(1);
+        builder
+    }
+
+    pub fn to_account_id(value: &str) -> AccountId {
+        value.parse().unwrap()
+    }
+
+    fn create_contract() -> Contract {
+        testing_env!(get_context("auto_compounder.near").build());
+        let mut contract = Contract::new(
+            to_account_id("auto_compounder.near"),
+            to_account_id("treasure.near"),
+        );
+
+        contract.register_seed("fft_share_1".to_string());
+        contract
+            .data_mut()
+            .fft_share_by_seed_id
+            .insert(&SEED_ID.to_string(), &"fft_share_1".to_string());
+        contract
+            .data_mut()
+            .seed_id_by_fft_share
+            .insert(&"fft_share_1".to_string(), &SEED_ID.to_string());
+
+        contract
+    }
+
+    /// The strategy compounds shares, increasing the price of the fft_shares
+    fn harvest(contract: &mut Contract, shares: u128) {
+        let total_seed = contract.seed_total_amount(&SEED_ID.to_string());
+        contract
+            .data_mut()
+            .seed_id_amount
+            .insert(&SEED_ID.to_string(), &(total_seed + shares));
+    }
+
+    fn profit(contract: &Contract, account_id: &str) -> UserProfit {
+        contract.get_user_profit(SEED_ID.to_string(), to_account_id(account_id))
+    }
+
+    #[test]
+    fn test_deposit_and_withdraw() {
+        let mut contract = create_contract();
+        let user = to_account_id("user.near");
+
+        contract.internal_mint_staked_shares(SEED_ID, &user, 100);
+        harvest(&mut contract, 100);
+
+        assert_eq!(
+            profit(&contract, "user.near"),
+            UserProfit {
+                deposited: U128(100),
+                withdrawn: U128(0),
+                principal: U128(100),
+                current_value: U128(200),
+                realized_profit: I128(0),
+                unrealized_profit: I128(100),
+                earned: I128(100),
+            }
+        );
+
+        // withdraws half of the position
+        contract.internal_resolve_unstake(true, SEED_ID.to_string(), user, 100, 50, 0);
+
+        assert_eq!(
+            profit(&contract, "user.near"),
+            UserProfit {
+                deposited: U128(100),
+                withdrawn: U128(100),
+                principal: U128(50),
+                current_value: U128(100),
+                realized_profit: I128(50),
+                unrealized_profit: I128(50),
+                earned: I128(100),
+            }
+        );
+    }
+
+    #[test]
+    fn test_failed_unstake_keeps_cost_basis() {
+        let mut contract = create_contract();
+        let user = to_account_id("user.near");
+
+        contract.internal_mint_staked_shares(SEED_ID, &user, 100);
+        contract.internal_resolve_unstake(false, SEED_ID.to_string(), user, 100, 100, 0);
+
+        assert_eq!(profit(&contract, "user.near").principal, U128(100));
+        assert_eq!(profit(&contract, "user.near").withdrawn, U128(0));
+    }
+
+    #[test]
+    fn test_transfer_moves_principal() {
+        let mut contract = create_contract();
+
+        contract.internal_mint_staked_shares(SEED_ID, &to_account_id("user.near"), 100);
+        harvest(&mut contract, 100);
+
+        testing_env!(get_context("user.near").build());
+        contract.mft_transfer(
+            "fft_share_1".to_string(),
+            to_account_id("receiver.near"),
+            U128(40),
+            None,
+        );
+
+        let sender = profit(&contract, "user.near");
+        assert_eq!(sender.principal, U128(60));
+        assert_eq!(sender.current_value, U128(120));
+        assert_eq!(sender.earned, I128(60));
+
+        let receiver = profit(&contract, "receiver.near");
+        assert_eq!(receiver.deposited, U128(0));
+        assert_eq!(receiver.principal, U128(40));
+        assert_eq!(receiver.current_value, U128(80));
+        assert_eq!(receiver.earned, I128(40));
+    }
+
+    #[test]
+    fn test_position_held_before_tracking() {
+        let mut contract = create_contract();
+
+        contract.mft_mint("fft_share_1".to_string(), 100, to_account_id("user.near"));
+        harvest(&mut contract, 150);
+
+        let legacy = profit(&contract, "user.near");
+        assert_eq!(legacy.deposited, U128(0));
+        assert_eq!(legacy.principal, U128(150));
+        assert_eq!(legacy.earned, I128(0));
+    }
+}
@@ -0,0 +1,306 @@
+use crate::*;
+
+/// Strategy withdrawn from its farm by `emergency_exit`.
+/// Its shares are held by the contract, on the exchange or in the token for pembrock,
+/// and the fft_shares are redeemed pro-rata from them without calling the farm.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct EmergencyExit {
+    /// Block timestamp, in ms, of the withdrawal from the farm
+    pub timestamp: u64,
+
+    /// Amount of seed_id withdrawn from the farm
+    pub withdrawn_amount: U128,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Withdraws all the shares of seed_id from its farm and clears every farm of the strategy, for good.
+    /// Deposits and harvests are rejected from now on, unstakes are paid from the shares held by the contract.
+    /// The farms are cleared and the exit is recorded once the shares are withdrawn,
+    /// it can be called again if the withdrawal failed.
+    pub fn emergency_exit(&mut self, seed_id: String) -> PromiseOrValue<bool> {
+        assert!(self.is_owner_or_guardians(), "ERR: not allowed");
+        self.assert_not_emergency_exited(&seed_id);
+
+        let strat = self.get_strat(&seed_id);
+        assert!(
+            !strat.strategy().cycle_in_progress(),
+            "ERR: {} is in the middle of a harvest",
+            seed_id
+        );
+
+        // charged up to the exit, the held shares do not accrue any fee
+        self.internal_accrue_management_fee(&seed_id);
+
+        // the pending deposits were never staked, they are already held on the exchange
+        let staked = self
+            .seed_total_amount(&seed_id)
+            .saturating_sub(self.get_pending_deposits(seed_id.clone()).0);
+
+        if staked == 0 {
+            self.internal_clear_farms(&seed_id);
+            self.internal_record_emergency_exit(&seed_id, 0);
+            return PromiseOrValue::Value(true);
+        }
+
+        log!("Withdrawing {} shares of {} from the farm", staked, seed_id);
+
+        PromiseOrValue::Promise(strat.strategy().emergency_withdraw(staked).then(
+            callback_emergency_exit::callback_post_emergency_exit(
+                seed_id,
+                U128(staked),
+                env::current_account_id(),
+                0,
+                Gas(10_000_000_000_000),
+            ),
+        ))
+    }
+
+    /// Clears the farms and records the exit of seed_id once amount was withdrawn from the farm.
+    /// Returns false if the withdrawal failed, the shares are still staked and the farms are left as they were.
+    #[private]
+    pub fn callback_post_emergency_exit(&mut self, seed_id: String, amount: U128) -> bool {
+        if !matches!(env::promise_result(0), PromiseResult::Successful(_)) {
+            log!(
+                "ERR: failed to withdraw {} shares of {} from the farm",
+                amount.0,
+                seed_id
+            );
+            return false;
+        }
+
+        self.internal_clear_farms(&seed_id);
+        self.internal_record_emergency_exit(&seed_id, amount.0);
+
+        true
+    }
+
+    /// Returns the emergency exit of seed_id, None if the strategy was not exited
+    pub fn get_emergency_exit(&self, seed_id: String) -> Option<EmergencyExit> {
+        self.data().emergency_exit_by_seed_id.get(&seed_id)
+    }
+}
+
+impl Contract {
+    /// Returns true once all the shares of seed_id are held by the contract
+    pub(crate) fn is_emergency_exited(&self, seed_id: &str) -> bool {
+        self.data()
+            .emergency_exit_by_seed_id
+            .contains_key(&seed_id.to_string())
+    }
+
+    pub(crate) fn assert_not_emergency_exited(&self, seed_id: &str) {
+        assert!(
+            !self.is_emergency_exited(seed_id),
+            "ERR: {} was exited in an emergency",
+            seed_id
+        );
+    }
+
+    /// Moves every farm of seed_id to Cleared, archived strategies are already cleared and read-only
+    fn internal_clear_farms(&mut self, seed_id: &str) {
+        if self.is_strategy_archived(seed_id.to_string()) {
+            return;
+        }
+
+        let mut strat = self.get_strat(seed_id);
+        strat.strategy_mut().clear_farms();
+        self.save_strat(seed_id, &strat);
+    }
+
+    fn internal_record_emergency_exit(&mut self, seed_id: &str, withdrawn_amount: u128) {
+        // every share is held on the exchange now, none of them is pending anymore
+        self.internal_clear_pending_deposits(seed_id);
+
+        let emergency_exit = EmergencyExit {
+            timestamp: env::block_timestamp_ms(),
+            withdrawn_amount: U128(withdrawn_amount),
+        };
+
+        self.data_mut()
+            .emergency_exit_by_seed_id
+            .insert(&seed_id.to_string(), &emergency_exit);
+
+        Event::EmergencyExit(&[EmergencyExitData {
+            seed_id,
+            withdrawn_amount: U128(withdrawn_amount),
+        }])
+        .emit();
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use near_sdk::{testing_env, RuntimeFeesConfig, VMConfig};
+    use std::collections::HashMap;
+
+    /// Running ref strategy with 1% withdrawal fee.
+    /// The 100 fft_shares of user.near are worth 200 shares of SEED_ID
+    fn create_contract() -> Contract {
+        let mut contract = create_contract_with_farm(10, 200, 100);
+
+        let mut strat = contract.get_strat(SEED_ID);
+        strat.get_compounder_mut().farms.push(running_farm("1"));
+        contract.save_strat(SEED_ID, &strat);
+
+        contract.set_entry_exit_fees(SEED_ID.to_string(), 0, 100, 0, 0);
+
+        contract
+    }
+
+    #[test]
+    fn test_emergency_exit() {
+        let mut contract = create_contract();
+        contract.extend_guardians(vec![to_account_id("guardian.near")]);
+
+        testing_env!(get_context("guardian.near").build());
+        assert!(matches!(
+            contract.emergency_exit(SEED_ID.to_string()),
+            PromiseOrValue::Promise(_)
+        ));
+
+        // the farms are cleared and the exit is recorded once the shares are withdrawn
+        assert!(contract.get_strat(SEED_ID).strategy().is_running());
+        assert_eq!(contract.get_emergency_exit(SEED_ID.to_string()), None);
+
+        testing_env!(
+            get_context("auto_compounder.near").build(),
+            VMConfig::test(),
+            RuntimeFeesConfig::test(),
+            HashMap::default(),
+            vec![PromiseResult::Successful(vec![])],
+        );
+        assert!(contract.callback_post_emergency_exit(SEED_ID.to_string(), U128(200)));
+
+        let strat = contract.get_strat(SEED_ID);
+        assert!(!strat.strategy().is_running());
+        assert_eq!(strat.strategy().farm_state("0"), "Cleared");
+        assert_eq!(strat.strategy().farm_state("1"), "Cleared");
+        assert_eq!(
+            contract.get_emergency_exit(SEED_ID.to_string()),
+            Some(EmergencyExit {
+                timestamp: 0,
+                withdrawn_amount: U128(200),
+            })
+        );
+    }
+
+    #[test]
+    fn test_failed_emergency_exit() {
+        let mut contract = create_contract();
+        contract.emergency_exit(SEED_ID.to_string());
+
+        testing_env!(
+            get_context("auto_compounder.near").build(),
+            VMConfig::test(),
+            RuntimeFeesConfig::test(),
+            HashMap::default(),
+            vec![PromiseResult::Failed],
+        );
+        assert!(!contract.callback_post_emergency_exit(SEED_ID.to_string(), U128(200)));
+
+        // the shares are still staked, the strategy keeps running
+        let strat = contract.get_strat(SEED_ID);
+        assert!(strat.strategy().is_running());
+        assert_eq!(strat.strategy().farm_state("0"), "Running");
+        assert_eq!(contract.get_emergency_exit(SEED_ID.to_string()), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR: exchange.near@1 is in the middle of a harvest")]
+    fn test_emergency_exit_during_harvest() {
+        let mut contract = create_contract();
+        let mut strat = contract.get_strat(SEED_ID);
+        strat.get_compounder_mut().farms[0].cycle_stage = AutoCompounderCycle::Withdrawal;
+        contract.save_strat(SEED_ID, &strat);
+
+        contract.emergency_exit(SEED_ID.to_string());
+    }
+
+    #[test]
+    fn test_unstake_after_emergency_exit() {
+        let mut contract = create_contract();
+        contract.internal_record_emergency_exit(SEED_ID, 200);
+
+        // no withdrawal fee is charged on the way out
+        assert_eq!(
+            contract.get_withdrawal_fee(SEED_ID.to_string(), to_account_id("user.near")),
+            0
+        );
+
+        testing_env!(get_context("user.near").build());
+        contract.unstake(SEED_ID.to_string(), None);
+
+        testing_env!(get_context("auto_compounder.near").build());
+        contract.callback_withdraw_shares(
+            Ok(()),
+            SEED_ID.to_string(),
+            to_account_id("user.near"),
+            200,
+            100,
+            0,
+        );
+        assert_eq!(
+            contract.users_fft_share_amount("fft_share_1".to_string(), to_account_id("user.near")),
+            0
+        );
+        assert_eq!(contract.seed_total_amount(&SEED_ID.to_string()), 0);
+    }
+
+    #[test]
+    fn test_emergency_exit_of_pending_deposits() {
+        let mut contract = create_contract();
+        contract
+            .data_mut()
+            .pending_deposits_by_seed_id
+            .insert(&SEED_ID.to_string(), &200u128);
+
+        // nothing is staked into the farm, the exit is recorded right away
+        assert!(matches!(
+            contract.emergency_exit(SEED_ID.to_string()),
+            PromiseOrValue::Value(true)
+        ));
+        assert_eq!(
+            contract.get_strat(SEED_ID).strategy().farm_state("0"),
+            "Cleared"
+        );
+        assert_eq!(
+            contract.get_emergency_exit(SEED_ID.to_string()),
+            Some(EmergencyExit {
+                timestamp: 0,
+                withdrawn_amount: U128(0),
+            })
+        );
+        assert_eq!(contract.get_pending_deposits(SEED_ID.to_string()), U128(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR: exchange.near@1 was exited in an emergency")]
+    fn test_state_update_after_emergency_exit() {
+        let mut contract = create_contract();
+        contract.internal_record_emergency_exit(SEED_ID, 200);
+
+        contract.update_compounder_state(format!("{}#0", SEED_ID), AutoCompounderState::Running);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR: not allowed")]
+    fn test_update_compounder_state_not_allowed() {
+        let mut contract = create_contract();
+
+        testing_env!(get_context("user.near").build());
+        contract.update_compounder_state(format!("{}#0", SEED_ID), AutoCompounderState::Ended);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR: not allowed")]
+    fn test_emergency_exit_not_allowed() {
+        let mut contract = create_contract();
+
+        testing_env!(get_context("user.near").build());
+        contract.emergency_exit(SEED_ID.to_string());
+    }
+}
@@ -0,0 +1,374 @@
+use crate::*;
+
+/// Max deposit fee, 1%
+const MAX_DEPOSIT_FEE: u128 = 100;
+
+/// Max withdrawal fee, 1%
+const MAX_WITHDRAWAL_FEE: u128 = 100;
+
+/// Max early exit fee, 5%
+const MAX_EARLY_EXIT_FEE: u128 = 500;
+
+/// Fees kept by a strategy on deposits and withdrawals, in basis points.
+/// They are not paid to anyone: the shares of seed_id stay in the strategy,
+/// raising the price per share for the accounts that stay.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct EntryExitFees {
+    /// Kept from every deposit, fewer fft_shares are minted for it
+    pub deposit_fee: u128,
+
+    /// Kept from every withdrawal, fewer shares are sent for the fft_shares burnt
+    pub withdrawal_fee: u128,
+
+    /// Added to the withdrawal fee of positions held less than early_exit_period_ms.
+    /// It decays linearly to 0 over the period.
+    pub early_exit_fee: u128,
+
+    pub early_exit_period_ms: u64,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Sets the deposit and withdrawal fees of seed_id, in basis points. All of them at 0 disables them.
+    pub fn set_entry_exit_fees(
+        &mut self,
+        seed_id: String,
+        deposit_fee: u128,
+        withdrawal_fee: u128,
+        early_exit_fee: u128,
+        early_exit_period_ms: u64,
+    ) {
+        self.is_owner();
+        assert!(
+            deposit_fee <= MAX_DEPOSIT_FEE
+                && withdrawal_fee <= MAX_WITHDRAWAL_FEE
+                && early_exit_fee <= MAX_EARLY_EXIT_FEE,
+            "ERR: fees too high"
+        );
+        // panics if the strategy does not exist
+        self.get_strat(&seed_id);
+
+        if deposit_fee == 0
+            && withdrawal_fee == 0
+            && (early_exit_fee == 0 || early_exit_period_ms == 0)
+        {
+            self.data_mut().entry_exit_fees_by_seed_id.remove(&seed_id);
+            return;
+        }
+
+        let fees = EntryExitFees {
+            deposit_fee,
+            withdrawal_fee,
+            early_exit_fee,
+            early_exit_period_ms,
+        };
+
+        self.data_mut()
+            .entry_exit_fees_by_seed_id
+            .insert(&seed_id, &fees);
+    }
+
+    /// Returns the deposit and withdrawal fees of seed_id, None if it does not charge them
+    pub fn get_entry_exit_fees(&self, seed_id: String) -> Option<EntryExitFees> {
+        self.data().entry_exit_fees_by_seed_id.get(&seed_id)
+    }
+
+    /// Returns the fee, in basis points, charged if account_id withdraws from seed_id now
+    pub fn get_withdrawal_fee(&self, seed_id: String, account_id: AccountId) -> u128 {
+        // users are not charged for leaving a strategy exited in an emergency
+        if self.is_emergency_exited(&seed_id) {
+            return 0;
+        }
+
+        let fees = match self.data().entry_exit_fees_by_seed_id.get(&seed_id) {
+            Some(fees) => fees,
+            None => return 0,
+        };
+
+        let fft_share_id = self.fft_token_seed_id(seed_id);
+        let held_ms = env::block_timestamp_ms()
+            .saturating_sub(self.internal_get_deposit_timestamp(&fft_share_id, &account_id));
+
+        if held_ms >= fees.early_exit_period_ms {
+            return fees.withdrawal_fee;
+        }
+
+        let remaining_ms = (fees.early_exit_period_ms - held_ms) as u128;
+        fees.withdrawal_fee + fees.early_exit_fee * remaining_ms / fees.early_exit_period_ms as u128
+    }
+}
+
+impl Contract {
+    /// Returns the part of a deposit of shares into seed_id kept by the strategy
+    pub(crate) fn internal_deposit_fee(&self, seed_id: &str, shares: u128) -> u128 {
+        self.data()
+            .entry_exit_fees_by_seed_id
+            .get(&seed_id.to_string())
+            .map_or(0, |fees| apply_fee(shares, fees.deposit_fee))
+    }
+
+    /// Returns the part of a withdrawal of amount shares from seed_id kept by the strategy
+    pub(crate) fn internal_withdrawal_fee(
+        &self,
+        seed_id: &str,
+        account_id: &AccountId,
+        amount: u128,
+    ) -> u128 {
+        apply_fee(
+            amount,
+            self.get_withdrawal_fee(seed_id.to_string(), account_id.clone()),
+        )
+    }
+
+    /// Returns the amount account_id must withdraw from seed_id to receive exactly assets after the withdrawal fee
+    pub(crate) fn internal_gross_withdrawal(
+        &self,
+        seed_id: &str,
+        account_id: &AccountId,
+        assets: u128,
+    ) -> u128 {
+        let fee = self.get_withdrawal_fee(seed_id.to_string(), account_id.clone());
+        if fee == 0 {
+            return assets;
+        }
+
+        // the fee is rounded down, the amount received grows by at most 1 with each share
+        let mut amount = (U256::from(assets) * U256::from(FEE_DENOMINATOR)
+            / U256::from(FEE_DENOMINATOR - fee))
+        .as_u128();
+        while amount - apply_fee(amount, fee) < assets {
+            amount += 1;
+        }
+
+        amount
+    }
+
+    /// Moves the deposit timestamp of account_id towards now, weighted by the fft_shares minted.
+    /// Must be called before the fft_shares are minted.
+    pub(crate) fn internal_record_deposit_timestamp(
+        &mut self,
+        fft_share: &str,
+        account_id: &AccountId,
+        fft_shares: u128,
+    ) {
+        self.internal_merge_deposit_timestamp(
+            fft_share,
+            account_id,
+            env::block_timestamp_ms(),
+            fft_shares,
+        );
+    }
+
+    /// Moves the deposit timestamp of receiver_id towards the one of sender_id, weighted by the fft_shares received.
+    /// Must be called before the fft_shares are transferred.
+    pub(crate) fn internal_transfer_deposit_timestamp(
+        &mut self,
+        fft_share: &str,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        fft_shares: u128,
+    ) {
+        let sender_timestamp = self.internal_get_deposit_timestamp(fft_share, sender_id);
+        self.internal_merge_deposit_timestamp(fft_share, receiver_id, sender_timestamp, fft_shares);
+    }
+
+    /// Returns when the fft_shares of account_id were deposited.
+    /// Positions held before the tracking started count as deposited at 0.
+    fn internal_get_deposit_timestamp(&self, fft_share: &str, account_id: &AccountId) -> u64 {
+        self.data()
+            .deposit_timestamp_by_fft_share
+            .get(&(fft_share.to_string(), account_id.clone()))
+            .unwrap_or_default()
+    }
+
+    fn internal_merge_deposit_timestamp(
+        &mut self,
+        fft_share: &str,
+        account_id: &AccountId,
+        timestamp: u64,
+        fft_shares: u128,
+    ) {
+        let balance = self.users_fft_share_amount(fft_share.to_string(), account_id.clone());
+        if balance + fft_shares == 0 {
+            return;
+        }
+
+        let held_timestamp = self.internal_get_deposit_timestamp(fft_share, account_id);
+        let merged_timestamp = ((U256::from(held_timestamp) * U256::from(balance)
+            + U256::from(timestamp) * U256::from(fft_shares))
+            / U256::from(balance + fft_shares))
+        .as_u64();
+
+        self.data_mut().deposit_timestamp_by_fft_share.insert(
+            &(fft_share.to_string(), account_id.clone()),
+            &merged_timestamp,
+        );
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::testing_env;
+
+    const START_MS: u64 = 1_000_000_000_000;
+    const EARLY_EXIT_PERIOD_MS: u64 = 10 * 24 * 60 * 60 * 1000;
+
+    fn get_context(predecessor_id: &str, timestamp_ms: u64) -> VMContextBuilder {
+        let mut builder = VMContextBuilder::new();
+        builder
+            .current_account_id(to_account_id("auto_compounder.near"))
+            .predecessor_account_id(to_account_id(predecessor_id))
+            .block_timestamp(timestamp_ms * 1_000_000)
+            .attached_deposit(1);
+        builder
+    }
+
+    /// user.near and other.near hold 500_000 fft_shares each, worth 1_000_000 shares of SEED_ID.
+    /// 0.5% withdrawal fee, plus 2% on positions held less than 10 days.
+    fn create_contract() -> Contract {
+        testing_env!(get_context("auto_compounder.near", START_MS).build());
+        let mut contract = create_safe();
+
+        register_strategy(
+            &mut contract,
+            SEED_ID,
+            "fft_share_1",
+            VersionedStrategy::AutoCompounder(create_compounder(SEED_ID, 1_000)),
+            1_000_000,
+        );
+        contract.mft_mint(
+            "fft_share_1".to_string(),
+            500_000,
+            to_account_id("user.near"),
+        );
+        contract.mft_mint(
+            "fft_share_1".to_string(),
+            500_000,
+            to_account_id("other.near"),
+        );
+
+        contract.set_entry_exit_fees(SEED_ID.to_string(), 0, 50, 200, EARLY_EXIT_PERIOD_MS);
+
+        contract
+    }
+
+    fn balance(contract: &Contract, account_id: &str) -> u128 {
+        contract.users_fft_share_amount("fft_share_1".to_string(), to_account_id(account_id))
+    }
+
+    fn withdrawal_fee(contract: &Contract, account_id: &str, timestamp_ms: u64) -> u128 {
+        testing_env!(get_context("auto_compounder.near", timestamp_ms).build());
+        contract.get_withdrawal_fee(SEED_ID.to_string(), to_account_id(account_id))
+    }
+
+    #[test]
+    fn test_deposit_fee() {
+        let mut contract = create_contract();
+        contract.set_entry_exit_fees(SEED_ID.to_string(), 100, 0, 0, 0);
+
+        contract.internal_mint_staked_shares(SEED_ID, &to_account_id("new.near"), 1_000);
+
+        // 1% of the deposit stays with the previous holders
+        assert_eq!(balance(&contract, "new.near"), 990);
+        assert_eq!(
+            contract.seed_amount_for_fft_shares(SEED_ID, 500_000),
+            500_004
+        );
+    }
+
+    #[test]
+    fn test_withdrawal_fee() {
+        let mut contract = create_contract();
+        let user = to_account_id("user.near");
+
+        let fee = contract.internal_withdrawal_fee(SEED_ID, &user, 100_000);
+        assert_eq!(fee, 500);
+
+        let fft_shares = contract.fft_shares_for_withdraw(SEED_ID, 100_000);
+        contract.internal_resolve_unstake(
+            true,
+            SEED_ID.to_string(),
+            user,
+            100_000 - fee,
+            fft_shares,
+            0,
+        );
+
+        // the fee stays in the strategy, owned by the remaining fft_shares
+        assert_eq!(balance(&contract, "user.near"), 400_000);
+        assert_eq!(contract.seed_total_amount(&SEED_ID.to_string()), 900_500);
+        assert_eq!(
+            contract.seed_amount_for_fft_shares(SEED_ID, 500_000),
+            500_277
+        );
+    }
+
+    #[test]
+    fn test_early_exit_fee_decays() {
+        let mut contract = create_contract();
+        contract.internal_mint_staked_shares(SEED_ID, &to_account_id("new.near"), 1_000);
+
+        assert_eq!(withdrawal_fee(&contract, "new.near", START_MS), 250);
+        assert_eq!(
+            withdrawal_fee(&contract, "new.near", START_MS + EARLY_EXIT_PERIOD_MS / 2),
+            150
+        );
+        assert_eq!(
+            withdrawal_fee(&contract, "new.near", START_MS + EARLY_EXIT_PERIOD_MS),
+            50
+        );
+
+        // positions held before the tracking started only pay the withdrawal fee
+        assert_eq!(withdrawal_fee(&contract, "user.near", START_MS), 50);
+    }
+
+    #[test]
+    fn test_transfer_averages_deposit_timestamp() {
+        let mut contract = create_contract();
+        let half_period = START_MS + EARLY_EXIT_PERIOD_MS / 2;
+
+        contract.internal_mint_staked_shares(SEED_ID, &to_account_id("early.near"), 1_000);
+        testing_env!(get_context("auto_compounder.near", half_period).build());
+        contract.internal_mint_staked_shares(SEED_ID, &to_account_id("late.near"), 1_000);
+
+        testing_env!(get_context("early.near", half_period).build());
+        contract.mft_transfer(
+            "fft_share_1".to_string(),
+            to_account_id("late.near"),
+            U128(1_000),
+            None,
+        );
+
+        // held for a quarter of the period on average
+        assert_eq!(withdrawal_fee(&contract, "late.near", half_period), 200);
+    }
+
+    #[test]
+    fn test_disable_entry_exit_fees() {
+        let mut contract = create_contract();
+        contract.set_entry_exit_fees(SEED_ID.to_string(), 0, 0, 0, 0);
+
+        assert_eq!(contract.get_entry_exit_fees(SEED_ID.to_string()), None);
+        assert_eq!(withdrawal_fee(&contract, "user.near", START_MS), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR: fees too high")]
+    fn test_entry_exit_fees_too_high() {
+        let mut contract = create_contract();
+        contract.set_entry_exit_fees(SEED_ID.to_string(), 0, MAX_WITHDRAWAL_FEE + 1, 0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_ALLOWED")]
+    fn test_set_entry_exit_fees_not_allowed() {
+        let mut contract = create_contract();
+
+        testing_env!(get_context("user.near", START_MS).build());
+        contract.set_entry_exit_fees(SEED_ID.to_string(), 0, 50, 0, 0);
+    }
+}
@@ -1,3 +1,4 @@
-
 pub const ERR21_TOKEN_NOT_REG: &str = "E21: token not registered";
 pub const ERR33_TRANSFER_TO_SELF: &str = "E33: transfer to self";
+pub const ERR60_STATE_NOT_MIGRATED: &str = "E60: state not migrated";
+pub const ERR61_WRONG_STRATEGY_KIND: &str = "E61: strategy is not of the requested kind";
@@ -0,0 +1,562 @@
+use crate::*;
+
+/// Name of the NEP-297 standard implemented by the events below
+pub const EVENT_STANDARD: &str = "fluxus";
+
+/// Must be bumped whenever the data of an existing event changes
+pub const EVENT_STANDARD_VERSION: &str = "1.0.0";
+
+/// Name and version of the multi-token standard followed by the fft_shares
+pub const NEP245_STANDARD: &str = "nep245";
+pub const NEP245_STANDARD_VERSION: &str = "1.0.0";
+
+/// Events are logged as `EVENT_JSON:{"standard":"fluxus","version":"1.0.0","event":"...","data":[...]}`
+#[derive(Serialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(tag = "event", content = "data")]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Event<'a> {
+    Deposit(&'a [DepositData<'a>]),
+    Withdraw(&'a [WithdrawData<'a>]),
+    HarvestStep(&'a [HarvestStepData<'a>]),
+    HarvestFees(&'a [HarvestFeesData<'a>]),
+    StrategyCreated(&'a [StrategyCreatedData<'a>]),
+    StrategyDeleted(&'a [StrategyDeletedData<'a>]),
+    FarmStateUpdated(&'a [FarmStateUpdatedData<'a>]),
+    ContractStateUpdated(&'a [ContractStateUpdatedData]),
+    FftApprove(&'a [FftApproveData<'a>]),
+    FftRevoke(&'a [FftRevokeData<'a>]),
+    PerformanceFee(&'a [PerformanceFeeData<'a>]),
+    ManagementFee(&'a [ManagementFeeData<'a>]),
+    EmergencyExit(&'a [EmergencyExitData<'a>]),
+    StrategyArchived(&'a [StrategyArchivedData<'a>]),
+}
+
+/// Shares staked into a strategy, and the fft_shares minted for them
+#[derive(Serialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct DepositData<'a> {
+    pub account_id: &'a AccountId,
+    pub seed_id: &'a str,
+    pub amount: U128,
+    pub fft_shares: U128,
+}
+
+/// Shares transferred back to the user, and the fft_shares burnt for them
+#[derive(Serialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct WithdrawData<'a> {
+    pub account_id: &'a AccountId,
+    pub seed_id: &'a str,
+    pub amount: U128,
+    pub fft_shares: U128,
+}
+
+/// Transition of the auto-compound cycle, farm_id is omitted for strategies without farms
+#[derive(Serialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct HarvestStepData<'a> {
+    pub seed_id: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub farm_id: Option<&'a str>,
+    pub from: String,
+    pub to: String,
+}
+
+/// Split of the claimed reward, as returned by `compute_fees`
+#[derive(Serialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct HarvestFeesData<'a> {
+    pub seed_id: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub farm_id: Option<&'a str>,
+    pub reward_token: &'a AccountId,
+    pub reward_amount: U128,
+    pub treasury_amount: U128,
+    pub strat_creator_amount: U128,
+    pub sentry_amount: U128,
+    pub remaining_amount: U128,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct StrategyCreatedData<'a> {
+    pub seed_id: &'a str,
+    pub kind: String,
+    pub fft_share: &'a str,
+}
+
+/// farm_id is set when a single farm was removed from the strategy
+#[derive(Serialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct StrategyDeletedData<'a> {
+    pub seed_id: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub farm_id: Option<&'a str>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct FarmStateUpdatedData<'a> {
+    pub seed_id: &'a str,
+    pub farm_id: &'a str,
+    pub state: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct ContractStateUpdatedData {
+    pub state: String,
+}
+
+/// amount is the new allowance of spender_id, it replaces the previous one
+#[derive(Serialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct FftApproveData<'a> {
+    pub owner_id: &'a AccountId,
+    pub spender_id: &'a AccountId,
+    pub fft_share: &'a str,
+    pub amount: U128,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct FftRevokeData<'a> {
+    pub owner_id: &'a AccountId,
+    pub spender_id: &'a AccountId,
+    pub fft_share: &'a str,
+}
+
+/// Performance fee paid in fft_shares, fee_amount is in shares of seed_id
+#[derive(Serialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct PerformanceFeeData<'a> {
+    pub seed_id: &'a str,
+    pub price_per_share: U128,
+    pub high_water_mark: U128,
+    pub fee_amount: U128,
+    pub treasury_fft_shares: U128,
+    pub strat_creator_fft_shares: U128,
+}
+
+/// Management fee paid in fft_shares, fee_amount is in shares of seed_id
+#[derive(Serialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct ManagementFeeData<'a> {
+    pub seed_id: &'a str,
+    pub fee_amount: U128,
+    pub treasury_fft_shares: U128,
+}
+
+/// Strategy withdrawn from its farm, withdrawn_amount is in shares of seed_id
+#[derive(Serialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct EmergencyExitData<'a> {
+    pub seed_id: &'a str,
+    pub withdrawn_amount: U128,
+}
+
+/// Strategy retired to the archive, its users can still withdraw
+#[derive(Serialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct StrategyArchivedData<'a> {
+    pub seed_id: &'a str,
+}
+
+/// fft_shares ledger changes, as defined by NEP-245
+#[derive(Serialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(tag = "event", content = "data")]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum MtEvent<'a> {
+    MtMint(&'a [MtMintData<'a>]),
+    MtBurn(&'a [MtBurnData<'a>]),
+    MtTransfer(&'a [MtTransferData<'a>]),
+}
+
+#[derive(Serialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct MtMintData<'a> {
+    pub owner_id: &'a AccountId,
+    pub token_ids: &'a [&'a str],
+    pub amounts: &'a [U128],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<&'a str>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct MtBurnData<'a> {
+    pub owner_id: &'a AccountId,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub authorized_id: Option<&'a AccountId>,
+    pub token_ids: &'a [&'a str],
+    pub amounts: &'a [U128],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<&'a str>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct MtTransferData<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub authorized_id: Option<&'a AccountId>,
+    pub old_owner_id: &'a AccountId,
+    pub new_owner_id: &'a AccountId,
+    pub token_ids: &'a [&'a str],
+    pub amounts: &'a [U128],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct EventLog<'a, T: Serialize> {
+    standard: &'static str,
+    version: &'static str,
+    #[serde(flatten)]
+    event: &'a T,
+}
+
+fn emit_event<T: Serialize>(standard: &'static str, version: &'static str, event: &T) {
+    let log = EventLog {
+        standard,
+        version,
+        event,
+    };
+
+    env::log_str(&format!(
+        "EVENT_JSON:{}",
+        near_sdk::serde_json::to_string(&log).expect("ERR_SERIALIZE_EVENT")
+    ));
+}
+
+impl Event<'_> {
+    pub(crate) fn emit(&self) {
+        emit_event(EVENT_STANDARD, EVENT_STANDARD_VERSION, self);
+    }
+}
+
+impl MtEvent<'_> {
+    pub(crate) fn emit(&self) {
+        emit_event(NEP245_STANDARD, NEP245_STANDARD_VERSION, self);
+    }
+}
+
+/// Emits `harvest_step` for a cycle transition of seed_id
+pub(crate) fn emit_harvest_step(seed_id: &str, farm_id: Option<&str>, from: String, to: String) {
+    Event::HarvestStep(&[HarvestStepData {
+        seed_id,
+        farm_id,
+        from,
+        to,
+    }])
+    .emit();
+}
+
+/// Emits `farm_state_updated` once the state of farm_id was changed
+pub(crate) fn emit_farm_state_updated(seed_id: &str, farm_id: &str, state: String) {
+    Event::FarmStateUpdated(&[FarmStateUpdatedData {
+        seed_id,
+        farm_id,
+        state,
+    }])
+    .emit();
+}
+
+/// Emits `harvest_fees` with the split returned by `compute_fees`
+pub(crate) fn emit_harvest_fees(
+    seed_id: &str,
+    farm_id: Option<&str>,
+    reward_token: &AccountId,
+    reward_amount: u128,
+    fees: (u128, u128, u128, u128),
+) {
+    let (remaining_amount, treasury_amount, sentry_amount, strat_creator_amount) = fees;
+
+    Event::HarvestFees(&[HarvestFeesData {
+        seed_id,
+        farm_id,
+        reward_token,
+        reward_amount: U128(reward_amount),
+        treasury_amount: U128(treasury_amount),
+        strat_creator_amount: U128(strat_creator_amount),
+        sentry_amount: U128(sentry_amount),
+        remaining_amount: U128(remaining_amount),
+    }])
+    .emit();
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::{get_logs, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    fn setup() {
+        testing_env!(VMContextBuilder::new().build());
+    }
+
+    fn to_account_id(value: &str) -> AccountId {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn test_deposit_and_withdraw() {
+        setup();
+        let account_id = to_account_id("user.near");
+
+        Event::Deposit(&[DepositData {
+            account_id: &account_id,
+            seed_id: "exchange.near@0",
+            amount: U128(100),
+            fft_shares: U128(90),
+        }])
+        .emit();
+        Event::Withdraw(&[WithdrawData {
+            account_id: &account_id,
+            seed_id: "exchange.near@0",
+            amount: U128(50),
+            fft_shares: U128(45),
+        }])
+        .emit();
+
+        assert_eq!(
+            get_logs(),
+            vec![
+                r#"EVENT_JSON:{"standard":"fluxus","version":"1.0.0","event":"deposit","data":[{"account_id":"user.near","seed_id":"exchange.near@0","amount":"100","fft_shares":"90"}]}"#,
+                r#"EVENT_JSON:{"standard":"fluxus","version":"1.0.0","event":"withdraw","data":[{"account_id":"user.near","seed_id":"exchange.near@0","amount":"50","fft_shares":"45"}]}"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_mt_mint_burn_transfer() {
+        setup();
+        let user = to_account_id("user.near");
+        let other = to_account_id("other.near");
+
+        MtEvent::MtMint(&[MtMintData {
+            owner_id: &user,
+            token_ids: &["fft_share_1"],
+            amounts: &[U128(10)],
+            memo: None,
+        }])
+        .emit();
+        MtEvent::MtBurn(&[MtBurnData {
+            owner_id: &user,
+            authorized_id: None,
+            token_ids: &["fft_share_1"],
+            amounts: &[U128(3)],
+            memo: None,
+        }])
+        .emit();
+        MtEvent::MtTransfer(&[MtTransferData {
+            authorized_id: None,
+            old_owner_id: &user,
+            new_owner_id: &other,
+            token_ids: &["fft_share_1", "fft_share_2"],
+            amounts: &[U128(2), U128(5)],
+            memo: Some("refund"),
+        }])
+        .emit();
+
+        assert_eq!(
+            get_logs(),
+            vec![
+                r#"EVENT_JSON:{"standard":"nep245","version":"1.0.0","event":"mt_mint","data":[{"owner_id":"user.near","token_ids":["fft_share_1"],"amounts":["10"]}]}"#,
+                r#"EVENT_JSON:{"standard":"nep245","version":"1.0.0","event":"mt_burn","data":[{"owner_id":"user.near","token_ids":["fft_share_1"],"amounts":["3"]}]}"#,
+                r#"EVENT_JSON:{"standard":"nep245","version":"1.0.0","event":"mt_transfer","data":[{"old_owner_id":"user.near","new_owner_id":"other.near","token_ids":["fft_share_1","fft_share_2"],"amounts":["2","5"],"memo":"refund"}]}"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_harvest_step() {
+        setup();
+
+        emit_harvest_step(
+            "exchange.near@0",
+            Some("0"),
+            String::from(&AutoCompounderCycle::ClaimReward),
+            String::from(&AutoCompounderCycle::Withdrawal),
+        );
+        emit_harvest_step(
+            "pembrock@token.near",
+            None,
+            String::from(&PembAutoCompounderCycle::SwapAndLend),
+            String::from(&PembAutoCompounderCycle::ClaimReward),
+        );
+
+        assert_eq!(
+            get_logs(),
+            vec![
+                r#"EVENT_JSON:{"standard":"fluxus","version":"1.0.0","event":"harvest_step","data":[{"seed_id":"exchange.near@0","farm_id":"0","from":"Reward","to":"Withdrawal"}]}"#,
+                r#"EVENT_JSON:{"standard":"fluxus","version":"1.0.0","event":"harvest_step","data":[{"seed_id":"pembrock@token.near","from":"SwapAndLend","to":"Reward"}]}"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_harvest_fees() {
+        setup();
+
+        emit_harvest_fees(
+            "exchange.near@0",
+            Some("0"),
+            &to_account_id("reward.near"),
+            1_000,
+            (900, 70, 20, 10),
+        );
+
+        assert_eq!(
+            get_logs(),
+            vec![
+                r#"EVENT_JSON:{"standard":"fluxus","version":"1.0.0","event":"harvest_fees","data":[{"seed_id":"exchange.near@0","farm_id":"0","reward_token":"reward.near","reward_amount":"1000","treasury_amount":"70","strat_creator_amount":"10","sentry_amount":"20","remaining_amount":"900"}]}"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_strategy_created_and_deleted() {
+        setup();
+
+        Event::StrategyCreated(&[StrategyCreatedData {
+            seed_id: "exchange.near@0",
+            kind: "REF_REGULAR".to_string(),
+            fft_share: "fft_share_1",
+        }])
+        .emit();
+        Event::StrategyDeleted(&[StrategyDeletedData {
+            seed_id: "exchange.near@0",
+            farm_id: Some("0"),
+        }])
+        .emit();
+        Event::StrategyDeleted(&[StrategyDeletedData {
+            seed_id: "exchange.near@0",
+            farm_id: None,
+        }])
+        .emit();
+
+        assert_eq!(
+            get_logs(),
+            vec![
+                r#"EVENT_JSON:{"standard":"fluxus","version":"1.0.0","event":"strategy_created","data":[{"seed_id":"exchange.near@0","kind":"REF_REGULAR","fft_share":"fft_share_1"}]}"#,
+                r#"EVENT_JSON:{"standard":"fluxus","version":"1.0.0","event":"strategy_deleted","data":[{"seed_id":"exchange.near@0","farm_id":"0"}]}"#,
+                r#"EVENT_JSON:{"standard":"fluxus","version":"1.0.0","event":"strategy_deleted","data":[{"seed_id":"exchange.near@0"}]}"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_state_updated() {
+        setup();
+
+        emit_farm_state_updated(
+            "exchange.near@0",
+            "0",
+            String::from(&AutoCompounderState::Ended),
+        );
+        Event::ContractStateUpdated(&[ContractStateUpdatedData {
+            state: RunningState::Paused.to_string(),
+        }])
+        .emit();
+
+        assert_eq!(
+            get_logs(),
+            vec![
+                r#"EVENT_JSON:{"standard":"fluxus","version":"1.0.0","event":"farm_state_updated","data":[{"seed_id":"exchange.near@0","farm_id":"0","state":"Ended"}]}"#,
+                r#"EVENT_JSON:{"standard":"fluxus","version":"1.0.0","event":"contract_state_updated","data":[{"state":"Paused"}]}"#,
+            ]
+        );
+    }
+
+    fn events() -> Vec<String> {
+        get_logs()
+            .into_iter()
+            .filter(|log| log.starts_with("EVENT_JSON:"))
+            .collect()
+    }
+
+    fn farm_info() -> StratFarmInfo {
+        StratFarmInfo {
+            state: AutoCompounderState::Running,
+            cycle_stage: AutoCompounderCycle::Stake,
+            slippage: 80,
+            max_slippage: MAX_SLIPPAGE_ALLOWED,
+            last_reward_amount: 0,
+            last_fee_amount: 0,
+            pool_id_token1_reward: 1,
+            pool_id_token2_reward: 2,
+            reward_token: to_account_id("reward.near"),
+            available_balance: vec![0, 0],
+            id: "0".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_farm_cycle_and_slippage_events() {
+        setup();
+        let mut farm = farm_info();
+
+        farm.next_cycle("exchange.near@1");
+        farm.increase_slippage("exchange.near@1");
+
+        assert_eq!(farm.state, AutoCompounderState::Ended);
+        assert_eq!(
+            events(),
+            vec![
+                r#"EVENT_JSON:{"standard":"fluxus","version":"1.0.0","event":"harvest_step","data":[{"seed_id":"exchange.near@1","farm_id":"0","from":"Stake","to":"Reward"}]}"#,
+                r#"EVENT_JSON:{"standard":"fluxus","version":"1.0.0","event":"farm_state_updated","data":[{"seed_id":"exchange.near@1","farm_id":"0","state":"Ended"}]}"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_strategy_lifecycle_events() {
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(to_account_id("auto_compounder.near"))
+            .predecessor_account_id(to_account_id("owner.near"))
+            .build());
+        let mut contract =
+            Contract::new(to_account_id("owner.near"), to_account_id("treasure.near"));
+
+        contract.create_strategy(
+            String::new(),
+            10,
+            AccountFee::new(to_account_id("creator.near"), 5),
+            1,
+            to_account_id("exchange.near"),
+            to_account_id("farm.near"),
+            to_account_id("token1.near"),
+            to_account_id("token2.near"),
+            1,
+            U128(1_000),
+        );
+        contract.add_farm_to_strategy(
+            "exchange.near@1".to_string(),
+            1,
+            2,
+            to_account_id("reward.near"),
+            "0".to_string(),
+        );
+        contract
+            .update_compounder_state("exchange.near@1#0".to_string(), AutoCompounderState::Ended);
+        contract.delete_strategy_by_farm_id("exchange.near@1#0".to_string());
+        contract.delete_strategy_by_strat_name("exchange.near@1".to_string());
+        // nothing left to delete
+        contract.delete_strategy_by_strat_name("exchange.near@1".to_string());
+        contract.update_contract_state(RunningState::Paused);
+
+        assert_eq!(
+            events(),
+            vec![
+                r#"EVENT_JSON:{"standard":"fluxus","version":"1.0.0","event":"strategy_created","data":[{"seed_id":"exchange.near@1","kind":"REF_REGULAR","fft_share":"fft_share_1"}]}"#,
+                r#"EVENT_JSON:{"standard":"fluxus","version":"1.0.0","event":"farm_state_updated","data":[{"seed_id":"exchange.near@1","farm_id":"0","state":"Ended"}]}"#,
+                r#"EVENT_JSON:{"standard":"fluxus","version":"1.0.0","event":"strategy_deleted","data":[{"seed_id":"exchange.near@1","farm_id":"0"}]}"#,
+                r#"EVENT_JSON:{"standard":"fluxus","version":"1.0.0","event":"strategy_deleted","data":[{"seed_id":"exchange.near@1"}]}"#,
+                r#"EVENT_JSON:{"standard":"fluxus","version":"1.0.0","event":"contract_state_updated","data":[{"state":"Paused"}]}"#,
+            ]
+        );
+    }
+}
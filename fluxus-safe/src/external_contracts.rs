@@ -263,3 +263,12 @@ pub trait JumboExchange {
     fn remove_liquidity(&mut self, pool_id: u64, shares: U128, min_amounts: Vec<U128>);
     fn withdraw(&mut self, token_id: AccountId, amount: U128, unregister: Option<bool>);
 }
+
+// NEP-141 share token deployed for a strategy, see fluxus-share-token
+#[ext_contract(ext_share_token)]
+pub trait ShareToken {
+    fn mint(&mut self, account_id: AccountId, amount: U128);
+    fn burn(&mut self, account_id: AccountId, amount: U128);
+    fn transfer(&mut self, sender_id: AccountId, receiver_id: AccountId, amount: U128);
+    fn ft_balance_of(&self, account_id: AccountId) -> U128;
+}
@@ -1,4 +1,4 @@
-use crate::auto_compounder::AutoCompounder;
+use crate::auto_compounder::{AutoCompounder, AutoCompounderV1};
 use crate::jumbo_auto_compounder::JumboAutoCompounder;
 use crate::pembrock_auto_compounder::PembrockAutoCompounder;
 use crate::*;
@@ -10,28 +10,126 @@ use near_sdk::{
 
 // pub(crate) type StratId = String;
 
+/// Behaviour shared by every kind of strategy.
+/// Callers should go through it instead of matching on each `VersionedStrategy` variant.
+pub trait Strategy {
+    /// Returns Strategy kind.
+    fn kind(&self) -> String;
+
+    /// Min amount accepted by the farm for stake
+    fn seed_min_deposit(&self) -> U128;
+
+    /// Fees struct to be distribute at each round of compound
+    fn admin_fees(&self) -> &AdminFees;
+
+    fn admin_fees_mut(&mut self) -> &mut AdminFees;
+
+    /// Latest harvest timestamp
+    fn harvest_timestamp(&self) -> u64;
+
+    /// Number of farms used to compound
+    fn farms_len(&self) -> u64;
+
+    /// Reward tokens given by each farm
+    fn reward_tokens(&self) -> Vec<AccountId>;
+
+    /// Returns true if at least one farm is running
+    fn is_running(&self) -> bool;
+
+    /// Returns the state of farm_id: Running, Ended or Cleared
+    fn farm_state(&self, farm_id: &str) -> String;
+
+    /// Returns the current stage of the auto-compound cycle for farm_id
+    fn farm_cycle_stage(&self, farm_id: &str) -> String;
+
+    /// Updates the state of farm_id, returning the new state
+    fn set_farm_state(&mut self, farm_id: &str, state: AutoCompounderState) -> String;
+
+    /// Updates the slippage of farm_id, returning the new slippage
+    fn set_farm_slippage(&mut self, farm_id: &str, slippage: u128) -> u128;
+
+    /// Removes farm_id from the strategy, returning false if it was not found
+    fn remove_farm(&mut self, farm_id: &str) -> bool;
+
+    /// Moves every farm to the Cleared state, once the strategy left them
+    fn clear_farms(&mut self);
+
+    /// Returns true if farm_id is past the first step of the auto-compound cycle,
+    /// holding rewards or balances that were not compounded yet
+    fn farm_cycle_in_progress(&self, farm_id: &str) -> bool;
+
+    /// Returns true if any farm is in the middle of the auto-compound cycle
+    fn cycle_in_progress(&self) -> bool;
+
+    /// Stake shares into the farm and mint the fft_share for account_id
+    fn stake(
+        &self,
+        token_id: String,
+        seed_id: String,
+        account_id: &AccountId,
+        shares: u128,
+    ) -> Promise;
+
+    /// Withdraw shares from the farm and send it to receiver_id
+    fn unstake(
+        &self,
+        seed_id: String,
+        receiver_id: AccountId,
+        withdraw_amount: u128,
+        user_fft_shares: u128,
+    ) -> Promise;
+
+    /// Stakes back shares withdrawn from the farm that could not be sent to the user,
+    /// without minting fft_shares
+    fn restake(&self, shares: u128) -> Promise;
+
+    /// Withdraws shares from the farm, they are kept by the contract
+    fn emergency_withdraw(&self, shares: u128) -> Promise;
+
+    /// Sends shares already held by the contract to receiver_id, without calling the farm
+    fn withdraw_held(
+        &self,
+        seed_id: String,
+        receiver_id: AccountId,
+        withdraw_amount: u128,
+        user_fft_shares: u128,
+    ) -> Promise;
+
+    /// Transfers shares held by the contract to receiver_id, without touching any position
+    fn transfer_held(&self, receiver_id: AccountId, amount: u128) -> Promise;
+
+    /// Executes the current step of the auto-compound cycle
+    /// Args:
+    ///   farm_id_str: exchange@pool_id#farm_id, empty for strategies without farms
+    ///   strat_name: used by strategies without farms, such as pembrock@token
+    fn harvest_step(
+        &mut self,
+        farm_id_str: String,
+        strat_name: String,
+        treasure: AccountFee,
+    ) -> PromiseOrValue<u128>;
+}
+
 /// Generic Strategy, providing wrapper around different implementations of strategies.
 /// Allows to add new types of strategies just by adding extra item in the enum
 /// without needing to migrate the storage.
+/// Outdated layouts are kept in the enum and upgraded to the latest one on first access,
+/// new variants must always be appended.
 #[derive(Debug, BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Clone)]
 #[serde(crate = "near_sdk::serde")]
 #[allow(clippy::enum_variant_names)]
 pub enum VersionedStrategy {
-    AutoCompounder(AutoCompounder),
+    AutoCompounderV1(AutoCompounderV1),
     StableAutoCompounder(StableAutoCompounder),
     PembrockAutoCompounder(PembrockAutoCompounder),
     JumboAutoCompounder(JumboAutoCompounder),
+    AutoCompounder(AutoCompounder),
 }
 
 impl VersionedStrategy {
     /// Returns Strategy kind.
     pub fn kind(&self) -> String {
-        match self {
-            VersionedStrategy::AutoCompounder(_) => "REF_REGULAR".to_string(),
-            VersionedStrategy::StableAutoCompounder(_) => "REF_STABLE".to_string(),
-            VersionedStrategy::JumboAutoCompounder(_) => "JUMBO_REGULAR".to_string(),
-            VersionedStrategy::PembrockAutoCompounder(_) => "PEMBROCK".to_string(),
-        }
+        self.strategy().kind()
     }
 
     // TODO: impl
@@ -45,6 +143,9 @@ impl VersionedStrategy {
     #[allow(unreachable_patterns)]
     pub fn upgrade(&self) -> Self {
         match self {
+            VersionedStrategy::AutoCompounderV1(compounder) => {
+                VersionedStrategy::AutoCompounder(compounder.clone().into())
+            }
             VersionedStrategy::AutoCompounder(compounder) => {
                 VersionedStrategy::AutoCompounder(compounder.clone())
             }
@@ -65,6 +166,7 @@ impl VersionedStrategy {
     #[allow(unreachable_patterns)]
     pub fn need_upgrade(&self) -> bool {
         match self {
+            Self::AutoCompounderV1(_) => true,
             Self::AutoCompounder(_) => false,
             Self::StableAutoCompounder(_) => false,
             Self::PembrockAutoCompounder(_) => false,
@@ -85,7 +187,7 @@ impl VersionedStrategy {
     pub fn get_compounder(self) -> AutoCompounder {
         match self {
             VersionedStrategy::AutoCompounder(compounder) => compounder,
-            _ => unimplemented!(),
+            _ => env::panic_str(ERR61_WRONG_STRATEGY_KIND),
         }
     }
 
@@ -93,7 +195,7 @@ impl VersionedStrategy {
     pub fn get_compounder_ref(&self) -> &AutoCompounder {
         match self {
             VersionedStrategy::AutoCompounder(compounder) => compounder,
-            _ => unimplemented!(),
+            _ => env::panic_str(ERR61_WRONG_STRATEGY_KIND),
         }
     }
 
@@ -101,7 +203,7 @@ impl VersionedStrategy {
     pub fn get_compounder_mut(&mut self) -> &mut AutoCompounder {
         match self {
             VersionedStrategy::AutoCompounder(compounder) => compounder,
-            _ => unimplemented!(),
+            _ => env::panic_str(ERR61_WRONG_STRATEGY_KIND),
         }
     }
 
@@ -109,7 +211,7 @@ impl VersionedStrategy {
     pub fn get_stable_compounder(self) -> StableAutoCompounder {
         match self {
             VersionedStrategy::StableAutoCompounder(stable_compounder) => stable_compounder,
-            _ => unimplemented!(),
+            _ => env::panic_str(ERR61_WRONG_STRATEGY_KIND),
         }
     }
 
@@ -117,7 +219,7 @@ impl VersionedStrategy {
     pub fn get_stable_compounder_ref(&self) -> &StableAutoCompounder {
         match self {
             VersionedStrategy::StableAutoCompounder(stable_compounder) => stable_compounder,
-            _ => unimplemented!(),
+            _ => env::panic_str(ERR61_WRONG_STRATEGY_KIND),
         }
     }
 
@@ -125,7 +227,7 @@ impl VersionedStrategy {
     pub fn get_stable_compounder_mut(&mut self) -> &mut StableAutoCompounder {
         match self {
             VersionedStrategy::StableAutoCompounder(stable_compounder) => stable_compounder,
-            _ => unimplemented!(),
+            _ => env::panic_str(ERR61_WRONG_STRATEGY_KIND),
         }
     }
 
@@ -133,26 +235,53 @@ impl VersionedStrategy {
     pub fn get_jumbo(self) -> JumboAutoCompounder {
         match self {
             VersionedStrategy::JumboAutoCompounder(compounder) => compounder,
-            _ => unimplemented!(),
+            _ => env::panic_str(ERR61_WRONG_STRATEGY_KIND),
         }
     }
     #[allow(unreachable_patterns)]
     pub fn get_jumbo_ref(&self) -> &JumboAutoCompounder {
         match self {
             VersionedStrategy::JumboAutoCompounder(compounder) => compounder,
-            _ => unimplemented!(),
+            _ => env::panic_str(ERR61_WRONG_STRATEGY_KIND),
         }
     }
     #[allow(unreachable_patterns)]
     pub fn get_jumbo_mut(&mut self) -> &mut JumboAutoCompounder {
         match self {
             VersionedStrategy::JumboAutoCompounder(compounder) => compounder,
-            _ => unimplemented!(),
+            _ => env::panic_str(ERR61_WRONG_STRATEGY_KIND),
         }
     }
 }
 
 impl VersionedStrategy {
+    /// Returns the implementation behind the enum, used to dispatch generic calls
+    pub fn strategy(&self) -> &dyn Strategy {
+        match self {
+            VersionedStrategy::AutoCompounderV1(compounder) => env::panic_str(&format!(
+                "ERR: strategy {} must be upgraded before use",
+                compounder.seed_id
+            )),
+            VersionedStrategy::AutoCompounder(compounder) => compounder,
+            VersionedStrategy::StableAutoCompounder(stable_compounder) => stable_compounder,
+            VersionedStrategy::PembrockAutoCompounder(pemb_compounder) => pemb_compounder,
+            VersionedStrategy::JumboAutoCompounder(jumbo_compounder) => jumbo_compounder,
+        }
+    }
+
+    pub fn strategy_mut(&mut self) -> &mut dyn Strategy {
+        match self {
+            VersionedStrategy::AutoCompounderV1(compounder) => env::panic_str(&format!(
+                "ERR: strategy {} must be upgraded before use",
+                compounder.seed_id
+            )),
+            VersionedStrategy::AutoCompounder(compounder) => compounder,
+            VersionedStrategy::StableAutoCompounder(stable_compounder) => stable_compounder,
+            VersionedStrategy::PembrockAutoCompounder(pemb_compounder) => pemb_compounder,
+            VersionedStrategy::JumboAutoCompounder(jumbo_compounder) => jumbo_compounder,
+        }
+    }
+
     pub fn stake(
         &self,
         token_id: String,
@@ -160,21 +289,7 @@ impl VersionedStrategy {
         account_id: &AccountId,
         shares: u128,
     ) -> Promise {
-        match self {
-            VersionedStrategy::AutoCompounder(compounder) => {
-                compounder.stake(token_id, seed_id, account_id, shares)
-            }
-            VersionedStrategy::StableAutoCompounder(stable_compounder) => {
-                stable_compounder.stake(token_id, seed_id, account_id, shares)
-            }
-            VersionedStrategy::PembrockAutoCompounder(pemb_compounder) => {
-                // pemb_compounder.stake_on_pembrock(account_id, shares, account_id, shares)
-                unimplemented!()
-            }
-            VersionedStrategy::JumboAutoCompounder(jumbo_compounder) => {
-                jumbo_compounder.stake(token_id, seed_id, account_id, shares)
-            }
-        }
+        self.strategy().stake(token_id, seed_id, account_id, shares)
     }
 
     pub fn unstake(
@@ -189,31 +304,8 @@ impl VersionedStrategy {
             receiver_id,
             withdraw_amount
         );
-        match self {
-            VersionedStrategy::AutoCompounder(compounder) => compounder.unstake(
-                wrap_mft_token_id(&compounder.pool_id.to_string()),
-                seed_id,
-                receiver_id,
-                withdraw_amount,
-                user_fft_shares,
-            ),
-            VersionedStrategy::StableAutoCompounder(stable_compounder) => stable_compounder
-                .unstake(
-                    wrap_mft_token_id(&stable_compounder.pool_id.to_string()),
-                    seed_id,
-                    receiver_id,
-                    withdraw_amount,
-                    user_fft_shares,
-                ),
-            VersionedStrategy::PembrockAutoCompounder(stable_compounder) => unimplemented!(),
-            VersionedStrategy::JumboAutoCompounder(jumbo_compounder) => jumbo_compounder.unstake(
-                wrap_mft_token_id(&jumbo_compounder.pool_id.to_string()),
-                seed_id,
-                receiver_id,
-                withdraw_amount,
-                user_fft_shares,
-            ),
-        }
+        self.strategy()
+            .unstake(seed_id, receiver_id, withdraw_amount, user_fft_shares)
     }
 
     pub fn harvest_proxy(
@@ -222,163 +314,251 @@ impl VersionedStrategy {
         strat_name: String,
         treasure: AccountFee,
     ) -> PromiseOrValue<u128> {
-        let mut farm_id: String = "".to_string();
-        if farm_id_str != *"" {
-            (_, _, farm_id) = get_ids_from_farm(farm_id_str.to_string());
-        }
-        match self {
-            VersionedStrategy::AutoCompounder(compounder) => {
-                let farm_info = compounder.get_farm_info(&farm_id);
-
-                assert_strategy_not_cleared(farm_info.state);
-
-                match farm_info.cycle_stage {
-                    AutoCompounderCycle::ClaimReward => {
-                        PromiseOrValue::Promise(compounder.claim_reward(farm_id_str))
-                    }
-                    AutoCompounderCycle::Withdrawal => PromiseOrValue::Promise(
-                        compounder.withdraw_of_reward(farm_id_str, treasure.current_amount),
-                    ),
-                    AutoCompounderCycle::Swap => PromiseOrValue::Promise(
-                        compounder.autocompounds_swap(farm_id_str, treasure),
-                    ),
-                    AutoCompounderCycle::Stake => PromiseOrValue::Promise(
-                        compounder.autocompounds_liquidity_and_stake(farm_id_str),
-                    ),
-                }
-            }
-            VersionedStrategy::StableAutoCompounder(stable_compounder) => {
-                let farm_info = stable_compounder.get_farm_info(&farm_id);
-
-                assert_strategy_not_cleared(farm_info.state);
-
-                match farm_info.cycle_stage {
-                    AutoCompounderCycle::ClaimReward => {
-                        PromiseOrValue::Promise(stable_compounder.claim_reward(farm_id_str))
-                    }
-                    AutoCompounderCycle::Withdrawal => PromiseOrValue::Promise(
-                        stable_compounder.withdraw_of_reward(farm_id_str, treasure.current_amount),
-                    ),
-                    AutoCompounderCycle::Swap => {
-                        stable_compounder.autocompounds_swap(farm_id_str, treasure)
-                    }
-                    AutoCompounderCycle::Stake => PromiseOrValue::Promise(
-                        stable_compounder.autocompounds_liquidity_and_stake(farm_id_str),
-                    ),
-                }
-            }
-            VersionedStrategy::JumboAutoCompounder(jumbo_compounder) => {
-                let farm_info = jumbo_compounder.get_jumbo_farm_info(&farm_id);
-                match farm_info.cycle_stage {
-                    JumboAutoCompounderCycle::ClaimReward => {
-                        PromiseOrValue::Promise(jumbo_compounder.claim_reward(farm_id_str))
-                    }
-                    JumboAutoCompounderCycle::Withdrawal => PromiseOrValue::Promise(
-                        jumbo_compounder.withdraw_of_reward(farm_id_str, treasure.current_amount),
-                    ),
-                    JumboAutoCompounderCycle::SwapToken1 => PromiseOrValue::Promise(
-                        jumbo_compounder.autocompounds_swap(farm_id_str, treasure),
-                    ),
-                    JumboAutoCompounderCycle::SwapToken2 => PromiseOrValue::Promise(
-                        jumbo_compounder.autocompounds_swap_second_token(farm_id_str),
-                    ),
-                    JumboAutoCompounderCycle::Stake => PromiseOrValue::Promise(
-                        jumbo_compounder.autocompounds_liquidity_and_stake(farm_id_str),
-                    ),
-                }
-            }
-            VersionedStrategy::PembrockAutoCompounder(pemb_compounder) => {
-                match pemb_compounder.cycle_stage {
-                    PembAutoCompounderCycle::ClaimReward => {
-                        PromiseOrValue::Promise(pemb_compounder.claim_reward(strat_name))
-                    }
-                    PembAutoCompounderCycle::SwapAndLend => {
-                        PromiseOrValue::Promise(pemb_compounder.swap_and_lend(strat_name))
-                    }
-                }
-            }
-        }
+        self.strategy_mut()
+            .harvest_step(farm_id_str, strat_name, treasure)
     }
 
     #[allow(unreachable_patterns)]
     pub fn pemb_get(self) -> PembrockAutoCompounder {
         match self {
             VersionedStrategy::PembrockAutoCompounder(compounder) => compounder,
-            _ => unimplemented!(),
+            _ => env::panic_str(ERR61_WRONG_STRATEGY_KIND),
         }
     }
     #[allow(unreachable_patterns)]
     pub fn pemb_get_ref(&self) -> &PembrockAutoCompounder {
         match self {
             VersionedStrategy::PembrockAutoCompounder(compounder) => compounder,
-            _ => unimplemented!(),
+            _ => env::panic_str(ERR61_WRONG_STRATEGY_KIND),
         }
     }
     #[allow(unreachable_patterns)]
     pub fn pemb_get_mut(&mut self) -> &mut PembrockAutoCompounder {
         match self {
             VersionedStrategy::PembrockAutoCompounder(compounder) => compounder,
-            _ => unimplemented!(),
+            _ => env::panic_str(ERR61_WRONG_STRATEGY_KIND),
         }
     }
 }
 
 impl Contract {
+    /// Loads the strategy for seed_id, already upgraded to its latest layout.
+    /// Archived strategies are loaded as well, so their users can still withdraw.
+    /// Changes are only persisted after calling `save_strat`.
     pub fn get_strat(&self, seed_id: &str) -> VersionedStrategy {
         let strat = self
             .data()
             .strategies
-            .get(seed_id)
+            .get(&seed_id.to_string())
+            .or_else(|| self.data().archived_strategies.get(&seed_id.to_string()))
             .expect(ERR21_TOKEN_NOT_REG);
 
         if strat.need_upgrade() {
             strat.upgrade()
         } else {
-            strat.clone()
+            strat
         }
     }
 
-    pub fn get_strat_mut(&mut self, seed_id: &str) -> &mut VersionedStrategy {
-        let strat = self
-            .data_mut()
+    /// Stores the strategy for seed_id, used to write back changes made to a loaded strategy.
+    /// Panics if the strategy is archived, archived strategies are read-only.
+    pub fn save_strat(&mut self, seed_id: &str, strat: &VersionedStrategy) {
+        self.assert_not_archived(seed_id);
+
+        self.data_mut()
             .strategies
-            .get_mut(seed_id)
-            .expect(ERR21_TOKEN_NOT_REG);
+            .insert(&seed_id.to_string(), strat);
+    }
 
-        if strat.need_upgrade() {
-            strat.upgrade();
-            strat
-        } else {
-            strat
+    pub fn pemb_get_strat(&self, seed_id: &str) -> VersionedStrategy {
+        self.get_strat(seed_id)
+    }
+
+    /// Returns the seed_ids stored between from_index and from_index + limit
+    pub(crate) fn get_seed_ids(&self, from_index: Option<u64>, limit: Option<u64>) -> Vec<String> {
+        let keys = self.data().strategies.keys_as_vector();
+
+        let from_index = from_index.unwrap_or(0);
+        let limit = limit.unwrap_or(keys.len());
+
+        (from_index..std::cmp::min(from_index.saturating_add(limit), keys.len()))
+            .map(|index| keys.get(index).unwrap())
+            .collect()
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::testing_env;
+
+    fn get_context() -> VMContextBuilder {
+        let mut builder = VMContextBuilder::new();
+        builder
+            .current_account_id(to_account_id("auto_compounder.near"))
+            .signer_account_id(to_account_id("fluxus.near"))
+            .predecessor_account_id(to_account_id("fluxus.near"));
+        builder
+    }
+
+    pub fn to_account_id(value: &str) -> AccountId {
+        value.parse().unwrap()
+    }
+
+    /// Returns a strategy stored with the layout used before StratFarmInfo.max_slippage
+    fn create_compounder_v1(pool_id: u64) -> VersionedStrategy {
+        let seed_id = format!("exchange.near@{}", pool_id);
+
+        VersionedStrategy::AutoCompounderV1(AutoCompounderV1 {
+            admin_fees: AdminFees::new(AccountFee::new(to_account_id("creator.near"), 5), 1, 10),
+            exchange_contract_id: to_account_id("exchange.near"),
+            farm_contract_id: to_account_id("farm.near"),
+            token1_address: to_account_id("token1.near"),
+            token2_address: to_account_id("token2.near"),
+            pool_id,
+            seed_min_deposit: U128(1_000),
+            seed_id: seed_id.clone(),
+            farms: vec![StratFarmInfoV1 {
+                state: AutoCompounderState::Running,
+                cycle_stage: AutoCompounderCycle::Swap,
+                slippage: 95,
+                last_reward_amount: 10,
+                last_fee_amount: 0,
+                pool_id_token1_reward: 1,
+                pool_id_token2_reward: 2,
+                reward_token: to_account_id("reward.near"),
+                available_balance: vec![0, 0],
+                id: "0".to_string(),
+            }],
+            harvest_timestamp: 7,
+        })
+    }
+
+    fn create_contract() -> Contract {
+        let mut contract =
+            Contract::new(to_account_id("fluxus.near"), to_account_id("treasure.near"));
+
+        for pool_id in 1..4 {
+            contract.data_mut().strategies.insert(
+                &format!("exchange.near@{}", pool_id),
+                &create_compounder_v1(pool_id),
+            );
         }
+
+        contract
     }
 
-    pub fn pemb_get_strat(&self, seed_id: &str) -> VersionedStrategy {
-        let strat = self
+    #[test]
+    fn test_upgrade_compounder_v1() {
+        testing_env!(get_context().build());
+
+        let strat = create_compounder_v1(1);
+        assert!(strat.need_upgrade());
+
+        let strat = strat.upgrade();
+        assert!(!strat.need_upgrade());
+        assert_eq!(strat.kind(), "REF_REGULAR");
+
+        let compounder = strat.get_compounder();
+        assert_eq!(compounder.seed_id, "exchange.near@1");
+        assert_eq!(compounder.harvest_timestamp, 7);
+
+        let farm = compounder.get_farm_info("0");
+        assert_eq!(farm.cycle_stage, AutoCompounderCycle::Swap);
+        assert_eq!(farm.slippage, 95);
+        assert_eq!(farm.max_slippage, MAX_SLIPPAGE_ALLOWED);
+        assert_eq!(farm.last_reward_amount, 10);
+    }
+
+    fn stored_strat(contract: &Contract, seed_id: &str) -> VersionedStrategy {
+        contract
             .data()
             .strategies
-            .get(seed_id)
-            .expect(ERR21_TOKEN_NOT_REG);
+            .get(&seed_id.to_string())
+            .unwrap()
+    }
 
-        if strat.need_upgrade() {
-            strat.upgrade()
-        } else {
-            strat.clone()
-        }
+    #[test]
+    fn test_upgrade_written_back_on_save() {
+        testing_env!(get_context().build());
+
+        let mut contract = create_contract();
+        let seed_id = "exchange.near@1";
+
+        // strategies are loaded as an upgraded copy, without touching the storage
+        let mut strat = contract.get_strat(seed_id);
+        assert_eq!(strat.kind(), "REF_REGULAR");
+        assert!(stored_strat(&contract, seed_id).need_upgrade());
+
+        strat
+            .strategy_mut()
+            .set_farm_state("0", AutoCompounderState::Ended);
+        contract.save_strat(seed_id, &strat);
+
+        let strat = stored_strat(&contract, seed_id);
+        assert!(!strat.need_upgrade());
+        assert_eq!(strat.strategy().farm_state("0"), "Ended");
+        assert!(stored_strat(&contract, "exchange.near@2").need_upgrade());
     }
 
-    pub fn pemb_get_strat_mut(&mut self, seed_id: &str) -> &mut VersionedStrategy {
-        let strat = self
-            .data_mut()
+    #[test]
+    fn test_upgrade_strategies_in_batches() {
+        testing_env!(get_context().build());
+
+        let mut contract = create_contract();
+
+        assert_eq!(contract.upgrade_strategies(0, 2), 2);
+        assert!(stored_strat(&contract, "exchange.near@3").need_upgrade());
+
+        assert_eq!(contract.upgrade_strategies(2, 2), 1);
+        assert_eq!(contract.upgrade_strategies(0, 10), 0);
+        assert!(contract
+            .data()
             .strategies
-            .get_mut(seed_id)
-            .expect(ERR21_TOKEN_NOT_REG);
+            .values()
+            .all(|strat| !strat.need_upgrade()));
+    }
 
-        if strat.need_upgrade() {
-            strat.upgrade();
-            strat
-        } else {
-            strat
-        }
+    #[test]
+    fn test_paginated_strategies() {
+        testing_env!(get_context().build());
+
+        let contract = create_contract();
+
+        assert_eq!(contract.get_seed_ids(None, None).len(), 3);
+        assert_eq!(
+            contract.get_seed_ids(Some(1), Some(1)),
+            vec!["exchange.near@2"]
+        );
+        assert!(contract.get_seed_ids(Some(5), Some(1)).is_empty());
+
+        let farms = contract.get_strategies_info_for_ref_finance(Some(1), Some(10));
+        assert_eq!(farms.len(), 2);
+        assert_eq!(farms[0].max_slippage, MAX_SLIPPAGE_ALLOWED);
+
+        let strats = contract.get_strats_info(Some(2), None);
+        assert_eq!(strats.len(), 1);
+        assert_eq!(strats[0].get_compounder_ref().seed_id, "exchange.near@3");
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR: not allowed")]
+    fn test_upgrade_strategies_not_allowed() {
+        testing_env!(get_context()
+            .predecessor_account_id(to_account_id("user.near"))
+            .build());
+
+        let mut contract = create_contract();
+        contract.upgrade_strategies(0, 10);
+    }
+
+    #[test]
+    #[should_panic(expected = "E61: strategy is not of the requested kind")]
+    fn test_get_wrong_strategy_kind() {
+        testing_env!(get_context().build());
+
+        let strat = create_compounder_v1(1).upgrade();
+        strat.pemb_get_ref();
     }
 }
@@ -22,13 +22,21 @@ impl Contract {
         // Try to unclaim before change to Ended
         for farm in farms.iter() {
             if farm.farm_id == farm_id && farm.farm_status != *"Running" {
-                let compounder = self.get_strat_mut(&seed_id).get_jumbo_mut();
+                let mut strat = self.get_strat(&seed_id);
+                let compounder = strat.get_jumbo_mut();
 
                 for strat_farm in compounder.farms.iter_mut() {
                     if strat_farm.id == farm_id {
                         strat_farm.state = JumboAutoCompounderState::Ended;
+                        emit_farm_state_updated(
+                            &seed_id,
+                            &strat_farm.id,
+                            String::from(&strat_farm.state),
+                        );
                     }
                 }
+
+                self.save_strat(&seed_id, &strat);
             }
         }
 
@@ -65,7 +73,7 @@ impl Contract {
 
         let (seed_id, token_id, farm_id) = get_ids_from_farm(farm_id_str.to_string());
 
-        let strat = self.get_strat_mut(&seed_id);
+        let mut strat = self.get_strat(&seed_id);
 
         let compounder = strat.get_jumbo_mut();
 
@@ -75,6 +83,8 @@ impl Contract {
             // if farm is ended, there is no more actions to do
             if farm_info.state == JumboAutoCompounderState::Ended {
                 farm_info.state = JumboAutoCompounderState::Cleared;
+                emit_farm_state_updated(&seed_id, &farm_info.id, String::from(&farm_info.state));
+                self.save_strat(&seed_id, &strat);
                 return PromiseOrValue::Value(0u128);
             } else {
                 panic!("ERR: zero rewards earned")
@@ -84,10 +94,14 @@ impl Contract {
         // store the amount of reward earned
         farm_info.last_reward_amount = reward_amount.0;
 
+        let farm_contract_id = compounder.farm_contract_id.clone();
+
+        self.save_strat(&seed_id, &strat);
+
         PromiseOrValue::Promise(
             ext_jumbo_farming::claim_reward_by_farm(
                 farm_id_str.clone(),
-                compounder.farm_contract_id.clone(),
+                farm_contract_id,
                 0,
                 Gas(40_000_000_000_000),
             )
@@ -110,9 +124,13 @@ impl Contract {
 
         let (seed_id, token_id, farm_id) = get_ids_from_farm(farm_id_str);
 
-        let compounder = self.get_strat_mut(&seed_id).get_jumbo_mut();
+        let mut strat = self.get_strat(&seed_id);
+
+        let compounder = strat.get_jumbo_mut();
         let farm_info = compounder.get_mut_jumbo_farm_info(farm_id);
-        farm_info.next_cycle();
+        farm_info.next_cycle(&seed_id);
+
+        self.save_strat(&seed_id, &strat);
     }
 
     #[private]
@@ -125,12 +143,7 @@ impl Contract {
 
         let (seed_id, token_id, farm_id) = get_ids_from_farm(farm_id_str.to_string());
 
-        let data_mut = self.data_mut();
-
-        let strat = data_mut
-            .strategies
-            .get_mut(&seed_id)
-            .expect(ERR21_TOKEN_NOT_REG);
+        let mut strat = self.get_strat(&seed_id);
 
         let compounder = strat.get_jumbo_mut();
 
@@ -139,7 +152,22 @@ impl Contract {
             .last_reward_amount;
 
         let (remaining_amount, protocol_amount, sentry_amount, strat_creator_amount) =
-            compounder.compute_fees(last_reward_amount);
+            self.internal_compute_fees(&seed_id, &compounder.admin_fees, last_reward_amount);
+
+        emit_harvest_fees(
+            &seed_id,
+            Some(&farm_id),
+            &compounder
+                .get_mut_jumbo_farm_info(farm_id.clone())
+                .reward_token,
+            last_reward_amount,
+            (
+                remaining_amount,
+                protocol_amount,
+                sentry_amount,
+                strat_creator_amount,
+            ),
+        );
 
         // storing the amount earned by the strat creator
         compounder.admin_fees.strat_creator.current_amount += strat_creator_amount;
@@ -151,7 +179,7 @@ impl Contract {
             .insert(env::current_account_id(), sentry_amount);
 
         // increase protocol amount to cover the case that the last transfer failed
-        data_mut.treasury.current_amount += protocol_amount;
+        self.data_mut().treasury.current_amount += protocol_amount;
 
         // remaining amount to reinvest
         compounder
@@ -161,15 +189,20 @@ impl Contract {
         // amount sent to ref, both remaining value and treasury
         let amount = remaining_amount + protocol_amount;
 
+        let exchange_contract_id = compounder.exchange_contract_id.clone();
+        let reward_token = compounder
+            .get_mut_jumbo_farm_info(farm_id)
+            .reward_token
+            .clone();
+
+        self.save_strat(&seed_id, &strat);
+
         PromiseOrValue::Promise(
             ext_reward_token::ft_transfer_call(
-                compounder.exchange_contract_id.clone(),
+                exchange_contract_id,
                 U128(amount), //Amount after withdraw the rewards
                 "".to_string(),
-                compounder
-                    .get_mut_jumbo_farm_info(farm_id)
-                    .reward_token
-                    .clone(),
+                reward_token,
                 1,
                 Gas(140_000_000_000_000),
             )
@@ -195,15 +228,13 @@ impl Contract {
 
         let (seed_id, token_id, farm_id) = get_ids_from_farm(farm_id_str);
 
-        let data_mut = self.data_mut();
-        let strat = data_mut
-            .strategies
-            .get_mut(&seed_id)
-            .expect(ERR21_TOKEN_NOT_REG);
+        let mut strat = self.get_strat(&seed_id);
 
         let compounder = strat.get_jumbo_mut();
         let farm_info_mut = compounder.get_mut_jumbo_farm_info(farm_id);
-        farm_info_mut.next_cycle();
+        farm_info_mut.next_cycle(&seed_id);
+
+        self.save_strat(&seed_id, &strat);
     }
 
     /// Callback to verify that transfer to treasure succeeded
@@ -239,10 +270,11 @@ impl Contract {
             return;
         }
 
-        let strat = self.get_strat_mut(&seed_id);
+        let mut strat = self.get_strat(&seed_id);
         let compounder = strat.get_jumbo_mut();
 
         compounder.admin_fees.strat_creator.current_amount = 0;
+        self.save_strat(&seed_id, &strat);
         log!("Transfer fees to the creator of the strategy succeeded");
     }
 
@@ -257,9 +289,12 @@ impl Contract {
 
         if min_amount_out.is_err() {
             log!("Swap for token 1 failed.");
-            let compounder = self.get_strat_mut(&seed_id).get_jumbo_mut();
+            let mut strat = self.get_strat(&seed_id);
+            let compounder = strat.get_jumbo_mut();
             let farm_info_mut = compounder.get_mut_jumbo_farm_info(farm_id);
-            farm_info_mut.increase_slippage();
+            farm_info_mut.increase_slippage(&seed_id);
+
+            self.save_strat(&seed_id, &strat);
 
             return PromiseOrValue::Value(0u128);
         }
@@ -303,12 +338,14 @@ impl Contract {
         min_amount_out: U128,
     ) -> U128 {
         let (seed_id, token_id, farm_id) = get_ids_from_farm(farm_id_str);
-        let compounder_mut = self.get_strat_mut(&seed_id).get_jumbo_mut();
+        let mut strat = self.get_strat(&seed_id);
+        let compounder_mut = strat.get_jumbo_mut();
         let farm_info_mut = compounder_mut.get_mut_jumbo_farm_info(farm_id);
 
         // Do not panic if err == true, otherwise the slippage update will not be applied
         if swap_result.is_err() {
-            farm_info_mut.increase_slippage();
+            farm_info_mut.increase_slippage(&seed_id);
+            self.save_strat(&seed_id, &strat);
             log!("ERR_FIRST_SWAP_FAILED");
 
             return U128(0u128);
@@ -319,7 +356,9 @@ impl Contract {
         // First swap succeeded, thus decrement the last reward_amount
         farm_info_mut.last_reward_amount -= amount_in.0;
 
-        farm_info_mut.next_cycle();
+        farm_info_mut.next_cycle(&seed_id);
+
+        self.save_strat(&seed_id, &strat);
 
         min_amount_out
     }
@@ -335,9 +374,12 @@ impl Contract {
 
         if min_amount_out.is_err() {
             log!("Swap for token 2 failed.");
-            let compounder = self.get_strat_mut(&seed_id).get_jumbo_mut();
+            let mut strat = self.get_strat(&seed_id);
+            let compounder = strat.get_jumbo_mut();
             let farm_info_mut = compounder.get_mut_jumbo_farm_info(farm_id);
-            farm_info_mut.increase_slippage();
+            farm_info_mut.increase_slippage(&seed_id);
+
+            self.save_strat(&seed_id, &strat);
 
             return PromiseOrValue::Value(0u128);
         }
@@ -381,12 +423,14 @@ impl Contract {
         min_amount_out: U128,
     ) -> U128 {
         let (seed_id, token_id, farm_id) = get_ids_from_farm(farm_id_str);
-        let compounder_mut = self.get_strat_mut(&seed_id).get_jumbo_mut();
+        let mut strat = self.get_strat(&seed_id);
+        let compounder_mut = strat.get_jumbo_mut();
         let farm_info_mut = compounder_mut.get_mut_jumbo_farm_info(farm_id);
 
         // Do not panic if err == true, otherwise the slippage update will not be applied
         if swap_result.is_err() {
-            farm_info_mut.increase_slippage();
+            farm_info_mut.increase_slippage(&seed_id);
+            self.save_strat(&seed_id, &strat);
             log!("ERR_FIRST_SWAP_FAILED");
 
             return U128(0u128);
@@ -398,7 +442,9 @@ impl Contract {
         farm_info_mut.last_reward_amount -= amount_in.0;
 
         // after both swaps succeeded, it's ready to stake
-        farm_info_mut.next_cycle();
+        farm_info_mut.next_cycle(&seed_id);
+
+        self.save_strat(&seed_id, &strat);
 
         min_amount_out
     }
@@ -436,7 +482,8 @@ impl Contract {
         }
 
         let (seed_id, token_id, farm_id) = get_ids_from_farm(farm_id_str.to_string());
-        let compounder = self.get_strat_mut(&seed_id).get_jumbo_mut();
+        let mut strat = self.get_strat(&seed_id);
+        let compounder = strat.get_jumbo_mut();
 
         // reset default sentry address and get last earned amount
         let amount = compounder
@@ -450,10 +497,17 @@ impl Contract {
         // if farm is ended, there is no more actions to do
         if farm_info_mut.state == JumboAutoCompounderState::Ended {
             farm_info_mut.state = JumboAutoCompounderState::Cleared;
+            emit_farm_state_updated(
+                &seed_id,
+                &farm_info_mut.id,
+                String::from(&farm_info_mut.state),
+            );
 
             log!("There farm {} ended. Strategy is now Cleared.", farm_id_str);
         }
 
+        self.save_strat(&seed_id, &strat);
+
         if amount > 0 {
             ext_reward_token::ft_transfer(
                 sentry_acc_id.clone(),
@@ -494,13 +548,17 @@ impl Contract {
         if ft_transfer_result.is_err() {
             log!("Transfer to sentry failed".to_string());
 
-            let compounder = self.get_strat_mut(&seed_id).get_jumbo_mut();
+            let mut strat = self.get_strat(&seed_id);
+
+            let compounder = strat.get_jumbo_mut();
 
             // store amount earned by sentry to be redeemed
             compounder
                 .admin_fees
                 .sentries
                 .insert(sentry_id, amount_earned);
+
+            self.save_strat(&seed_id, &strat);
         }
 
         PromiseOrValue::Promise(self.jumbo_harvest_add_liquidity(farm_id_str))
@@ -544,17 +602,24 @@ impl Contract {
 
         let (seed_id, token_id, farm_id) = get_ids_from_farm(farm_id_str.to_string());
 
-        let compounder_mut = self.get_strat_mut(&seed_id).get_jumbo_mut();
+        let mut strat = self.get_strat(&seed_id);
+
+        let compounder_mut = strat.get_jumbo_mut();
         let farm_info_mut = compounder_mut.get_mut_jumbo_farm_info(farm_id);
 
         // ensure that in the next run we won't have a balance unless previous steps succeeds
         farm_info_mut.available_balance[0] = 0u128;
         farm_info_mut.available_balance[1] = 0u128;
 
+        let pool_id = compounder_mut.pool_id;
+        let exchange_contract_id = compounder_mut.exchange_contract_id.clone();
+
+        self.save_strat(&seed_id, &strat);
+
         ext_jumbo_exchange::get_pool_shares(
-            compounder_mut.pool_id,
+            pool_id,
             env::current_account_id(),
-            compounder_mut.exchange_contract_id.clone(),
+            exchange_contract_id,
             0,
             Gas(10_000_000_000_000),
         )
@@ -579,7 +644,9 @@ impl Contract {
 
         let total_seed = self.seed_total_amount(&seed_id);
 
-        let compounder_mut = self.get_strat_mut(&seed_id).get_jumbo_mut();
+        let mut strat = self.get_strat(&seed_id);
+
+        let compounder_mut = strat.get_jumbo_mut();
         let farm_info_mut = compounder_mut.get_mut_jumbo_farm_info(farm_id);
         log!("seed: {}", seed_id);
 
@@ -599,7 +666,9 @@ impl Contract {
 
             farm_info_mut.current_shares_to_stake = shares_on_exchange;
 
-            farm_info_mut.next_cycle();
+            farm_info_mut.next_cycle(&seed_id);
+
+            self.save_strat(&seed_id, &strat);
 
             return new_seed_amount;
         }
@@ -619,7 +688,9 @@ impl Contract {
 
         farm_info_mut.current_shares_to_stake = shares_on_exchange;
 
-        farm_info_mut.next_cycle();
+        farm_info_mut.next_cycle(&seed_id);
+
+        self.save_strat(&seed_id, &strat);
 
         new_seed_amount
     }
@@ -634,12 +705,16 @@ impl Contract {
     ) -> PromiseOrValue<u64> {
         assert!(total_shares_result.is_ok(), "ERR");
 
-        let shares_on_exchange = total_shares_result.unwrap().0;
+        let (seed_id, token_id, _) = get_ids_from_farm(farm_id_str.clone());
+
+        // the claimable shares are held on the exchange too, but are not staked anymore
+        let shares_on_exchange = total_shares_result
+            .unwrap()
+            .0
+            .saturating_sub(self.get_total_claimable_shares(&seed_id));
 
         log!("accumulated shares: {}", shares_on_exchange);
 
-        let (seed_id, token_id, _) = get_ids_from_farm(farm_id_str.clone());
-
         let new_seed_amount =
             self.update_shares_and_forward_cycle(farm_id_str.clone(), shares_on_exchange);
 
@@ -647,6 +722,14 @@ impl Contract {
             .seed_id_amount
             .insert(&seed_id, &new_seed_amount);
 
+        self.internal_accrue_management_fee(&seed_id);
+        self.internal_charge_performance_fee(&seed_id);
+
+        // harvest started before an emergency exit, the shares stay on the exchange
+        if self.is_emergency_exited(&seed_id) {
+            return PromiseOrValue::Value(0u64);
+        }
+
         let compounder = self.get_strat(&seed_id).get_jumbo();
 
         // Prevents failing on stake if below minimum deposit
@@ -658,6 +741,10 @@ impl Contract {
             return PromiseOrValue::Value(0u64);
         }
 
+        // the pending deposits held on the exchange are staked with the harvested shares,
+        // they stay pending until the farm accepts them
+        let pending = self.get_pending_deposits(seed_id.clone());
+
         // return PromiseOrValue::Value(0u64);
 
         PromiseOrValue::Promise(
@@ -671,6 +758,7 @@ impl Contract {
             .then(
                 callback_jumbo_exchange::callback_jumbo_post_stake_from_harvest(
                     farm_id_str,
+                    pending,
                     env::current_account_id(),
                     0,
                     Gas(10_000_000_000_000),
@@ -684,15 +772,25 @@ impl Contract {
         &mut self,
         #[callback_result] stake_result: Result<U128, PromiseError>,
         farm_id_str: String,
+        pending: U128,
     ) {
         assert!(stake_result.is_ok());
+        let used = stake_result.unwrap().0;
 
         let (seed_id, token_id, farm_id) = get_ids_from_farm(farm_id_str);
 
-        let compounder_mut = self.get_strat_mut(&seed_id).get_jumbo_mut();
+        let mut strat = self.get_strat(&seed_id);
+
+        let compounder_mut = strat.get_jumbo_mut();
         let farm_info_mut = compounder_mut.get_mut_jumbo_farm_info(farm_id);
 
         // reset shares after staking
         farm_info_mut.current_shares_to_stake = 0;
+
+        self.save_strat(&seed_id, &strat);
+
+        // the pending deposits staked with the harvested shares were not added by the harvest
+        self.internal_stake_pending_deposits(&seed_id, std::cmp::min(used, pending.0));
+        self.record_share_price(&seed_id, used.saturating_sub(pending.0));
     }
 }
@@ -50,7 +50,9 @@ pub struct JumboStratFarmInfo {
 }
 
 impl JumboStratFarmInfo {
-    pub(crate) fn next_cycle(&mut self) {
+    pub(crate) fn next_cycle(&mut self, seed_id: &str) {
+        let from = String::from(&self.cycle_stage);
+
         match self.cycle_stage {
             JumboAutoCompounderCycle::ClaimReward => {
                 self.cycle_stage = JumboAutoCompounderCycle::Withdrawal
@@ -68,9 +70,16 @@ impl JumboStratFarmInfo {
                 self.cycle_stage = JumboAutoCompounderCycle::ClaimReward
             }
         }
+
+        emit_harvest_step(
+            seed_id,
+            Some(&self.id),
+            from,
+            String::from(&self.cycle_stage),
+        );
     }
 
-    pub fn increase_slippage(&mut self) {
+    pub fn increase_slippage(&mut self, seed_id: &str) {
         if 100u128 - self.slippage < MAX_SLIPPAGE_ALLOWED {
             // increment slippage
             self.slippage -= 4;
@@ -82,6 +91,8 @@ impl JumboStratFarmInfo {
         } else {
             self.state = JumboAutoCompounderState::Ended;
             log!("Slippage too high. State was updated to Ended");
+
+            emit_farm_state_updated(seed_id, &self.id, String::from(&self.state));
         }
     }
 }
@@ -121,22 +132,24 @@ pub struct JumboAutoCompounder {
     pub harvest_timestamp: u64,
 }
 
-#[derive(Debug, BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Clone)]
-#[serde(crate = "near_sdk::serde")]
-pub enum AutoCompounderState {
-    Running,
-    Ended,
-    Cleared,
-} //Should we really add paused state ? It would be best if our code is at such a synchronicity with ref-farm that we won't ever need to pause it.
-
-impl From<&AutoCompounderState> for String {
-    fn from(status: &AutoCompounderState) -> Self {
+impl From<&JumboAutoCompounderState> for String {
+    fn from(status: &JumboAutoCompounderState) -> Self {
         match *status {
-            AutoCompounderState::Running => String::from("Running"),
+            JumboAutoCompounderState::Running => String::from("Running"),
             // Define how long the strategy should be on ended state, waiting for withdrawal
-            AutoCompounderState::Ended => String::from("Ended"),
+            JumboAutoCompounderState::Ended => String::from("Ended"),
             // Latest state, after all withdraw was done
-            AutoCompounderState::Cleared => String::from("Cleared"),
+            JumboAutoCompounderState::Cleared => String::from("Cleared"),
+        }
+    }
+}
+
+impl From<AutoCompounderState> for JumboAutoCompounderState {
+    fn from(status: AutoCompounderState) -> Self {
+        match status {
+            AutoCompounderState::Running => JumboAutoCompounderState::Running,
+            AutoCompounderState::Ended => JumboAutoCompounderState::Ended,
+            AutoCompounderState::Cleared => JumboAutoCompounderState::Cleared,
         }
     }
 }
@@ -193,31 +206,6 @@ impl JumboAutoCompounder {
         }
     }
 
-    pub(crate) fn compute_fees(&mut self, reward_amount: u128) -> (u128, u128, u128, u128) {
-        // apply fees to reward amount
-        let percent = Percentage::from(self.admin_fees.strategy_fee);
-        let all_fees_amount = percent.apply_to(reward_amount);
-        // let percent = Percentage::from(treasury_fee_percentage);
-        // let protocol_amount = percent.apply_to(self.last_reward_amount);
-
-        let percent = Percentage::from(self.admin_fees.sentries_fee);
-        let sentry_amount = percent.apply_to(all_fees_amount);
-
-        let percent = Percentage::from(self.admin_fees.strat_creator.fee_percentage);
-        let strat_creator_amount = percent.apply_to(all_fees_amount);
-        let treasury_amount = all_fees_amount - sentry_amount - strat_creator_amount;
-
-        let remaining_amount =
-            reward_amount - treasury_amount - sentry_amount - strat_creator_amount;
-
-        (
-            remaining_amount,
-            treasury_amount,
-            sentry_amount,
-            strat_creator_amount,
-        )
-    }
-
     pub fn get_jumbo_farm_info(&self, farm_id: &str) -> JumboStratFarmInfo {
         for farm in self.farms.iter() {
             if farm.id == farm_id {
@@ -521,63 +509,223 @@ impl JumboAutoCompounder {
     }
 }
 
-#[near_bindgen]
-impl Contract {
-    #[private]
-    pub fn callback_jumbo_stake_result(
-        &mut self,
-        #[callback_result] transfer_result: Result<U128, PromiseError>,
-        seed_id: String,
-        account_id: AccountId,
-        shares: u128,
-    ) -> String {
-        if let Ok(amount) = transfer_result {
-            assert_eq!(amount.0, 0, "ERR_STAKE_FAILED");
-        } else {
-            panic!("ERR_STAKE_FAILED");
-        }
-        //Total fft_share
-        let total_fft = self.total_supply_by_pool_id(seed_id.clone());
-        log!("total fft is = {}", total_fft);
+impl Strategy for JumboAutoCompounder {
+    fn kind(&self) -> String {
+        "JUMBO_REGULAR".to_string()
+    }
 
-        let fft_share_id = self.get_fft_share_id_from_seed(seed_id.clone());
+    fn seed_min_deposit(&self) -> U128 {
+        self.seed_min_deposit
+    }
 
-        // let data_mut = self.data_mut();
-        //Total seed_id
-        let total_seed = self.seed_total_amount(&seed_id);
+    fn admin_fees(&self) -> &AdminFees {
+        &self.admin_fees
+    }
 
-        let new_seed_amount = total_seed + shares;
+    fn admin_fees_mut(&mut self) -> &mut AdminFees {
+        &mut self.admin_fees
+    }
 
-        log!("seed {}", seed_id);
+    fn harvest_timestamp(&self) -> u64 {
+        self.harvest_timestamp
+    }
 
-        log!("total seed: {} shares added: {}", total_seed, shares);
+    fn farms_len(&self) -> u64 {
+        self.farms.len() as u64
+    }
 
-        log!("new seed amount: {}", new_seed_amount);
+    fn reward_tokens(&self) -> Vec<AccountId> {
+        self.farms
+            .iter()
+            .map(|farm| farm.reward_token.clone())
+            .collect()
+    }
 
-        let fft_share_amount = if total_fft == 0 {
-            shares
-        } else {
-            (U256::from(shares) * U256::from(total_fft) / U256::from(total_seed)).as_u128()
-        };
+    fn is_running(&self) -> bool {
+        self.farms
+            .iter()
+            .any(|farm| farm.state == JumboAutoCompounderState::Running)
+    }
 
-        log!(
-            "{} {} will be minted for {}",
-            fft_share_amount,
-            fft_share_id,
-            account_id.to_string()
-        );
-        self.mft_mint(fft_share_id, fft_share_amount, account_id.to_string());
+    fn farm_state(&self, farm_id: &str) -> String {
+        String::from(&self.get_jumbo_farm_info(farm_id).state)
+    }
+
+    fn farm_cycle_stage(&self, farm_id: &str) -> String {
+        String::from(&self.get_jumbo_farm_info(farm_id).cycle_stage)
+    }
+
+    fn set_farm_state(&mut self, farm_id: &str, state: AutoCompounderState) -> String {
+        let farm_info_mut = self.get_mut_jumbo_farm_info(farm_id.to_string());
+        farm_info_mut.state = state.into();
+
+        String::from(&farm_info_mut.state)
+    }
+
+    fn set_farm_slippage(&mut self, farm_id: &str, slippage: u128) -> u128 {
+        let farm_info_mut = self.get_mut_jumbo_farm_info(farm_id.to_string());
+        farm_info_mut.slippage = slippage;
+
+        farm_info_mut.slippage
+    }
+
+    fn remove_farm(&mut self, farm_id: &str) -> bool {
+        let len = self.farms.len();
+        self.farms.retain(|farm| farm.id != farm_id);
+
+        self.farms.len() != len
+    }
+
+    fn clear_farms(&mut self) {
+        for farm in self.farms.iter_mut() {
+            farm.state = JumboAutoCompounderState::Cleared;
+        }
+    }
+
+    fn farm_cycle_in_progress(&self, farm_id: &str) -> bool {
+        self.farms.iter().any(|farm| {
+            farm.id == farm_id && farm.cycle_stage != JumboAutoCompounderCycle::ClaimReward
+        })
+    }
+
+    fn cycle_in_progress(&self) -> bool {
+        self.farms
+            .iter()
+            .any(|farm| farm.cycle_stage != JumboAutoCompounderCycle::ClaimReward)
+    }
+
+    fn stake(
+        &self,
+        token_id: String,
+        seed_id: String,
+        account_id: &AccountId,
+        shares: u128,
+    ) -> Promise {
+        JumboAutoCompounder::stake(self, token_id, seed_id, account_id, shares)
+    }
+
+    fn unstake(
+        &self,
+        seed_id: String,
+        receiver_id: AccountId,
+        withdraw_amount: u128,
+        user_fft_shares: u128,
+    ) -> Promise {
+        JumboAutoCompounder::unstake(
+            self,
+            wrap_mft_token_id(&self.pool_id.to_string()),
+            seed_id,
+            receiver_id,
+            withdraw_amount,
+            user_fft_shares,
+        )
+    }
+
+    fn restake(&self, shares: u128) -> Promise {
+        ext_jumbo_exchange::mft_transfer_call(
+            wrap_mft_token_id(&self.pool_id.to_string()),
+            self.farm_contract_id.clone(),
+            U128(shares),
+            None,
+            "".to_string(),
+            self.exchange_contract_id.clone(),
+            1,
+            Gas(70_000_000_000_000),
+        )
+    }
+
+    fn emergency_withdraw(&self, shares: u128) -> Promise {
+        ext_jumbo_farming::withdraw_seed(
+            self.seed_id.clone(),
+            U128(shares),
+            self.farm_contract_id.clone(),
+            1,
+            Gas(120_000_000_000_000),
+        )
+    }
 
-        self.data_mut()
-            .seed_id_amount
-            .insert(&seed_id, &new_seed_amount);
+    fn withdraw_held(
+        &self,
+        seed_id: String,
+        receiver_id: AccountId,
+        withdraw_amount: u128,
+        user_fft_shares: u128,
+    ) -> Promise {
+        self.transfer_held(receiver_id.clone(), withdraw_amount)
+            .then(callback_jumbo_exchange::callback_jumbo_withdraw_shares(
+                seed_id,
+                receiver_id,
+                withdraw_amount,
+                user_fft_shares,
+                0,
+                env::current_account_id(),
+                0,
+                Gas(20_000_000_000_000),
+            ))
+    }
 
-        format!(
-            "The {} added {} to {}",
-            account_id, fft_share_amount, seed_id
+    fn transfer_held(&self, receiver_id: AccountId, amount: u128) -> Promise {
+        ext_jumbo_exchange::mft_transfer(
+            wrap_mft_token_id(&self.pool_id.to_string()),
+            receiver_id,
+            U128(amount),
+            Some("".to_string()),
+            self.exchange_contract_id.clone(),
+            1,
+            Gas(30_000_000_000_000),
         )
     }
 
+    fn harvest_step(
+        &mut self,
+        farm_id_str: String,
+        _strat_name: String,
+        treasure: AccountFee,
+    ) -> PromiseOrValue<u128> {
+        let (_, _, farm_id) = get_ids_from_farm(farm_id_str.to_string());
+        let farm_info = self.get_jumbo_farm_info(&farm_id);
+
+        match farm_info.cycle_stage {
+            JumboAutoCompounderCycle::ClaimReward => {
+                PromiseOrValue::Promise(self.claim_reward(farm_id_str))
+            }
+            JumboAutoCompounderCycle::Withdrawal => PromiseOrValue::Promise(
+                self.withdraw_of_reward(farm_id_str, treasure.current_amount),
+            ),
+            JumboAutoCompounderCycle::SwapToken1 => {
+                PromiseOrValue::Promise(self.autocompounds_swap(farm_id_str, treasure))
+            }
+            JumboAutoCompounderCycle::SwapToken2 => {
+                PromiseOrValue::Promise(self.autocompounds_swap_second_token(farm_id_str))
+            }
+            JumboAutoCompounderCycle::Stake => {
+                PromiseOrValue::Promise(self.autocompounds_liquidity_and_stake(farm_id_str))
+            }
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Mints fft_shares for the amount staked into the farm.
+    /// Returns the unused amount, which is refunded to the depositor by the exchange.
+    #[private]
+    pub fn callback_jumbo_stake_result(
+        &mut self,
+        #[callback_result] transfer_result: Result<U128, PromiseError>,
+        seed_id: String,
+        account_id: AccountId,
+        shares: u128,
+    ) -> U128 {
+        // mft_transfer_call returns the amount refunded by the farm
+        let unused = match transfer_result {
+            Ok(amount) => std::cmp::min(amount.0, shares),
+            Err(_) => shares,
+        };
+
+        self.internal_resolve_stake(seed_id, account_id, shares, unused)
+    }
+
     #[private]
     pub fn callback_jumbo_get_pool_shares(
         &self,
@@ -592,7 +740,11 @@ impl Contract {
 
         let compounder = self.get_strat(&seed_id).get_jumbo();
 
-        let shares_on_exchange: u128 = shares_result.unwrap().into();
+        // the claimable shares are held on the exchange too, but belong to their accounts
+        let shares_on_exchange: u128 = shares_result
+            .unwrap()
+            .0
+            .saturating_sub(self.get_total_claimable_shares(&seed_id));
 
         if shares_on_exchange >= withdraw_amount {
             ext_jumbo_exchange::mft_transfer(
@@ -609,6 +761,7 @@ impl Contract {
                 receiver_id,
                 withdraw_amount,
                 user_fft_shares,
+                0,
                 env::current_account_id(),
                 0,
                 Gas(20_000_000_000_000),
@@ -622,30 +775,64 @@ impl Contract {
                 U128(amount),
                 compounder.farm_contract_id.clone(),
                 1,
-                Gas(180_000_000_000_000),
+                Gas(120_000_000_000_000),
             )
-            // transfer the total amount required
-            .then(ext_jumbo_exchange::mft_transfer(
-                token_id.clone(),
-                receiver_id.clone(),
-                U128(withdraw_amount),
-                Some("".to_string()),
-                compounder.exchange_contract_id.clone(),
-                1,
-                Gas(30_000_000_000_000),
-            ))
-            .then(callback_jumbo_exchange::callback_jumbo_withdraw_shares(
+            .then(callback_jumbo_exchange::callback_jumbo_post_withdraw_seed(
+                token_id,
                 seed_id,
                 receiver_id,
                 withdraw_amount,
                 user_fft_shares,
+                amount,
                 env::current_account_id(),
                 0,
-                Gas(20_000_000_000_000),
+                Gas(130_000_000_000_000),
             ))
         }
     }
 
+    /// Transfers the total amount required once the missing amount was withdrawn from the farm.
+    /// Otherwise nothing left the farm, the unstake fails and the user position is kept.
+    #[private]
+    pub fn callback_jumbo_post_withdraw_seed(
+        &self,
+        token_id: String,
+        seed_id: String,
+        receiver_id: AccountId,
+        withdraw_amount: u128,
+        user_fft_shares: u128,
+        withdrawn_from_farm: u128,
+    ) -> Promise {
+        assert!(
+            matches!(env::promise_result(0), PromiseResult::Successful(_)),
+            "ERR: failed to withdraw {} shares of {} from the farm",
+            withdrawn_from_farm,
+            seed_id
+        );
+
+        let compounder = self.get_strat(&seed_id).get_jumbo();
+
+        ext_jumbo_exchange::mft_transfer(
+            token_id,
+            receiver_id.clone(),
+            U128(withdraw_amount),
+            Some("".to_string()),
+            compounder.exchange_contract_id,
+            1,
+            Gas(30_000_000_000_000),
+        )
+        .then(callback_jumbo_exchange::callback_jumbo_withdraw_shares(
+            seed_id,
+            receiver_id,
+            withdraw_amount,
+            user_fft_shares,
+            withdrawn_from_farm,
+            env::current_account_id(),
+            0,
+            Gas(95_000_000_000_000),
+        ))
+    }
+
     #[private]
     pub fn callback_jumbo_withdraw_shares(
         &mut self,
@@ -654,28 +841,15 @@ impl Contract {
         account_id: AccountId,
         amount: Balance,
         fft_shares: Balance,
-    ) {
-        match mft_transfer_result {
-            Ok(_) => log!("Nice!"),
-            Err(err) => {
-                panic!("err")
-            }
-        }
-
-        let data = self.data_mut();
-        let total_seed = data.seed_id_amount.get(&seed_id).unwrap_or_default();
-
-        self.data_mut()
-            .seed_id_amount
-            .insert(&seed_id, &(total_seed - amount));
-
-        let fft_share_id = self
-            .data()
-            .fft_share_by_seed_id
-            .get(&seed_id)
-            .unwrap()
-            .clone();
-
-        self.mft_burn(fft_share_id, fft_shares, account_id.to_string());
+        withdrawn_from_farm: Balance,
+    ) -> PromiseOrValue<()> {
+        self.internal_resolve_unstake(
+            mft_transfer_result.is_ok(),
+            seed_id,
+            account_id,
+            amount,
+            fft_shares,
+            withdrawn_from_farm,
+        )
     }
 }
@@ -9,7 +9,7 @@ use near_contract_standards::storage_management::{
     StorageBalance, StorageBalanceBounds, StorageManagement,
 };
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::{LookupMap, UnorderedSet};
+use near_sdk::collections::{LazyOption, LookupMap, UnorderedMap, UnorderedSet};
 use near_sdk::json_types::U128;
 use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::{
@@ -59,6 +59,57 @@ use callback::*;
 
 mod multi_fungible_token;
 
+mod multi_token;
+use multi_token::*;
+
+mod mft_approval;
+
+mod share_token;
+use share_token::*;
+
+mod vault;
+
+mod share_price;
+use share_price::*;
+
+mod positions;
+use positions::*;
+
+mod cost_basis;
+use cost_basis::*;
+
+mod performance_fee;
+use performance_fee::*;
+
+mod management_fee;
+use management_fee::*;
+
+mod entry_exit_fee;
+use entry_exit_fee::*;
+
+mod tvl_cap;
+use tvl_cap::*;
+
+mod pending_deposit;
+use pending_deposit::*;
+
+mod claimable_shares;
+
+mod emergency_exit;
+use emergency_exit::*;
+
+mod strategy_archive;
+use strategy_archive::*;
+
+mod migration;
+use migration::*;
+
+mod events;
+use events::*;
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod test_utils;
+
 #[derive(BorshStorageKey, BorshSerialize)]
 pub(crate) enum StorageKey {
     Accounts,
@@ -71,6 +122,27 @@ pub(crate) enum StorageKey {
     SeedIdAmount,
     SeedRegister { fft_share: String },
     Strategy { fft_share_id: String },
+    FftShareBySeedId,
+    SeedIdByFftShare,
+    Strategies,
+    AllowancesByShare,
+    ShareTokenCode,
+    ShareTokenByShare,
+    SharePriceHistory,
+    SharePriceSnapshots { seed_id: String },
+    FftSharesByAccount,
+    AccountFftShares { account_id: AccountId },
+    CostBasisByShare,
+    PerformanceFeeBySeedId,
+    ManagementFeeBySeedId,
+    EntryExitFeesBySeedId,
+    DepositTimestampByShare,
+    TvlCapBySeedId,
+    PendingDepositsBySeedId,
+    ClaimableSharesByAccount,
+    ClaimableSharesBySeedId,
+    EmergencyExitBySeedId,
+    ArchivedStrategies,
 }
 
 // TODO: update this to newer version, following AutoCompounderState
@@ -90,7 +162,6 @@ impl fmt::Display for RunningState {
     }
 }
 
-// TODO: update to a versionable contract
 #[near_bindgen]
 #[derive(BorshSerialize, BorshDeserialize, PanicOnDefault)]
 pub struct ContractData {
@@ -120,20 +191,77 @@ pub struct ContractData {
 
     ///It is a map that store the fft_share and a map of users and their balance.
     /// illustration: map(fft_share[i], map(user[i], balance[i])).
-    users_balance_by_fft_share: LookupMap<String, LookupMap<String, u128>>,
+    users_balance_by_fft_share: LookupMap<String, LookupMap<AccountId, u128>>,
 
     ///Store the fft_share total_supply for each seed_id.
     total_supply_by_fft_share: LookupMap<String, u128>,
 
     ///Store the fft_share for each seed_id.
-    /// TODO: Change HashMap for LookupMap as it is more gas efficient
-    fft_share_by_seed_id: HashMap<String, String>,
+    fft_share_by_seed_id: UnorderedMap<String, String>,
+
+    ///Store the seed_id of each fft_share, the reverse of fft_share_by_seed_id.
+    seed_id_by_fft_share: LookupMap<String, String>,
 
     ///Store the fft_share for each seed_id.
     seed_id_amount: LookupMap<String, u128>,
 
-    // Keeps track of token_id to strategy used
-    strategies: HashMap<String, VersionedStrategy>,
+    // Keeps track of seed_id to strategy used, only the accessed strategies are loaded
+    strategies: UnorderedMap<String, VersionedStrategy>,
+
+    /// Block timestamp, in ms, of the latest state migration
+    last_migration_timestamp: u64,
+
+    ///Store the amount of fft_share that a spender can transfer on behalf of the owner.
+    /// illustration: map((fft_share, owner, spender), allowance).
+    allowances_by_fft_share: LookupMap<(String, AccountId, AccountId), u128>,
+
+    ///Wasm of the NEP-141 share tokens deployed for the strategies, built from fluxus-share-token.
+    share_token_code: LazyOption<Vec<u8>>,
+
+    ///Store the NEP-141 share token deployed for each fft_share.
+    share_token_by_fft_share: LookupMap<String, AccountId>,
+
+    ///Store the latest share prices of each seed_id, recorded on every harvest.
+    share_price_history_by_seed_id: LookupMap<String, SharePriceHistory>,
+
+    ///Store the fft_shares that each account holds, kept by mint, burn and transfers.
+    fft_shares_by_account: LookupMap<AccountId, UnorderedSet<String>>,
+
+    ///Store what each account deposited and withdrew from each fft_share.
+    /// illustration: map((fft_share, account), cost_basis).
+    cost_basis_by_fft_share: LookupMap<(String, AccountId), CostBasis>,
+
+    ///Store the high-water mark of the strategies charging a performance fee.
+    performance_fee_by_seed_id: LookupMap<String, PerformanceFee>,
+
+    ///Store the annual management fee of the strategies charging one.
+    management_fee_by_seed_id: LookupMap<String, ManagementFee>,
+
+    ///Store the deposit and withdrawal fees of the strategies charging them.
+    entry_exit_fees_by_seed_id: LookupMap<String, EntryExitFees>,
+
+    ///Store when the fft_shares of each account were deposited, averaged by amount.
+    /// illustration: map((fft_share, account), timestamp_ms).
+    deposit_timestamp_by_fft_share: LookupMap<(String, AccountId), u64>,
+
+    ///Store the deposit caps of the strategies in guarded beta.
+    tvl_cap_by_seed_id: LookupMap<String, TvlCap>,
+
+    ///Store the shares deposited below seed_min_deposit, held on the exchange until they are staked.
+    pending_deposits_by_seed_id: LookupMap<String, u128>,
+
+    ///Store the shares left on the exchange by withdrawals that could not be sent or staked back.
+    /// illustration: map((seed_id, account), shares).
+    claimable_shares_by_account: LookupMap<(String, AccountId), u128>,
+
+    ///Store the total of claimable shares of each seed_id, not owned by the strategy anymore.
+    claimable_shares_by_seed_id: LookupMap<String, u128>,
+
+    ///Store the strategies withdrawn from their farms by an emergency exit.
+    emergency_exit_by_seed_id: LookupMap<String, EmergencyExit>,
+
+    ///Store the retired strategies, read-only, until every fft_share is withdrawn.
+    archived_strategies: UnorderedMap<String, VersionedStrategy>,
 }
 
 construct_uint! {
@@ -148,13 +276,13 @@ pub struct Contract {
 }
 
 /// Versioned contract data. Allows to easily upgrade contracts.
+/// Older layouts are kept only to be read by `migrate`, new variants must always be appended.
 #[derive(BorshSerialize, BorshDeserialize)]
 pub enum VersionedContractData {
-    V0001(ContractData),
+    V0001(ContractDataV0001),
+    V0002(ContractData),
 }
 
-impl VersionedContractData {}
-
 #[near_bindgen]
 impl Contract {
     #[init]
@@ -164,13 +292,13 @@ impl Contract {
 
         let treasury: AccountFee = AccountFee {
             account_id: treasure_contract_id,
-            fee_percentage: 10, //TODO: the treasury fee_percentage can be removed from here as the treasury contract will receive all the fees amount that won't be sent to strat_creator or sentry
+            fee_percentage: 1_000, //TODO: the treasury fee_percentage can be removed from here as the treasury contract will receive all the fees amount that won't be sent to strat_creator or sentry
             // The breakdown of amount for Stakers, operations and treasury will be dealt with inside the treasury contract
             current_amount: 0u128,
         };
 
         Self {
-            data: VersionedContractData::V0001(ContractData {
+            data: VersionedContractData::V0002(ContractData {
                 owner_id,
                 guardians: UnorderedSet::new(StorageKey::Guardian),
                 treasury,
@@ -181,11 +309,27 @@ impl Contract {
                 users_total_near_deposited: LookupMap::new(StorageKey::NearDeposited),
                 users_balance_by_fft_share: LookupMap::new(StorageKey::UsersBalanceByShare),
                 total_supply_by_fft_share: LookupMap::new(StorageKey::TotalSupplyByShare),
-                fft_share_by_seed_id: HashMap::new(),
+                fft_share_by_seed_id: UnorderedMap::new(StorageKey::FftShareBySeedId),
+                seed_id_by_fft_share: LookupMap::new(StorageKey::SeedIdByFftShare),
                 seed_id_amount: LookupMap::new(StorageKey::SeedIdAmount),
-                /// List of all the pools.
-                /// TODO: with more exchanges, this should not exist
-                strategies: HashMap::new(),
+                strategies: UnorderedMap::new(StorageKey::Strategies),
+                last_migration_timestamp: 0u64,
+                allowances_by_fft_share: LookupMap::new(StorageKey::AllowancesByShare),
+                share_token_code: LazyOption::new(StorageKey::ShareTokenCode, None),
+                share_token_by_fft_share: LookupMap::new(StorageKey::ShareTokenByShare),
+                share_price_history_by_seed_id: LookupMap::new(StorageKey::SharePriceHistory),
+                fft_shares_by_account: LookupMap::new(StorageKey::FftSharesByAccount),
+                cost_basis_by_fft_share: LookupMap::new(StorageKey::CostBasisByShare),
+                performance_fee_by_seed_id: LookupMap::new(StorageKey::PerformanceFeeBySeedId),
+                management_fee_by_seed_id: LookupMap::new(StorageKey::ManagementFeeBySeedId),
+                entry_exit_fees_by_seed_id: LookupMap::new(StorageKey::EntryExitFeesBySeedId),
+                deposit_timestamp_by_fft_share: LookupMap::new(StorageKey::DepositTimestampByShare),
+                tvl_cap_by_seed_id: LookupMap::new(StorageKey::TvlCapBySeedId),
+                pending_deposits_by_seed_id: LookupMap::new(StorageKey::PendingDepositsBySeedId),
+                claimable_shares_by_account: LookupMap::new(StorageKey::ClaimableSharesByAccount),
+                claimable_shares_by_seed_id: LookupMap::new(StorageKey::ClaimableSharesBySeedId),
+                emergency_exit_by_seed_id: LookupMap::new(StorageKey::EmergencyExitBySeedId),
+                archived_strategies: UnorderedMap::new(StorageKey::ArchivedStrategies),
             }),
         }
     }
@@ -195,15 +339,15 @@ impl Contract {
     #[allow(unreachable_patterns)]
     fn data(&self) -> &ContractData {
         match &self.data {
-            VersionedContractData::V0001(data) => data,
-            _ => unimplemented!(),
+            VersionedContractData::V0002(data) => data,
+            _ => env::panic_str(ERR60_STATE_NOT_MIGRATED),
         }
     }
     #[allow(unreachable_patterns)]
     fn data_mut(&mut self) -> &mut ContractData {
         match &mut self.data {
-            VersionedContractData::V0001(data) => data,
-            _ => unimplemented!(),
+            VersionedContractData::V0002(data) => data,
+            _ => env::panic_str(ERR60_STATE_NOT_MIGRATED),
         }
     }
 
@@ -216,42 +360,8 @@ impl Contract {
 
     /// Ensures that at least one strategy is running for given token_id
     fn assert_strategy_is_running(&self, seed_id: &str) {
-        let strat = self.get_strat(seed_id);
-
-        match strat {
-            VersionedStrategy::AutoCompounder(_) => {
-                let compounder = strat.get_compounder_ref();
-
-                for farm in compounder.farms.iter() {
-                    if farm.state == AutoCompounderState::Running {
-                        return;
-                    }
-                }
-            }
-            VersionedStrategy::StableAutoCompounder(_) => {
-                let compounder = strat.get_stable_compounder_ref();
-
-                for farm in compounder.farms.iter() {
-                    if farm.state == AutoCompounderState::Running {
-                        return;
-                    }
-                }
-            }
-            VersionedStrategy::JumboAutoCompounder(_) => {
-                let compounder = strat.get_jumbo();
-
-                for farm in compounder.farms.iter() {
-                    if farm.state == JumboAutoCompounderState::Running {
-                        return;
-                    }
-                }
-            }
-            VersionedStrategy::PembrockAutoCompounder(_) => {
-                let compounder = strat.pemb_get_ref();
-                if compounder.state == PembAutoCompounderState::Running {
-                    return;
-                }
-            }
+        if self.get_strat(seed_id).strategy().is_running() {
+            return;
         }
 
         panic!("There is no running strategy for this pool")
@@ -0,0 +1,267 @@
+use crate::*;
+
+/// Max annual management fee, 5%
+const MAX_MANAGEMENT_FEE: u128 = 500;
+
+/// Annual fee on the shares of seed_id held by a strategy.
+/// It is paid by minting fft_shares to the treasury, diluting every holder by the same ratio.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ManagementFee {
+    /// Fee per year, in basis points
+    pub fee: u128,
+
+    /// Block timestamp, in ms, up to which the fee was charged
+    pub last_accrual_timestamp: u64,
+
+    /// fft_shares minted to the treasury, cumulative
+    pub treasury_fft_shares: U128,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Sets the annual management fee of seed_id, in basis points. 0 disables it.
+    /// The fee accrued at the previous rate is charged first.
+    pub fn set_management_fee(&mut self, seed_id: String, fee: u128) {
+        self.is_owner();
+        assert!(fee <= MAX_MANAGEMENT_FEE, "ERR: fees too high");
+        // panics if the strategy does not exist
+        self.get_strat(&seed_id);
+
+        self.internal_accrue_management_fee(&seed_id);
+
+        if fee == 0 {
+            self.data_mut().management_fee_by_seed_id.remove(&seed_id);
+            return;
+        }
+
+        let management_fee = match self.data().management_fee_by_seed_id.get(&seed_id) {
+            Some(management_fee) => ManagementFee {
+                fee,
+                last_accrual_timestamp: env::block_timestamp_ms(),
+                ..management_fee
+            },
+            None => ManagementFee {
+                fee,
+                last_accrual_timestamp: env::block_timestamp_ms(),
+                treasury_fft_shares: U128(0),
+            },
+        };
+
+        self.data_mut()
+            .management_fee_by_seed_id
+            .insert(&seed_id, &management_fee);
+    }
+
+    /// Returns the management fee of seed_id, None if it does not charge one
+    pub fn get_management_fee(&self, seed_id: String) -> Option<ManagementFee> {
+        self.data().management_fee_by_seed_id.get(&seed_id)
+    }
+}
+
+impl Contract {
+    /// Mints the management fee accrued by seed_id since the last accrual.
+    /// Called by the deposits, unstakes and harvests of seed_id, and once more when it is archived.
+    /// Deposits and unstakes call it first, so they happen at the price after the fee.
+    pub(crate) fn internal_accrue_management_fee(&mut self, seed_id: &str) {
+        let seed_id = seed_id.to_string();
+        let mut management_fee = match self.data().management_fee_by_seed_id.get(&seed_id) {
+            Some(management_fee) => management_fee,
+            None => return,
+        };
+
+        // the shares held after an emergency exit do not earn anything,
+        // an archived strategy was settled when it was archived
+        if self.is_emergency_exited(&seed_id) || self.is_strategy_archived(seed_id.clone()) {
+            return;
+        }
+
+        let now = env::block_timestamp_ms();
+        let (total_fft, total_seed) = self.fft_and_seed_totals(&seed_id);
+
+        // nothing is held by the strategy, the fee starts with the next deposit
+        if total_fft == 0 || total_seed == 0 {
+            management_fee.last_accrual_timestamp = now;
+            self.data_mut()
+                .management_fee_by_seed_id
+                .insert(&seed_id, &management_fee);
+            return;
+        }
+
+        let elapsed = now.saturating_sub(management_fee.last_accrual_timestamp);
+        let fee_amount =
+            (U256::from(total_seed) * U256::from(management_fee.fee) * U256::from(elapsed)
+                / (U256::from(FEE_DENOMINATOR) * U256::from(YEAR_MS)))
+            .as_u128();
+
+        if fee_amount == 0 || fee_amount >= total_seed {
+            return;
+        }
+
+        // fft_shares worth fee_amount once they are minted
+        let fee_fft_shares = (U256::from(fee_amount) * U256::from(total_fft)
+            / U256::from(total_seed - fee_amount))
+        .as_u128();
+
+        // keeps the previous timestamp, so the fee is not lost to rounding when accrued too often
+        if fee_fft_shares == 0 {
+            return;
+        }
+
+        let price_before = self.internal_price_per_share(&seed_id);
+        let fft_share_id = self.fft_token_seed_id(seed_id.clone());
+        let treasury_id = self.data().treasury.account_id.clone();
+        self.mft_mint(fft_share_id, fee_fft_shares, treasury_id);
+        // the dilution is not a loss to recover before the next performance fee
+        self.internal_move_high_water_mark(&seed_id, price_before);
+
+        management_fee.last_accrual_timestamp = now;
+        management_fee.treasury_fft_shares =
+            U128(management_fee.treasury_fft_shares.0 + fee_fft_shares);
+
+        Event::ManagementFee(&[ManagementFeeData {
+            seed_id: &seed_id,
+            fee_amount: U128(fee_amount),
+            treasury_fft_shares: U128(fee_fft_shares),
+        }])
+        .emit();
+
+        self.data_mut()
+            .management_fee_by_seed_id
+            .insert(&seed_id, &management_fee);
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::testing_env;
+
+    fn get_context(predecessor_id: &str, timestamp_ms: u64) -> VMContextBuilder {
+        let mut builder = VMContextBuilder::new();
+        builder
+            .current_account_id(to_account_id("auto_compounder.near"))
+            .predecessor_account_id(to_account_id(predecessor_id))
+            .block_timestamp(timestamp_ms * 1_000_000);
+        builder
+    }
+
+    /// 0.5% management fee, 1_000_000 fft_shares worth 1_000_000 shares of SEED_ID
+    fn create_contract() -> Contract {
+        testing_env!(get_context("auto_compounder.near", 0).build());
+        let mut contract = create_safe();
+
+        register_strategy(
+            &mut contract,
+            SEED_ID,
+            "fft_share_1",
+            VersionedStrategy::AutoCompounder(create_compounder(SEED_ID, 1_000)),
+            1_000_000,
+        );
+        contract.mft_mint(
+            "fft_share_1".to_string(),
+            1_000_000,
+            to_account_id("user.near"),
+        );
+
+        contract.set_management_fee(SEED_ID.to_string(), 50);
+
+        contract
+    }
+
+    fn balance(contract: &Contract, account_id: &str) -> u128 {
+        contract.users_fft_share_amount("fft_share_1".to_string(), to_account_id(account_id))
+    }
+
+    #[test]
+    fn test_accrue_management_fee() {
+        let mut contract = create_contract();
+
+        testing_env!(get_context("auto_compounder.near", YEAR_MS).build());
+        contract.internal_accrue_management_fee(SEED_ID);
+
+        // the treasury owns 0.5% of the shares of SEED_ID
+        assert_eq!(balance(&contract, "treasure.near"), 5_025);
+        assert_eq!(contract.seed_amount_for_fft_shares(SEED_ID, 5_025), 4_999);
+        assert_eq!(
+            contract.get_management_fee(SEED_ID.to_string()),
+            Some(ManagementFee {
+                fee: 50,
+                last_accrual_timestamp: YEAR_MS,
+                treasury_fft_shares: U128(5_025),
+            })
+        );
+
+        // the deposit happens at the price after the fee
+        contract.internal_mint_staked_shares(SEED_ID, &to_account_id("other.near"), 1_000);
+        assert_eq!(balance(&contract, "other.near"), 1_005);
+        assert_eq!(balance(&contract, "user.near"), 1_000_000);
+    }
+
+    #[test]
+    fn test_accrual_is_not_lost_to_rounding() {
+        let mut contract = create_contract();
+
+        testing_env!(get_context("auto_compounder.near", 1).build());
+        contract.internal_accrue_management_fee(SEED_ID);
+        assert_eq!(balance(&contract, "treasure.near"), 0);
+        assert_eq!(
+            contract
+                .get_management_fee(SEED_ID.to_string())
+                .unwrap()
+                .last_accrual_timestamp,
+            0
+        );
+
+        testing_env!(get_context("auto_compounder.near", YEAR_MS).build());
+        contract.internal_accrue_management_fee(SEED_ID);
+        assert_eq!(balance(&contract, "treasure.near"), 5_025);
+    }
+
+    #[test]
+    fn test_disable_management_fee() {
+        let mut contract = create_contract();
+
+        // the fee accrued before it is disabled is still charged
+        testing_env!(get_context("auto_compounder.near", YEAR_MS).build());
+        contract.set_management_fee(SEED_ID.to_string(), 0);
+        assert_eq!(balance(&contract, "treasure.near"), 5_025);
+        assert_eq!(contract.get_management_fee(SEED_ID.to_string()), None);
+
+        testing_env!(get_context("auto_compounder.near", 2 * YEAR_MS).build());
+        contract.internal_accrue_management_fee(SEED_ID);
+        assert_eq!(balance(&contract, "treasure.near"), 5_025);
+    }
+
+    #[test]
+    fn test_archived_strategy_stops_accruing() {
+        let mut contract = create_contract();
+
+        // the fee accrued until the strategy is archived is still charged
+        testing_env!(get_context("auto_compounder.near", YEAR_MS).build());
+        contract.archive_strategy(SEED_ID.to_string());
+        assert_eq!(balance(&contract, "treasure.near"), 5_025);
+
+        testing_env!(get_context("auto_compounder.near", 2 * YEAR_MS).build());
+        contract.internal_accrue_management_fee(SEED_ID);
+        assert_eq!(balance(&contract, "treasure.near"), 5_025);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR: fees too high")]
+    fn test_management_fee_too_high() {
+        let mut contract = create_contract();
+        contract.set_management_fee(SEED_ID.to_string(), MAX_MANAGEMENT_FEE + 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_ALLOWED")]
+    fn test_set_management_fee_not_allowed() {
+        let mut contract = create_contract();
+
+        testing_env!(get_context("user.near", 0).build());
+        contract.set_management_fee(SEED_ID.to_string(), 100);
+    }
+}
@@ -0,0 +1,302 @@
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    /// Allows spender_id to transfer up to amount of the caller's fft_shares of token_id,
+    /// replacing the previous allowance.
+    /// A new allowance requires a deposit covering its storage, the extra deposit is refunded.
+    /// The storage deposit is refunded by mft_revoke.
+    #[payable]
+    pub fn mft_approve(&mut self, token_id: String, spender_id: AccountId, amount: U128) {
+        self.assert_contract_running();
+        let attached_deposit = env::attached_deposit();
+        require!(
+            attached_deposit > 0,
+            "Requires attached deposit of at least 1 yoctoNEAR"
+        );
+        require!(amount.0 > 0, "ERR: use mft_revoke to remove an allowance");
+
+        let owner_id = env::predecessor_account_id();
+        require!(owner_id != spender_id, "ERR: cannot approve self");
+        require!(
+            self.data()
+                .total_supply_by_fft_share
+                .get(&token_id)
+                .is_some(),
+            "err: fft does not exist"
+        );
+
+        let initial_storage_usage = env::storage_usage();
+        self.data_mut().allowances_by_fft_share.insert(
+            &(token_id.clone(), owner_id.clone(), spender_id.clone()),
+            &amount.0,
+        );
+        let storage_cost = (env::storage_usage().saturating_sub(initial_storage_usage)) as u128
+            * env::storage_byte_cost();
+
+        require!(
+            attached_deposit >= storage_cost,
+            format!(
+                "ERR: requires attached deposit of at least {} yoctoNEAR to cover the allowance storage",
+                storage_cost
+            )
+        );
+
+        let refund = attached_deposit - storage_cost;
+        if refund > 1 {
+            Promise::new(owner_id.clone()).transfer(refund);
+        }
+
+        Event::FftApprove(&[FftApproveData {
+            owner_id: &owner_id,
+            spender_id: &spender_id,
+            fft_share: &token_id,
+            amount,
+        }])
+        .emit();
+    }
+
+    /// Removes the allowance of spender_id over the caller's fft_shares of token_id
+    /// and refunds its storage deposit.
+    #[payable]
+    pub fn mft_revoke(&mut self, token_id: String, spender_id: AccountId) {
+        assert_one_yocto();
+        let owner_id = env::predecessor_account_id();
+
+        let initial_storage_usage = env::storage_usage();
+        let removed = self.data_mut().allowances_by_fft_share.remove(&(
+            token_id.clone(),
+            owner_id.clone(),
+            spender_id.clone(),
+        ));
+        require!(removed.is_some(), "ERR: allowance does not exist");
+
+        let storage_released = (initial_storage_usage.saturating_sub(env::storage_usage())) as u128
+            * env::storage_byte_cost();
+        if storage_released > 0 {
+            Promise::new(owner_id.clone()).transfer(storage_released);
+        }
+
+        Event::FftRevoke(&[FftRevokeData {
+            owner_id: &owner_id,
+            spender_id: &spender_id,
+            fft_share: &token_id,
+        }])
+        .emit();
+    }
+
+    /// Returns the amount of owner_id's fft_shares of token_id that spender_id can still transfer
+    pub fn mft_allowance(
+        &self,
+        token_id: String,
+        owner_id: AccountId,
+        spender_id: AccountId,
+    ) -> U128 {
+        U128(
+            self.data()
+                .allowances_by_fft_share
+                .get(&(token_id, owner_id, spender_id))
+                .unwrap_or_default(),
+        )
+    }
+
+    /// Transfers fft_shares of owner_id to receiver_id, the caller must have enough allowance.
+    /// The allowance is decreased by amount, its storage is kept until the owner revokes it.
+    #[payable]
+    pub fn mft_transfer_from(
+        &mut self,
+        token_id: String,
+        owner_id: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+    ) {
+        assert_one_yocto();
+        self.assert_contract_running();
+        require!(amount.0 > 0, "ERR: amount must be positive");
+
+        let spender_id = env::predecessor_account_id();
+        let key = (token_id.clone(), owner_id.clone(), spender_id.clone());
+        let allowance = self
+            .data()
+            .allowances_by_fft_share
+            .get(&key)
+            .unwrap_or_default();
+        require!(allowance >= amount.0, "ERR: allowance exceeded");
+
+        self.data_mut()
+            .allowances_by_fft_share
+            .insert(&key, &(allowance - amount.0));
+
+        self.internal_share_transfer(&token_id, &owner_id, &receiver_id, amount.0);
+
+        MtEvent::MtTransfer(&[MtTransferData {
+            authorized_id: Some(&spender_id),
+            old_owner_id: &owner_id,
+            new_owner_id: &receiver_id,
+            token_ids: &[&token_id],
+            amounts: &[amount],
+            memo: memo.as_deref(),
+        }])
+        .emit();
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::{get_logs, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    const STORAGE_DEPOSIT: u128 = 10_000_000_000_000_000_000_000;
+
+    fn get_context(predecessor_id: &str, attached_deposit: u128) -> VMContextBuilder {
+        let mut builder = VMContextBuilder::new();
+        builder
+            .current_account_id(to_account_id("auto_compounder.near"))
+            .predecessor_account_id(to_account_id(predecessor_id))
+            .attached_deposit(attached_deposit);
+        builder
+    }
+
+    pub fn to_account_id(value: &str) -> AccountId {
+        value.parse().unwrap()
+    }
+
+    /// user.near holds 100 fft_share_1
+    fn create_contract() -> Contract {
+        testing_env!(get_context("auto_compounder.near", 0).build());
+        let mut contract = Contract::new(
+            to_account_id("auto_compounder.near"),
+            to_account_id("treasure.near"),
+        );
+
+        contract.register_seed("fft_share_1".to_string());
+        contract.mft_mint("fft_share_1".to_string(), 100, to_account_id("user.near"));
+
+        contract
+    }
+
+    fn allowance(contract: &Contract) -> u128 {
+        contract
+            .mft_allowance(
+                "fft_share_1".to_string(),
+                to_account_id("user.near"),
+                to_account_id("spender.near"),
+            )
+            .0
+    }
+
+    #[test]
+    fn test_approve_and_transfer_from() {
+        let mut contract = create_contract();
+
+        testing_env!(get_context("user.near", STORAGE_DEPOSIT).build());
+        contract.mft_approve(
+            "fft_share_1".to_string(),
+            to_account_id("spender.near"),
+            U128(60),
+        );
+        assert_eq!(allowance(&contract), 60);
+
+        testing_env!(get_context("spender.near", 1).build());
+        contract.mft_transfer_from(
+            "fft_share_1".to_string(),
+            to_account_id("user.near"),
+            to_account_id("market.near"),
+            U128(40),
+            None,
+        );
+
+        assert_eq!(allowance(&contract), 20);
+        assert_eq!(
+            contract.users_fft_share_amount("fft_share_1".to_string(), to_account_id("user.near")),
+            60
+        );
+        assert_eq!(
+            contract
+                .users_fft_share_amount("fft_share_1".to_string(), to_account_id("market.near")),
+            40
+        );
+        assert_eq!(
+            get_logs().last().unwrap(),
+            r#"EVENT_JSON:{"standard":"nep245","version":"1.0.0","event":"mt_transfer","data":[{"authorized_id":"spender.near","old_owner_id":"user.near","new_owner_id":"market.near","token_ids":["fft_share_1"],"amounts":["40"]}]}"#
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR: allowance exceeded")]
+    fn test_transfer_from_above_allowance() {
+        let mut contract = create_contract();
+
+        testing_env!(get_context("user.near", STORAGE_DEPOSIT).build());
+        contract.mft_approve(
+            "fft_share_1".to_string(),
+            to_account_id("spender.near"),
+            U128(10),
+        );
+
+        testing_env!(get_context("spender.near", 1).build());
+        contract.mft_transfer_from(
+            "fft_share_1".to_string(),
+            to_account_id("user.near"),
+            to_account_id("spender.near"),
+            U128(11),
+            None,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "to cover the allowance storage")]
+    fn test_approve_without_storage_deposit() {
+        let mut contract = create_contract();
+
+        testing_env!(get_context("user.near", 1).build());
+        contract.mft_approve(
+            "fft_share_1".to_string(),
+            to_account_id("spender.near"),
+            U128(10),
+        );
+    }
+
+    #[test]
+    fn test_approve_update_and_revoke() {
+        let mut contract = create_contract();
+
+        testing_env!(get_context("user.near", STORAGE_DEPOSIT).build());
+        contract.mft_approve(
+            "fft_share_1".to_string(),
+            to_account_id("spender.near"),
+            U128(10),
+        );
+
+        // updating an allowance does not use more storage
+        testing_env!(get_context("user.near", 1).build());
+        contract.mft_approve(
+            "fft_share_1".to_string(),
+            to_account_id("spender.near"),
+            U128(30),
+        );
+        assert_eq!(allowance(&contract), 30);
+
+        testing_env!(get_context("user.near", 1).build());
+        contract.mft_revoke("fft_share_1".to_string(), to_account_id("spender.near"));
+        assert_eq!(allowance(&contract), 0);
+
+        assert_eq!(
+            get_logs(),
+            vec![
+                r#"EVENT_JSON:{"standard":"fluxus","version":"1.0.0","event":"fft_revoke","data":[{"owner_id":"user.near","spender_id":"spender.near","fft_share":"fft_share_1"}]}"#
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR: allowance does not exist")]
+    fn test_revoke_missing_allowance() {
+        let mut contract = create_contract();
+
+        testing_env!(get_context("user.near", 1).build());
+        contract.mft_revoke("fft_share_1".to_string(), to_account_id("spender.near"));
+    }
+}
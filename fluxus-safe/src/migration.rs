@@ -0,0 +1,412 @@
+use crate::*;
+
+/// Layout of ContractData stored as VersionedContractData::V0001.
+/// Must not be changed, it is only used to read the state written by previous versions.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct ContractDataV0001 {
+    pub(crate) owner_id: AccountId,
+    pub(crate) guardians: UnorderedSet<AccountId>,
+    pub(crate) treasury: AccountFee,
+    pub(crate) accounts: LookupMap<AccountId, VAccount>,
+    pub(crate) allowed_accounts: Vec<AccountId>,
+    pub(crate) whitelisted_tokens: UnorderedSet<AccountId>,
+    pub(crate) state: RunningState,
+    pub(crate) users_total_near_deposited: LookupMap<AccountId, u128>,
+    pub(crate) users_balance_by_fft_share: LookupMap<String, LookupMap<String, u128>>,
+    pub(crate) total_supply_by_fft_share: LookupMap<String, u128>,
+    pub(crate) fft_share_by_seed_id: HashMap<String, String>,
+    pub(crate) seed_id_amount: LookupMap<String, u128>,
+    pub(crate) strategies: HashMap<String, VersionedStrategy>,
+}
+
+impl From<ContractDataV0001> for ContractData {
+    /// Moves the strategies and fft_shares from the contract state into their own collections,
+    /// converts the fees from percent to basis points and starts every new collection empty.
+    /// Every strategy is migrated in the same call, which is tested with up to 50 strategies.
+    /// V0001 already read all of them on each call, and batching would only save their upgrade,
+    /// not the storage writes that dominate the cost.
+    fn from(data: ContractDataV0001) -> Self {
+        let mut fft_share_by_seed_id = UnorderedMap::new(StorageKey::FftShareBySeedId);
+        let mut seed_id_by_fft_share = LookupMap::new(StorageKey::SeedIdByFftShare);
+        for (seed_id, fft_share_id) in data.fft_share_by_seed_id.iter() {
+            fft_share_by_seed_id.insert(seed_id, fft_share_id);
+            seed_id_by_fft_share.insert(fft_share_id, seed_id);
+        }
+
+        let mut strategies = UnorderedMap::new(StorageKey::Strategies);
+        for (seed_id, mut strat) in data.strategies.into_iter() {
+            if strat.need_upgrade() {
+                strat = strat.upgrade();
+            }
+
+            let admin_fees = strat.strategy_mut().admin_fees_mut();
+            admin_fees.strategy_fee *= 100;
+            admin_fees.sentries_fee *= 100;
+            admin_fees.strat_creator.fee_percentage *= 100;
+
+            strategies.insert(&seed_id, &strat);
+        }
+
+        let mut treasury = data.treasury;
+        treasury.fee_percentage *= 100;
+
+        ContractData {
+            owner_id: data.owner_id,
+            guardians: data.guardians,
+            treasury,
+            accounts: data.accounts,
+            allowed_accounts: data.allowed_accounts,
+            whitelisted_tokens: data.whitelisted_tokens,
+            state: data.state,
+            users_total_near_deposited: data.users_total_near_deposited,
+            // balances were keyed by String, which has the same borsh encoding as AccountId
+            users_balance_by_fft_share: LookupMap::new(StorageKey::UsersBalanceByShare),
+            total_supply_by_fft_share: data.total_supply_by_fft_share,
+            fft_share_by_seed_id,
+            seed_id_by_fft_share,
+            seed_id_amount: data.seed_id_amount,
+            strategies,
+            last_migration_timestamp: env::block_timestamp_ms(),
+            allowances_by_fft_share: LookupMap::new(StorageKey::AllowancesByShare),
+            // share tokens are deployed once the code is set
+            share_token_code: LazyOption::new(StorageKey::ShareTokenCode, None),
+            share_token_by_fft_share: LookupMap::new(StorageKey::ShareTokenByShare),
+            share_price_history_by_seed_id: LookupMap::new(StorageKey::SharePriceHistory),
+            // existing holders are added by `index_user_positions`
+            fft_shares_by_account: LookupMap::new(StorageKey::FftSharesByAccount),
+            // existing positions take their value at their next update as principal
+            cost_basis_by_fft_share: LookupMap::new(StorageKey::CostBasisByShare),
+            performance_fee_by_seed_id: LookupMap::new(StorageKey::PerformanceFeeBySeedId),
+            management_fee_by_seed_id: LookupMap::new(StorageKey::ManagementFeeBySeedId),
+            entry_exit_fees_by_seed_id: LookupMap::new(StorageKey::EntryExitFeesBySeedId),
+            // positions held before count as held for long
+            deposit_timestamp_by_fft_share: LookupMap::new(StorageKey::DepositTimestampByShare),
+            tvl_cap_by_seed_id: LookupMap::new(StorageKey::TvlCapBySeedId),
+            pending_deposits_by_seed_id: LookupMap::new(StorageKey::PendingDepositsBySeedId),
+            claimable_shares_by_account: LookupMap::new(StorageKey::ClaimableSharesByAccount),
+            claimable_shares_by_seed_id: LookupMap::new(StorageKey::ClaimableSharesBySeedId),
+            emergency_exit_by_seed_id: LookupMap::new(StorageKey::EmergencyExitBySeedId),
+            archived_strategies: UnorderedMap::new(StorageKey::ArchivedStrategies),
+        }
+    }
+}
+
+impl VersionedContractData {
+    /// Returns the version of the layout used to store the data
+    pub fn version(&self) -> &str {
+        match self {
+            VersionedContractData::V0001(_) => "V0001",
+            VersionedContractData::V0002(_) => "V0002",
+        }
+    }
+
+    fn owner_id(&self) -> &AccountId {
+        match self {
+            VersionedContractData::V0001(data) => &data.owner_id,
+            VersionedContractData::V0002(data) => &data.owner_id,
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Converts the stored state to the latest ContractData layout.
+    /// Should be called right after deploying the new code, either by the owner or by the contract itself.
+    #[init(ignore_state)]
+    pub fn migrate() -> Self {
+        let contract: Contract = env::state_read().expect("ERR_NOT_INITIALIZED");
+
+        let (caller_acc_id, contract_id) = get_predecessor_and_current_account();
+        require!(
+            caller_acc_id == contract_id || caller_acc_id == *contract.data.owner_id(),
+            "ERR_NOT_ALLOWED"
+        );
+
+        log!("Migrating state from {}", contract.data.version());
+
+        let data: ContractData = match contract.data {
+            VersionedContractData::V0001(data) => data.into(),
+            VersionedContractData::V0002(data) => data,
+        };
+
+        Self {
+            data: VersionedContractData::V0002(data),
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::testing_env;
+
+    fn get_context() -> VMContextBuilder {
+        let mut builder = VMContextBuilder::new();
+        builder
+            .current_account_id(to_account_id("auto_compounder.near"))
+            .signer_account_id(to_account_id("fluxus.near"))
+            .predecessor_account_id(to_account_id("fluxus.near"));
+        builder
+    }
+
+    pub fn to_account_id(value: &str) -> AccountId {
+        value.parse().unwrap()
+    }
+
+    /// Number of strategies that must fit in the gas of a single `migrate` call
+    const MAX_STRATEGIES_MIGRATED: u64 = 50;
+
+    /// Layout of VersionedStrategy written by V0001, where variant 0 held the AutoCompounder
+    /// now frozen as AutoCompounderV1. The other variants followed it and are not written here.
+    #[derive(BorshSerialize)]
+    enum VersionedStrategyV0001 {
+        AutoCompounder(AutoCompounderV1),
+    }
+
+    /// Copy of ContractDataV0001 holding the strategies with the V0001 layout
+    #[derive(BorshSerialize)]
+    struct ContractDataV0001Fixture {
+        owner_id: AccountId,
+        guardians: UnorderedSet<AccountId>,
+        treasury: AccountFee,
+        accounts: LookupMap<AccountId, VAccount>,
+        allowed_accounts: Vec<AccountId>,
+        whitelisted_tokens: UnorderedSet<AccountId>,
+        state: RunningState,
+        users_total_near_deposited: LookupMap<AccountId, u128>,
+        users_balance_by_fft_share: LookupMap<String, LookupMap<String, u128>>,
+        total_supply_by_fft_share: LookupMap<String, u128>,
+        fft_share_by_seed_id: HashMap<String, String>,
+        seed_id_amount: LookupMap<String, u128>,
+        strategies: HashMap<String, VersionedStrategyV0001>,
+    }
+
+    #[derive(BorshSerialize)]
+    enum VersionedContractDataV0001 {
+        V0001(ContractDataV0001Fixture),
+    }
+
+    #[derive(BorshSerialize)]
+    struct ContractV0001 {
+        data: VersionedContractDataV0001,
+    }
+
+    /// Returns an AutoCompounder as written by V0001, with fees in percent
+    fn create_compounder_v0001(pool_id: u64) -> VersionedStrategyV0001 {
+        VersionedStrategyV0001::AutoCompounder(AutoCompounderV1 {
+            admin_fees: AdminFees::new(AccountFee::new(to_account_id("creator.near"), 5), 15, 10),
+            exchange_contract_id: to_account_id("exchange.near"),
+            farm_contract_id: to_account_id("farm.near"),
+            token1_address: to_account_id("token1.near"),
+            token2_address: to_account_id("token2.near"),
+            pool_id,
+            seed_min_deposit: U128(1_000),
+            seed_id: format!("exchange.near@{}", pool_id),
+            farms: vec![StratFarmInfoV1 {
+                state: AutoCompounderState::Running,
+                cycle_stage: AutoCompounderCycle::ClaimReward,
+                slippage: 99,
+                last_reward_amount: 0,
+                last_fee_amount: 0,
+                pool_id_token1_reward: 1,
+                pool_id_token2_reward: 2,
+                reward_token: to_account_id("reward.near"),
+                available_balance: vec![0, 0],
+                id: "0".to_string(),
+            }],
+            harvest_timestamp: 7,
+        })
+    }
+
+    /// Writes a V0001 state, as stored by the contracts deployed before V0002
+    fn write_v0001_state() {
+        write_v0001_state_with_strategies(1);
+    }
+
+    /// Writes a V0001 state holding the strategies exchange.near@1 to exchange.near@{count}
+    fn write_v0001_state_with_strategies(count: u64) {
+        let mut data = ContractDataV0001Fixture {
+            owner_id: to_account_id("fluxus.near"),
+            guardians: UnorderedSet::new(StorageKey::Guardian),
+            treasury: AccountFee::new(to_account_id("treasure.near"), 10),
+            accounts: LookupMap::new(StorageKey::Accounts),
+            allowed_accounts: vec![to_account_id("auto_compounder.near")],
+            whitelisted_tokens: UnorderedSet::new(StorageKey::Whitelist),
+            state: RunningState::Paused,
+            users_total_near_deposited: LookupMap::new(StorageKey::NearDeposited),
+            users_balance_by_fft_share: LookupMap::new(StorageKey::UsersBalanceByShare),
+            total_supply_by_fft_share: LookupMap::new(StorageKey::TotalSupplyByShare),
+            fft_share_by_seed_id: HashMap::new(),
+            seed_id_amount: LookupMap::new(StorageKey::SeedIdAmount),
+            strategies: HashMap::new(),
+        };
+
+        data.guardians.insert(&to_account_id("guardian.near"));
+        for pool_id in 1..=count {
+            let seed_id = format!("exchange.near@{}", pool_id);
+            data.fft_share_by_seed_id
+                .insert(seed_id.clone(), format!("fft_share_{}", pool_id));
+            data.strategies
+                .insert(seed_id, create_compounder_v0001(pool_id));
+        }
+        data.total_supply_by_fft_share
+            .insert(&"fft_share_1".to_string(), &1_000u128);
+        data.seed_id_amount
+            .insert(&"exchange.near@1".to_string(), &1_500u128);
+
+        let mut balances: LookupMap<String, u128> = LookupMap::new(StorageKey::Strategy {
+            fft_share_id: "fft_share_1".to_string(),
+        });
+        balances.insert(&"user.near".to_string(), &1_000u128);
+        data.users_balance_by_fft_share
+            .insert(&"fft_share_1".to_string(), &balances);
+
+        env::state_write(&ContractV0001 {
+            data: VersionedContractDataV0001::V0001(data),
+        });
+    }
+
+    #[test]
+    fn test_migrate_from_v0001() {
+        testing_env!(get_context().block_timestamp(42_000_000).build());
+        write_v0001_state();
+
+        let contract = Contract::migrate();
+        env::state_write(&contract);
+
+        // read the migrated state back, as the next call would
+        let contract: Contract = env::state_read().unwrap();
+
+        assert_eq!(contract.data.version(), "V0002");
+        assert_eq!(
+            contract.contract_version(),
+            format!("{} (V0002)", env!("CARGO_PKG_VERSION"))
+        );
+        assert_eq!(contract.data().owner_id, to_account_id("fluxus.near"));
+        assert_eq!(contract.data().state, RunningState::Paused);
+        assert_eq!(contract.data().last_migration_timestamp, 42);
+        assert!(contract
+            .data()
+            .guardians
+            .contains(&to_account_id("guardian.near")));
+        assert_eq!(
+            contract.data().treasury.account_id,
+            to_account_id("treasure.near")
+        );
+        assert_eq!(
+            contract.get_fft_share_id_from_seed("exchange.near@1".to_string()),
+            "fft_share_1".to_string()
+        );
+        assert_eq!(
+            contract
+                .data()
+                .seed_id_by_fft_share
+                .get(&"fft_share_1".to_string()),
+            Some("exchange.near@1".to_string())
+        );
+        assert_eq!(
+            contract.seed_total_amount(&"exchange.near@1".to_string()),
+            1_500u128
+        );
+        assert_eq!(
+            contract.total_supply_amount("fft_share_1".to_string()),
+            1_000u128
+        );
+        assert_eq!(
+            contract.users_fft_share_amount("fft_share_1".to_string(), to_account_id("user.near")),
+            1_000u128
+        );
+
+        // the strategy is read through the V1 layout and stored upgraded
+        let stored = contract
+            .data()
+            .strategies
+            .get(&"exchange.near@1".to_string())
+            .unwrap();
+        assert!(!stored.need_upgrade());
+        assert_eq!(stored.kind(), "REF_REGULAR");
+        let compounder = stored.get_compounder();
+        assert_eq!(compounder.harvest_timestamp, 7);
+        let farm = compounder.get_farm_info("0");
+        assert_eq!(farm.slippage, 99);
+        assert_eq!(farm.max_slippage, MAX_SLIPPAGE_ALLOWED);
+
+        // fees converted to basis points
+        assert_eq!(contract.data().treasury.fee_percentage, 1_000);
+        let strat = contract.get_strat("exchange.near@1");
+        let admin_fees = strat.strategy().admin_fees();
+        assert_eq!(admin_fees.strategy_fee, 1_000);
+        assert_eq!(admin_fees.sentries_fee, 1_500);
+        assert_eq!(admin_fees.strat_creator.fee_percentage, 500);
+        assert_eq!(
+            contract.check_fee_by_strategy("exchange.near@1".to_string()),
+            "10.00%"
+        );
+        assert_eq!(contract.get_allowed_tokens(), vec!["exchange.near@1"]);
+
+        // the collections added since V0001 start empty
+        assert_eq!(
+            contract.get_emergency_exit("exchange.near@1".to_string()),
+            None
+        );
+        assert!(!contract.is_strategy_archived("exchange.near@1".to_string()));
+
+        // migrating again keeps the fees
+        let contract = Contract::migrate();
+        assert_eq!(contract.data().treasury.fee_percentage, 1_000);
+    }
+
+    #[test]
+    fn test_migrate_max_strategies() {
+        testing_env!(get_context().build());
+        write_v0001_state_with_strategies(MAX_STRATEGIES_MIGRATED);
+
+        // start metering gas from the migration
+        testing_env!(get_context().build());
+        let contract = Contract::migrate();
+
+        assert_eq!(contract.data().strategies.len(), MAX_STRATEGIES_MIGRATED);
+        assert!(contract
+            .data()
+            .strategies
+            .values()
+            .all(|strat| !strat.need_upgrade()));
+        // leaves half of the gas of a call to run the contract code
+        assert!(env::used_gas() < Gas(150_000_000_000_000));
+    }
+
+    #[test]
+    fn test_migrate_latest_version() {
+        testing_env!(get_context().build());
+
+        let contract = Contract::new(to_account_id("fluxus.near"), to_account_id("treasure.near"));
+        env::state_write(&contract);
+
+        let contract = Contract::migrate();
+
+        assert_eq!(contract.data.version(), "V0002");
+        assert_eq!(contract.data().last_migration_timestamp, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_ALLOWED")]
+    fn test_migrate_not_allowed() {
+        testing_env!(get_context()
+            .predecessor_account_id(to_account_id("user.near"))
+            .build());
+        write_v0001_state();
+
+        Contract::migrate();
+    }
+
+    #[test]
+    #[should_panic(expected = "E60: state not migrated")]
+    fn test_data_before_migration() {
+        testing_env!(get_context().build());
+        write_v0001_state();
+
+        let contract: Contract = env::state_read().unwrap();
+        contract.get_contract_state();
+    }
+}
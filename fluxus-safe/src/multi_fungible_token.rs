@@ -31,7 +31,7 @@ impl Contract {
     pub fn fft_token_seed_id(&self, seed_id: String) -> String {
         let data = self.data();
         let fft_name: String = if let Some(fft_resp) = data.fft_share_by_seed_id.get(&seed_id) {
-            fft_resp.to_owned()
+            fft_resp
         } else {
             env::panic_str("E1: seed_id doesn't exist");
         };
@@ -39,7 +39,7 @@ impl Contract {
     }
 
     ///Return the u128 amount of an user for an specific fft_share (ref lp token).
-    pub fn users_fft_share_amount(&self, fft_share: String, account_id: String) -> u128 {
+    pub fn users_fft_share_amount(&self, fft_share: String, account_id: AccountId) -> u128 {
         let map = self.data().users_balance_by_fft_share.get(&fft_share);
         if let Some(shares) = map {
             if let Some(user_balance) = shares.get(&account_id) {
@@ -51,10 +51,10 @@ impl Contract {
     }
 
     /// Return the u128 amount a user has in seed_id.
-    pub fn user_share_seed_id(&self, seed_id: String, user: String) -> u128 {
+    pub fn user_share_seed_id(&self, seed_id: String, user: AccountId) -> u128 {
         let data = self.data();
         let fft_name: String = if let Some(fft_resp) = data.fft_share_by_seed_id.get(&seed_id) {
-            fft_resp.to_owned()
+            fft_resp
         } else {
             env::panic_str("E1: seed_id doesn't exist");
         };
@@ -68,11 +68,8 @@ impl Contract {
             total_fft,
             total_seed
         );
-        if total_fft == 0_u128 || total_seed == 0 || user_fft_shares == 0 {
-            0
-        } else {
-            (U256::from(user_fft_shares) * U256::from(total_seed) / U256::from(total_fft)).as_u128()
-        }
+
+        self.seed_amount_for_fft_shares(&seed_id, user_fft_shares)
     }
 
     ///Register a seed into the users_balance_by_fft_share
@@ -101,12 +98,7 @@ impl Contract {
     ///Return the total_supply of an specific fft_share (ref lp token).
     #[private]
     pub fn total_supply_by_pool_id(&mut self, seed_id: String) -> u128 {
-        let fft_share_id = self
-            .data_mut()
-            .fft_share_by_seed_id
-            .get(&seed_id)
-            .unwrap()
-            .clone();
+        let fft_share_id = self.data_mut().fft_share_by_seed_id.get(&seed_id).unwrap();
 
         let result = self.data_mut().total_supply_by_fft_share.get(&fft_share_id);
         if let Some(res) = result {
@@ -117,12 +109,7 @@ impl Contract {
     }
 
     pub fn get_fft_share_id_from_seed(&self, seed_id: String) -> String {
-        let fft_share_id = self
-            .data()
-            .fft_share_by_seed_id
-            .get(&seed_id)
-            .unwrap()
-            .clone();
+        let fft_share_id = self.data().fft_share_by_seed_id.get(&seed_id).unwrap();
 
         log!("fft id is: {}", fft_share_id);
 
@@ -133,7 +120,7 @@ impl Contract {
     /// and increment the total_supply of this seed's fft_share.
     /// It returns the user's new balance.
     #[private]
-    pub fn mft_mint(&mut self, fft_share: String, balance: u128, user: String) -> u128 {
+    pub fn mft_mint(&mut self, fft_share: String, balance: u128, user: AccountId) -> u128 {
         //Add balance to the user for this seed
         let old_amount: u128 = self.users_fft_share_amount(fft_share.clone(), user.clone());
 
@@ -151,12 +138,24 @@ impl Contract {
             .users_balance_by_fft_share
             .insert(&fft_share, &map_temp);
 
+        self.internal_update_position(&fft_share, &user, new_balance);
+
         //Add balance to the total supply
         let old_total = self.total_supply_amount(fft_share.clone());
         self.data_mut()
             .total_supply_by_fft_share
             .insert(&fft_share, &(old_total + balance));
 
+        self.internal_mirror_mint(&fft_share, &user, balance);
+
+        MtEvent::MtMint(&[MtMintData {
+            owner_id: &user,
+            token_ids: &[&fft_share],
+            amounts: &[U128(balance)],
+            memo: None,
+        }])
+        .emit();
+
         //Returning the new balance
         new_balance
     }
@@ -165,7 +164,7 @@ impl Contract {
     /// and decrement the total_supply of this seed's fft_share.
     /// It returns the user's new balance.
     #[private]
-    pub fn mft_burn(&mut self, fft_share: String, balance: u128, user: String) -> u128 {
+    pub fn mft_burn(&mut self, fft_share: String, balance: u128, user: AccountId) -> u128 {
         //Sub balance to the user for this seed
         let old_amount: u128 = self.users_fft_share_amount(fft_share.clone(), user.clone());
         assert!(old_amount >= balance);
@@ -184,12 +183,25 @@ impl Contract {
             .users_balance_by_fft_share
             .insert(&fft_share, &map_temp);
 
+        self.internal_update_position(&fft_share, &user, new_balance);
+
         //Sub balance to the total supply
         let old_total = self.total_supply_amount(fft_share.clone());
         self.data_mut()
             .total_supply_by_fft_share
             .insert(&fft_share, &(old_total - balance));
 
+        self.internal_mirror_burn(&fft_share, &user, balance);
+
+        MtEvent::MtBurn(&[MtBurnData {
+            owner_id: &user,
+            authorized_id: None,
+            token_ids: &[&fft_share],
+            amounts: &[U128(balance)],
+            memo: None,
+        }])
+        .emit();
+
         //Returning the new balance
         new_balance
     }
@@ -200,92 +212,21 @@ impl Contract {
     pub fn mft_transfer(
         &mut self,
         token_id: String,
-        receiver_id: String,
+        receiver_id: AccountId,
         amount: U128,
         memo: Option<String>,
     ) {
         assert_one_yocto();
-        log!("{}", env::predecessor_account_id().to_string());
         self.assert_contract_running();
         self.internal_mft_transfer(
             token_id,
-            env::predecessor_account_id().to_string(),
+            env::predecessor_account_id(),
             receiver_id,
             amount.0,
             memo,
         );
     }
 
-    fn internal_mft_transfer(
-        &mut self,
-        token_id: String,
-        sender_id: String,
-        receiver_id: String,
-        amount: u128,
-        memo: Option<String>,
-    ) {
-        assert_ne!(sender_id, receiver_id, "{}", ERR33_TRANSFER_TO_SELF);
-        self.share_transfer(
-            token_id.clone(),
-            sender_id.clone(),
-            receiver_id.clone(),
-            amount,
-        );
-
-        log!(
-            "Transfer shares {}: {} from {} to {}",
-            token_id,
-            amount,
-            sender_id,
-            receiver_id
-        );
-
-        if let Some(memo) = memo {
-            log!("Memo: {}", memo);
-        }
-    }
-
-    pub fn share_transfer(
-        &mut self,
-        fft_share: String,
-        sender_id: String,
-        receiver_id: String,
-        amount: u128,
-    ) {
-        let old_amount: u128 = self.users_fft_share_amount(fft_share.clone(), sender_id.clone());
-        assert!(old_amount >= amount);
-
-        let new_balance = old_amount - amount;
-
-        let mut map_temp = self
-            .data()
-            .users_balance_by_fft_share
-            .get(&fft_share)
-            .expect("err: fft does not exist");
-
-        map_temp.insert(&sender_id, &new_balance);
-
-        self.data_mut()
-            .users_balance_by_fft_share
-            .insert(&fft_share, &map_temp);
-
-        let old_amount: u128 = self.users_fft_share_amount(fft_share.clone(), receiver_id.clone());
-        let new_balance = old_amount + amount;
-        log!("{} + {} = new_balance {}", old_amount, amount, new_balance);
-
-        let mut map_temp = self
-            .data()
-            .users_balance_by_fft_share
-            .get(&fft_share)
-            .expect("err: fft does not exist");
-
-        map_temp.insert(&receiver_id, &new_balance);
-
-        self.data_mut()
-            .users_balance_by_fft_share
-            .insert(&fft_share, &map_temp);
-    }
-
     ///Transfer fft_shares internally (account to account),
     /// call mft_on_transfer in the receiver contract and
     /// refound something if it is necessary.
@@ -303,8 +244,8 @@ impl Contract {
         let sender_id = env::predecessor_account_id();
         self.internal_mft_transfer(
             token_id.clone(),
-            sender_id.to_string(),
-            receiver_id.to_string(),
+            sender_id.clone(),
+            receiver_id.clone(),
             amount.0,
             memo,
         );
@@ -353,7 +294,7 @@ impl Contract {
         };
         if unused_amount > 0 {
             let receiver_balance =
-                self.users_fft_share_amount(token_id.clone(), (*receiver_id).to_string());
+                self.users_fft_share_amount(token_id.clone(), receiver_id.clone());
             if receiver_balance > 0 {
                 let refund_amount = std::cmp::min(receiver_balance, unused_amount);
                 // If sender's account was deleted, we assume that they have also withdrew all the liquidity from pools.
@@ -365,10 +306,10 @@ impl Contract {
                 };
                 self.internal_mft_transfer(
                     token_id,
-                    receiver_id.to_string(),
-                    refund_to.to_string(),
+                    receiver_id.clone(),
+                    refund_to,
                     refund_amount,
-                    None,
+                    Some("refund".to_string()),
                 );
             }
         }
@@ -376,10 +317,297 @@ impl Contract {
     }
 }
 
+impl Contract {
+    pub(crate) fn internal_mft_transfer(
+        &mut self,
+        token_id: String,
+        sender_id: AccountId,
+        receiver_id: AccountId,
+        amount: u128,
+        memo: Option<String>,
+    ) {
+        self.internal_share_transfer(&token_id, &sender_id, &receiver_id, amount);
+
+        MtEvent::MtTransfer(&[MtTransferData {
+            authorized_id: None,
+            old_owner_id: &sender_id,
+            new_owner_id: &receiver_id,
+            token_ids: &[&token_id],
+            amounts: &[U128(amount)],
+            memo: memo.as_deref(),
+        }])
+        .emit();
+    }
+
+    /// Moves amount of fft_share from sender_id to receiver_id and on its share token,
+    /// without emitting events
+    pub(crate) fn internal_share_transfer(
+        &mut self,
+        fft_share: &str,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        amount: u128,
+    ) {
+        self.internal_share_balance_transfer(fft_share, sender_id, receiver_id, amount);
+        self.internal_mirror_transfer(fft_share, sender_id, receiver_id, amount);
+    }
+
+    /// Moves amount of fft_share from sender_id to receiver_id in the safe only
+    pub(crate) fn internal_share_balance_transfer(
+        &mut self,
+        fft_share: &str,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        amount: u128,
+    ) {
+        assert_ne!(sender_id, receiver_id, "{}", ERR33_TRANSFER_TO_SELF);
+        self.internal_transfer_cost_basis(fft_share, sender_id, receiver_id, amount);
+        self.internal_transfer_deposit_timestamp(fft_share, sender_id, receiver_id, amount);
+        let fft_share = fft_share.to_string();
+
+        let old_amount: u128 = self.users_fft_share_amount(fft_share.clone(), sender_id.clone());
+        assert!(old_amount >= amount);
+
+        let new_balance = old_amount - amount;
+
+        let mut map_temp = self
+            .data()
+            .users_balance_by_fft_share
+            .get(&fft_share)
+            .expect("err: fft does not exist");
+
+        map_temp.insert(sender_id, &new_balance);
+
+        self.data_mut()
+            .users_balance_by_fft_share
+            .insert(&fft_share, &map_temp);
+
+        self.internal_update_position(&fft_share, sender_id, new_balance);
+
+        let old_amount: u128 = self.users_fft_share_amount(fft_share.clone(), receiver_id.clone());
+        let new_balance = old_amount + amount;
+        log!("{} + {} = new_balance {}", old_amount, amount, new_balance);
+
+        let mut map_temp = self
+            .data()
+            .users_balance_by_fft_share
+            .get(&fft_share)
+            .expect("err: fft does not exist");
+
+        map_temp.insert(receiver_id, &new_balance);
+
+        self.data_mut()
+            .users_balance_by_fft_share
+            .insert(&fft_share, &map_temp);
+
+        self.internal_update_position(&fft_share, receiver_id, new_balance);
+    }
+
+    /// Mints fft_shares for the part of shares that was staked.
+    /// Returns the unused part, to be refunded to the depositor.
+    pub(crate) fn internal_resolve_stake(
+        &mut self,
+        seed_id: String,
+        account_id: AccountId,
+        shares: u128,
+        unused: u128,
+    ) -> U128 {
+        if unused > 0 {
+            log!(
+                "ERR_STAKE_FAILED: {} of {} were not staked into {}, refunding",
+                unused,
+                shares,
+                seed_id
+            );
+        }
+
+        let used = shares - unused;
+        if used > 0 {
+            self.internal_mint_staked_shares(&seed_id, &account_id, used);
+        }
+
+        U128(unused)
+    }
+
+    /// Burns the fft_shares of an unstake once the shares were transferred to account_id.
+    /// Otherwise, the user position is kept and the shares withdrawn from the farm are staked back,
+    /// so seed_id_amount still matches the shares owned by the strategy.
+    /// The shares that cannot be staked back are left for account_id to claim.
+    pub(crate) fn internal_resolve_unstake(
+        &mut self,
+        transfer_succeeded: bool,
+        seed_id: String,
+        account_id: AccountId,
+        amount: Balance,
+        fft_shares: Balance,
+        withdrawn_from_farm: Balance,
+    ) -> PromiseOrValue<()> {
+        if !transfer_succeeded {
+            log!(
+                "ERR: failed to transfer {} shares to {}",
+                amount,
+                account_id
+            );
+
+            // after an emergency exit, the shares stay on the exchange with the others
+            if withdrawn_from_farm == 0 || self.is_emergency_exited(&seed_id) {
+                return PromiseOrValue::Value(());
+            }
+
+            log!(
+                "Staking back {} shares into {}",
+                withdrawn_from_farm,
+                seed_id
+            );
+
+            return PromiseOrValue::Promise(
+                self.get_strat(&seed_id)
+                    .strategy()
+                    .restake(withdrawn_from_farm)
+                    .then(callback_claimable_shares::callback_post_unstake_restake(
+                        seed_id,
+                        account_id,
+                        U128(amount),
+                        U128(fft_shares),
+                        U128(withdrawn_from_farm),
+                        env::current_account_id(),
+                        0,
+                        Gas(15_000_000_000_000),
+                    )),
+            );
+        }
+
+        self.internal_withdraw_pending_deposits(&seed_id, amount);
+        self.internal_record_unstake(&seed_id, &account_id, amount, fft_shares);
+
+        PromiseOrValue::Value(())
+    }
+
+    /// Removes amount of shares from seed_id and burns the fft_shares withdrawn by account_id
+    pub(crate) fn internal_record_unstake(
+        &mut self,
+        seed_id: &str,
+        account_id: &AccountId,
+        amount: Balance,
+        fft_shares: Balance,
+    ) {
+        let fft_share_id = self.get_fft_share_id_from_seed(seed_id.to_string());
+        let price_before = self.internal_price_per_share(seed_id);
+
+        self.internal_record_withdraw(&fft_share_id, account_id, amount, fft_shares);
+
+        let seed_id = seed_id.to_string();
+        let total_seed = self.seed_total_amount(&seed_id);
+
+        self.data_mut()
+            .seed_id_amount
+            .insert(&seed_id, &(total_seed - amount));
+
+        self.mft_burn(fft_share_id, fft_shares, account_id.clone());
+        // the withdrawal fee stays in the strategy
+        self.internal_move_high_water_mark(&seed_id, price_before);
+
+        Event::Withdraw(&[WithdrawData {
+            account_id,
+            seed_id: &seed_id,
+            amount: U128(amount),
+            fft_shares: U128(fft_shares),
+        }])
+        .emit();
+    }
+
+    /// Adds shares staked by the farm to seed_id and mints the equivalent fft_shares for account_id
+    pub(crate) fn internal_mint_staked_shares(
+        &mut self,
+        seed_id: &str,
+        account_id: &AccountId,
+        shares: u128,
+    ) -> u128 {
+        self.internal_accrue_management_fee(seed_id);
+        let price_before = self.internal_price_per_share(seed_id);
+
+        let fft_share_id = self.get_fft_share_id_from_seed(seed_id.to_string());
+        // the deposit fee stays in the strategy, the fft_shares are minted for the rest
+        let fee = self.internal_deposit_fee(seed_id, shares);
+        let fft_share_amount = self.fft_shares_for_deposit(seed_id, shares - fee);
+
+        self.internal_record_deposit(&fft_share_id, account_id, shares);
+        self.internal_record_deposit_timestamp(&fft_share_id, account_id, fft_share_amount);
+
+        let seed_id = seed_id.to_string();
+        let total_seed = self.seed_total_amount(&seed_id);
+        self.data_mut()
+            .seed_id_amount
+            .insert(&seed_id, &(total_seed + shares));
+
+        self.mft_mint(fft_share_id, fft_share_amount, account_id.clone());
+        self.internal_move_high_water_mark(&seed_id, price_before);
+
+        Event::Deposit(&[DepositData {
+            account_id,
+            seed_id: &seed_id,
+            amount: U128(shares),
+            fft_shares: U128(fft_share_amount),
+        }])
+        .emit();
+
+        fft_share_amount
+    }
+
+    /// Returns the fft_shares total supply and the amount of shares of seed_id
+    pub(crate) fn fft_and_seed_totals(&self, seed_id: &str) -> (u128, u128) {
+        let seed_id = seed_id.to_string();
+
+        let total_fft = self
+            .data()
+            .fft_share_by_seed_id
+            .get(&seed_id)
+            .map(|fft_share_id| self.total_supply_amount(fft_share_id))
+            .unwrap_or_default();
+
+        (total_fft, self.seed_total_amount(&seed_id))
+    }
+
+    /// Returns the amount of fft_shares that would be minted for shares added to seed_id
+    pub(crate) fn fft_shares_for_deposit(&self, seed_id: &str, shares: u128) -> u128 {
+        let (total_fft, total_seed) = self.fft_and_seed_totals(seed_id);
+
+        if total_fft == 0 || total_seed == 0 {
+            shares
+        } else {
+            (U256::from(shares) * U256::from(total_fft) / U256::from(total_seed)).as_u128()
+        }
+    }
+
+    /// Returns the amount of shares of seed_id owned by fft_shares, rounded down
+    pub(crate) fn seed_amount_for_fft_shares(&self, seed_id: &str, fft_shares: u128) -> u128 {
+        let (total_fft, total_seed) = self.fft_and_seed_totals(seed_id);
+
+        if total_fft == 0 || total_seed == 0 || fft_shares == 0 {
+            0
+        } else {
+            (U256::from(fft_shares) * U256::from(total_seed) / U256::from(total_fft)).as_u128()
+        }
+    }
+
+    /// Returns the amount of fft_shares burnt to withdraw amount of shares from seed_id,
+    /// rounded up so that a withdrawal never burns less than the shares it takes
+    pub(crate) fn fft_shares_for_withdraw(&self, seed_id: &str, amount: u128) -> u128 {
+        let (total_fft, total_seed) = self.fft_and_seed_totals(seed_id);
+
+        if total_fft == 0 || total_seed == 0 {
+            0
+        } else {
+            let total_seed = U256::from(total_seed);
+            ((U256::from(amount) * U256::from(total_fft) + total_seed - 1) / total_seed).as_u128()
+        }
+    }
+}
+
 #[cfg(all(test, not(target_arch = "wasm32")))]
 mod tests {
     use super::*;
-    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::test_utils::{get_logs, VMContextBuilder};
     use near_sdk::{testing_env, VMContext};
 
     fn get_context() -> VMContextBuilder {
@@ -417,16 +645,16 @@ mod tests {
 
         //Minting fft_share
         let mut deposit =
-            contract.mft_mint("fft_share_1".to_string(), 10_u128, "user1".to_string());
+            contract.mft_mint("fft_share_1".to_string(), 10_u128, to_account_id("user1"));
         assert_eq!(deposit, 10_u128);
 
         //Checking balance
         let mut balance =
-            contract.users_fft_share_amount("fft_share_1".to_string(), "user1".to_string());
+            contract.users_fft_share_amount("fft_share_1".to_string(), to_account_id("user1"));
         assert_eq!(balance, 10_u128);
 
         //Minting more fft_share
-        deposit = contract.mft_mint("fft_share_1".to_string(), 10_u128, "user1".to_string());
+        deposit = contract.mft_mint("fft_share_1".to_string(), 10_u128, to_account_id("user1"));
         assert_eq!(deposit, 20_u128);
     }
 
@@ -446,11 +674,12 @@ mod tests {
 
         //Minting fft_share
         let mut deposit =
-            contract.mft_mint("fft_share_1".to_string(), 10_u128, "user1".to_string());
+            contract.mft_mint("fft_share_1".to_string(), 10_u128, to_account_id("user1"));
         assert_eq!(deposit, 10_u128);
 
         //burning fft_share
-        let mut balance = contract.mft_burn("fft_share_1".to_string(), 2_u128, "user1".to_string());
+        let mut balance =
+            contract.mft_burn("fft_share_1".to_string(), 2_u128, to_account_id("user1"));
         assert_eq!(balance, 8_u128);
 
         //Checking total supply
@@ -475,14 +704,14 @@ mod tests {
         let mut balance_user1 = contract.mft_mint(
             "fft_share_1".to_string(),
             10_u128,
-            "auto_compounder.near".to_string(),
+            to_account_id("auto_compounder.near"),
         );
         assert_eq!(balance_user1, 10_u128);
         let mut balance_user2 =
-            contract.mft_mint("fft_share_1".to_string(), 10_u128, "user2".to_string());
+            contract.mft_mint("fft_share_1".to_string(), 10_u128, to_account_id("user2"));
         assert_eq!(balance_user2, 10_u128);
         let mut balance_user3 =
-            contract.mft_mint("fft_share_1".to_string(), 999_u128, "user3".to_string());
+            contract.mft_mint("fft_share_1".to_string(), 999_u128, to_account_id("user3"));
         assert_eq!(balance_user3, 999_u128);
 
         //Checking total supply
@@ -492,33 +721,111 @@ mod tests {
         //Transferring fft_shares
         contract.mft_transfer(
             "fft_share_1".to_string(),
-            "user2".to_string(),
+            to_account_id("user2"),
             U128::from(5_u128),
             None,
         );
         balance_user1 = contract.users_fft_share_amount(
             "fft_share_1".to_string(),
-            "auto_compounder.near".to_string(),
+            to_account_id("auto_compounder.near"),
         );
         assert_eq!(balance_user1, 5_u128);
         balance_user2 =
-            contract.users_fft_share_amount("fft_share_1".to_string(), "user2".to_string());
+            contract.users_fft_share_amount("fft_share_1".to_string(), to_account_id("user2"));
         assert_eq!(balance_user2, 15_u128);
 
         //Transferring fft_shares
         contract.mft_transfer(
             "fft_share_1".to_string(),
-            "user3".to_string(),
+            to_account_id("user3"),
             U128::from(5_u128),
             None,
         );
         balance_user1 = contract.users_fft_share_amount(
             "fft_share_1".to_string(),
-            "auto_compounder.near".to_string(),
+            to_account_id("auto_compounder.near"),
         );
         assert_eq!(balance_user1, 0_u128);
         balance_user3 =
-            contract.users_fft_share_amount("fft_share_1".to_string(), "user3".to_string());
+            contract.users_fft_share_amount("fft_share_1".to_string(), to_account_id("user3"));
         assert_eq!(balance_user3, 1004_u128);
     }
+
+    fn events() -> Vec<String> {
+        get_logs()
+            .into_iter()
+            .filter(|log| log.starts_with("EVENT_JSON:"))
+            .collect()
+    }
+
+    #[test]
+    fn test_mint_burn_transfer_events() {
+        let context = get_context();
+        testing_env!(context.build());
+        let mut contract = Contract::new(
+            "auto_compounder.near".parse().unwrap(),
+            "dev-1656420526638-61041719201929".parse().unwrap(),
+        );
+        contract.register_seed("fft_share_1".to_string());
+
+        contract.mft_mint(
+            "fft_share_1".to_string(),
+            10_u128,
+            to_account_id("auto_compounder.near"),
+        );
+        contract.mft_burn(
+            "fft_share_1".to_string(),
+            2_u128,
+            to_account_id("auto_compounder.near"),
+        );
+        contract.mft_transfer(
+            "fft_share_1".to_string(),
+            to_account_id("user2"),
+            U128(3),
+            Some("gift".to_string()),
+        );
+
+        assert_eq!(
+            events(),
+            vec![
+                r#"EVENT_JSON:{"standard":"nep245","version":"1.0.0","event":"mt_mint","data":[{"owner_id":"auto_compounder.near","token_ids":["fft_share_1"],"amounts":["10"]}]}"#,
+                r#"EVENT_JSON:{"standard":"nep245","version":"1.0.0","event":"mt_burn","data":[{"owner_id":"auto_compounder.near","token_ids":["fft_share_1"],"amounts":["2"]}]}"#,
+                r#"EVENT_JSON:{"standard":"nep245","version":"1.0.0","event":"mt_transfer","data":[{"old_owner_id":"auto_compounder.near","new_owner_id":"user2","token_ids":["fft_share_1"],"amounts":["3"],"memo":"gift"}]}"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_deposit_and_withdraw_events() {
+        let context = get_context();
+        testing_env!(context.build());
+        let mut contract = Contract::new(
+            "auto_compounder.near".parse().unwrap(),
+            "dev-1656420526638-61041719201929".parse().unwrap(),
+        );
+        let seed_id = "exchange.near@0".to_string();
+        contract.register_seed("fft_share_1".to_string());
+        contract
+            .data_mut()
+            .fft_share_by_seed_id
+            .insert(&seed_id, &"fft_share_1".to_string());
+        contract
+            .data_mut()
+            .seed_id_by_fft_share
+            .insert(&"fft_share_1".to_string(), &seed_id);
+
+        let user = to_account_id("user.near");
+        contract.internal_mint_staked_shares(&seed_id, &user, 100);
+        contract.internal_resolve_unstake(true, seed_id, user, 40, 40, 0);
+
+        assert_eq!(
+            events(),
+            vec![
+                r#"EVENT_JSON:{"standard":"nep245","version":"1.0.0","event":"mt_mint","data":[{"owner_id":"user.near","token_ids":["fft_share_1"],"amounts":["100"]}]}"#,
+                r#"EVENT_JSON:{"standard":"fluxus","version":"1.0.0","event":"deposit","data":[{"account_id":"user.near","seed_id":"exchange.near@0","amount":"100","fft_shares":"100"}]}"#,
+                r#"EVENT_JSON:{"standard":"nep245","version":"1.0.0","event":"mt_burn","data":[{"owner_id":"user.near","token_ids":["fft_share_1"],"amounts":["40"]}]}"#,
+                r#"EVENT_JSON:{"standard":"fluxus","version":"1.0.0","event":"withdraw","data":[{"account_id":"user.near","seed_id":"exchange.near@0","amount":"40","fft_shares":"40"}]}"#,
+            ]
+        );
+    }
 }
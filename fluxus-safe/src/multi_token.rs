@@ -0,0 +1,699 @@
+use crate::*;
+use near_sdk::json_types::Base64VecU8;
+
+/// NEP-245 multi-token interface over the fft_shares, each fft_share is a token_id
+pub type TokenId = String;
+
+pub const MT_METADATA_SPEC: &str = "mt-1.0.0";
+
+/// Gas kept by mt_batch_transfer_call, besides the gas given to mt_resolve_transfer
+pub const GAS_FOR_MT_TRANSFER_CALL: Gas = Gas(25_000_000_000_000);
+pub const GAS_FOR_MT_RESOLVE_TRANSFER: Gas = Gas(10_000_000_000_000);
+pub const GAS_FOR_MT_RESOLVE_TRANSFER_PER_TOKEN: Gas = Gas(5_000_000_000_000);
+
+/// fft_shares are fungible, so tokens have no owner
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Token {
+    pub token_id: TokenId,
+    pub owner_id: Option<AccountId>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MTContractMetadata {
+    pub spec: String,
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MTBaseTokenMetadata {
+    pub name: String,
+    pub id: String,
+    pub symbol: Option<String>,
+    pub icon: Option<String>,
+    pub decimals: Option<String>,
+    pub base_uri: Option<String>,
+    pub reference: Option<String>,
+    pub copies: Option<u64>,
+    pub reference_hash: Option<Base64VecU8>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MTTokenMetadata {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub media: Option<String>,
+    pub media_hash: Option<Base64VecU8>,
+    pub issued_at: Option<String>,
+    pub expires_at: Option<String>,
+    pub starts_at: Option<String>,
+    pub updated_at: Option<String>,
+    pub extra: Option<String>,
+    pub reference: Option<String>,
+    pub reference_hash: Option<Base64VecU8>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MTTokenMetadataAll {
+    pub base: MTBaseTokenMetadata,
+    pub token: MTTokenMetadata,
+}
+
+#[ext_contract(ext_mt_receiver)]
+pub trait MultiTokenReceiver {
+    fn mt_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        previous_owner_ids: Vec<AccountId>,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+        msg: String,
+    ) -> PromiseOrValue<Vec<U128>>;
+}
+
+#[ext_contract(ext_mt_resolver)]
+trait MultiTokenResolver {
+    fn mt_resolve_transfer(
+        &mut self,
+        previous_owner_ids: Vec<AccountId>,
+        receiver_id: AccountId,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+    ) -> Vec<U128>;
+}
+
+#[near_bindgen]
+impl Contract {
+    #[payable]
+    pub fn mt_transfer(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        amount: U128,
+        approval: Option<(AccountId, u64)>,
+        memo: Option<String>,
+    ) {
+        self.mt_batch_transfer(
+            receiver_id,
+            vec![token_id],
+            vec![amount],
+            approval.map(|approval| vec![Some(approval)]),
+            memo,
+        )
+    }
+
+    #[payable]
+    pub fn mt_batch_transfer(
+        &mut self,
+        receiver_id: AccountId,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+        approvals: Option<Vec<Option<(AccountId, u64)>>>,
+        memo: Option<String>,
+    ) {
+        assert_one_yocto();
+        self.assert_contract_running();
+        assert_no_approvals(&approvals);
+
+        let sender_id = env::predecessor_account_id();
+        self.internal_mt_batch_transfer(
+            &sender_id,
+            &receiver_id,
+            &token_ids,
+            &amounts,
+            memo.as_deref(),
+        );
+    }
+
+    #[payable]
+    pub fn mt_transfer_call(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        amount: U128,
+        approval: Option<(AccountId, u64)>,
+        memo: Option<String>,
+        msg: String,
+    ) -> PromiseOrValue<Vec<U128>> {
+        self.mt_batch_transfer_call(
+            receiver_id,
+            vec![token_id],
+            vec![amount],
+            approval.map(|approval| vec![Some(approval)]),
+            memo,
+            msg,
+        )
+    }
+
+    /// Transfers the fft_shares and calls mt_on_transfer on receiver_id.
+    /// The amounts returned as unused by the receiver are refunded in mt_resolve_transfer.
+    #[payable]
+    pub fn mt_batch_transfer_call(
+        &mut self,
+        receiver_id: AccountId,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+        approvals: Option<Vec<Option<(AccountId, u64)>>>,
+        memo: Option<String>,
+        msg: String,
+    ) -> PromiseOrValue<Vec<U128>> {
+        assert_one_yocto();
+        self.assert_contract_running();
+        assert_no_approvals(&approvals);
+
+        let gas_for_resolve = Gas(GAS_FOR_MT_RESOLVE_TRANSFER.0
+            + GAS_FOR_MT_RESOLVE_TRANSFER_PER_TOKEN.0 * token_ids.len() as u64);
+        require!(
+            env::prepaid_gas() > GAS_FOR_MT_TRANSFER_CALL + gas_for_resolve,
+            "ERR: more gas is required"
+        );
+
+        let sender_id = env::predecessor_account_id();
+        self.internal_mt_batch_transfer(
+            &sender_id,
+            &receiver_id,
+            &token_ids,
+            &amounts,
+            memo.as_deref(),
+        );
+
+        let previous_owner_ids = vec![sender_id.clone(); token_ids.len()];
+
+        ext_mt_receiver::mt_on_transfer(
+            sender_id,
+            previous_owner_ids.clone(),
+            token_ids.clone(),
+            amounts.clone(),
+            msg,
+            receiver_id.clone(),
+            NO_DEPOSIT,
+            env::prepaid_gas() - GAS_FOR_MT_TRANSFER_CALL - gas_for_resolve,
+        )
+        .then(ext_mt_resolver::mt_resolve_transfer(
+            previous_owner_ids,
+            receiver_id,
+            token_ids,
+            amounts,
+            env::current_account_id(),
+            NO_DEPOSIT,
+            gas_for_resolve,
+        ))
+        .into()
+    }
+
+    /// Refunds the unused amounts returned by mt_on_transfer to the previous owners,
+    /// limited to what receiver_id still holds.
+    /// Returns the amounts kept by receiver_id.
+    #[private]
+    pub fn mt_resolve_transfer(
+        &mut self,
+        previous_owner_ids: Vec<AccountId>,
+        receiver_id: AccountId,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+    ) -> Vec<U128> {
+        let unused_amounts: Vec<U128> = match env::promise_result(0) {
+            PromiseResult::NotReady => unreachable!(),
+            PromiseResult::Successful(value) => {
+                near_sdk::serde_json::from_slice::<Vec<U128>>(&value)
+                    .ok()
+                    .filter(|unused_amounts| unused_amounts.len() == amounts.len())
+                    .unwrap_or_else(|| amounts.clone())
+            }
+            PromiseResult::Failed => amounts.clone(),
+        };
+
+        let mut used_amounts: Vec<U128> = Vec::with_capacity(amounts.len());
+
+        for (i, token_id) in token_ids.iter().enumerate() {
+            let amount = amounts[i].0;
+            let unused = std::cmp::min(amount, unused_amounts[i].0);
+            let receiver_balance =
+                self.users_fft_share_amount(token_id.clone(), receiver_id.clone());
+            let refund = std::cmp::min(unused, receiver_balance);
+
+            if refund > 0 {
+                self.internal_share_transfer(
+                    token_id,
+                    &receiver_id,
+                    &previous_owner_ids[i],
+                    refund,
+                );
+
+                MtEvent::MtTransfer(&[MtTransferData {
+                    authorized_id: None,
+                    old_owner_id: &receiver_id,
+                    new_owner_id: &previous_owner_ids[i],
+                    token_ids: &[token_id],
+                    amounts: &[U128(refund)],
+                    memo: Some("refund"),
+                }])
+                .emit();
+            }
+
+            used_amounts.push(U128(amount - refund));
+        }
+
+        used_amounts
+    }
+
+    /// Returns None for the token_ids that are not a fft_share
+    pub fn mt_token(&self, token_ids: Vec<TokenId>) -> Vec<Option<Token>> {
+        token_ids
+            .into_iter()
+            .map(|token_id| {
+                self.mt_supply(token_id.clone()).map(|_| Token {
+                    token_id,
+                    owner_id: None,
+                })
+            })
+            .collect()
+    }
+
+    pub fn mt_balance_of(&self, account_id: AccountId, token_id: TokenId) -> U128 {
+        U128(self.users_fft_share_amount(token_id, account_id))
+    }
+
+    pub fn mt_batch_balance_of(&self, account_id: AccountId, token_ids: Vec<TokenId>) -> Vec<U128> {
+        token_ids
+            .into_iter()
+            .map(|token_id| self.mt_balance_of(account_id.clone(), token_id))
+            .collect()
+    }
+
+    pub fn mt_supply(&self, token_id: TokenId) -> Option<U128> {
+        self.data()
+            .total_supply_by_fft_share
+            .get(&token_id)
+            .map(U128)
+    }
+
+    pub fn mt_batch_supply(&self, token_ids: Vec<TokenId>) -> Vec<Option<U128>> {
+        token_ids
+            .into_iter()
+            .map(|token_id| self.mt_supply(token_id))
+            .collect()
+    }
+
+    pub fn mt_metadata_contract(&self) -> MTContractMetadata {
+        MTContractMetadata {
+            spec: MT_METADATA_SPEC.to_string(),
+            name: "Fluxus vault shares".to_string(),
+        }
+    }
+
+    pub fn mt_metadata_token_all(
+        &self,
+        token_ids: Vec<TokenId>,
+    ) -> Vec<Option<MTTokenMetadataAll>> {
+        token_ids
+            .iter()
+            .map(|token_id| {
+                let seed_id = self.seed_id_by_fft_share(token_id)?;
+
+                Some(MTTokenMetadataAll {
+                    base: self.internal_mt_base_metadata(token_id, &seed_id),
+                    token: internal_mt_token_metadata(&seed_id),
+                })
+            })
+            .collect()
+    }
+
+    pub fn mt_metadata_token_by_token_id(
+        &self,
+        token_ids: Vec<TokenId>,
+    ) -> Vec<Option<MTTokenMetadata>> {
+        token_ids
+            .iter()
+            .map(|token_id| {
+                self.seed_id_by_fft_share(token_id)
+                    .map(|seed_id| internal_mt_token_metadata(&seed_id))
+            })
+            .collect()
+    }
+
+    pub fn mt_metadata_base_by_token_id(
+        &self,
+        token_ids: Vec<TokenId>,
+    ) -> Vec<Option<MTBaseTokenMetadata>> {
+        token_ids
+            .iter()
+            .map(|token_id| {
+                self.seed_id_by_fft_share(token_id)
+                    .map(|seed_id| self.internal_mt_base_metadata(token_id, &seed_id))
+            })
+            .collect()
+    }
+
+    /// Every fft_share has its own base metadata, identified by the token_id
+    pub fn mt_metadata_base_by_metadata_id(
+        &self,
+        base_metadata_ids: Vec<String>,
+    ) -> Vec<Option<MTBaseTokenMetadata>> {
+        self.mt_metadata_base_by_token_id(base_metadata_ids)
+    }
+}
+
+impl Contract {
+    pub(crate) fn internal_mt_batch_transfer(
+        &mut self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        token_ids: &[TokenId],
+        amounts: &[U128],
+        memo: Option<&str>,
+    ) {
+        require!(!token_ids.is_empty(), "ERR: no token_ids to transfer");
+        require!(
+            token_ids.len() == amounts.len(),
+            "ERR: token_ids and amounts must have the same length"
+        );
+
+        for (token_id, amount) in token_ids.iter().zip(amounts) {
+            require!(amount.0 > 0, "ERR: amount must be positive");
+            self.internal_share_transfer(token_id, sender_id, receiver_id, amount.0);
+        }
+
+        let token_ids: Vec<&str> = token_ids.iter().map(|token_id| token_id.as_str()).collect();
+
+        MtEvent::MtTransfer(&[MtTransferData {
+            authorized_id: None,
+            old_owner_id: sender_id,
+            new_owner_id: receiver_id,
+            token_ids: &token_ids,
+            amounts,
+            memo,
+        }])
+        .emit();
+    }
+
+    /// Returns the seed_id that mints fft_share, if any
+    pub(crate) fn seed_id_by_fft_share(&self, fft_share: &str) -> Option<String> {
+        self.data().seed_id_by_fft_share.get(&fft_share.to_string())
+    }
+
+    fn internal_mt_base_metadata(&self, token_id: &str, seed_id: &str) -> MTBaseTokenMetadata {
+        // fft_shares are minted 1:1 with the first deposit, exchange LP shares have 24 decimals.
+        // The decimals of pembrock tokens are not stored in the strategy.
+        let decimals = self
+            .data()
+            .strategies
+            .get(&seed_id.to_string())
+            .filter(|strat| strat.kind() != "PEMBROCK")
+            .map(|_| "24".to_string());
+
+        MTBaseTokenMetadata {
+            name: format!("Fluxus {}", seed_id),
+            id: token_id.to_string(),
+            symbol: Some("FFT".to_string()),
+            icon: None,
+            decimals,
+            base_uri: None,
+            reference: None,
+            copies: None,
+            reference_hash: None,
+        }
+    }
+}
+
+fn internal_mt_token_metadata(seed_id: &str) -> MTTokenMetadata {
+    MTTokenMetadata {
+        title: Some(format!("Fluxus {} share", seed_id)),
+        description: Some(format!("Share of the auto-compounded {} position", seed_id)),
+        ..Default::default()
+    }
+}
+
+/// Approvals are not supported, only the owner can transfer its fft_shares
+fn assert_no_approvals(approvals: &Option<Vec<Option<(AccountId, u64)>>>) {
+    if let Some(approvals) = approvals {
+        require!(
+            approvals.iter().all(Option::is_none),
+            "ERR: approvals are not supported"
+        );
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use near_sdk::test_utils::get_logs;
+    use near_sdk::{testing_env, RuntimeFeesConfig, VMConfig};
+    use std::collections::HashMap;
+
+    /// Registers fft_share_1 for a ref strategy and fft_share_2 for a pembrock strategy,
+    /// user.near holds 100 of each
+    fn create_contract() -> Contract {
+        testing_env!(get_context("auto_compounder.near").build());
+        let mut contract = create_safe();
+
+        let ref_strat = VersionedStrategy::AutoCompounder(create_compounder(SEED_ID, 1_000));
+        let pemb_strat = VersionedStrategy::PembrockAutoCompounder(PembrockAutoCompounder::new(
+            10,
+            AccountFee::new(to_account_id("creator.near"), 5),
+            1,
+            to_account_id("exchange.near"),
+            to_account_id("pembrock.near"),
+            to_account_id("reward.pembrock.near"),
+            to_account_id("token.near"),
+            1,
+            to_account_id("pem.near"),
+        ));
+
+        for (seed_id, fft_share, strat) in [
+            (SEED_ID, "fft_share_1", ref_strat),
+            ("pembrock@token.near", "fft_share_2", pemb_strat),
+        ] {
+            register_strategy(&mut contract, seed_id, fft_share, strat, 0);
+            contract.mft_mint(fft_share.to_string(), 100, to_account_id("user.near"));
+        }
+
+        contract
+    }
+
+    fn events() -> Vec<String> {
+        get_logs()
+            .into_iter()
+            .filter(|log| log.starts_with("EVENT_JSON:"))
+            .collect()
+    }
+
+    #[test]
+    fn test_balances_and_supply() {
+        let contract = create_contract();
+
+        assert_eq!(
+            contract.mt_balance_of(to_account_id("user.near"), "fft_share_1".to_string()),
+            U128(100)
+        );
+        assert_eq!(
+            contract.mt_batch_balance_of(
+                to_account_id("other.near"),
+                vec!["fft_share_1".to_string(), "fft_share_3".to_string()]
+            ),
+            vec![U128(0), U128(0)]
+        );
+        assert_eq!(
+            contract.mt_batch_supply(vec!["fft_share_2".to_string(), "fft_share_3".to_string()]),
+            vec![Some(U128(100)), None]
+        );
+        assert_eq!(
+            contract.mt_token(vec!["fft_share_1".to_string(), "fft_share_3".to_string()]),
+            vec![
+                Some(Token {
+                    token_id: "fft_share_1".to_string(),
+                    owner_id: None
+                }),
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn test_metadata() {
+        let contract = create_contract();
+
+        assert_eq!(contract.mt_metadata_contract().spec, "mt-1.0.0");
+
+        let metadata = contract.mt_metadata_token_all(vec![
+            "fft_share_1".to_string(),
+            "fft_share_2".to_string(),
+            "fft_share_3".to_string(),
+        ]);
+
+        let ref_metadata = metadata[0].clone().unwrap();
+        assert_eq!(ref_metadata.base.id, "fft_share_1");
+        assert_eq!(ref_metadata.base.name, "Fluxus exchange.near@1");
+        assert_eq!(ref_metadata.base.decimals, Some("24".to_string()));
+        assert_eq!(
+            ref_metadata.token.title,
+            Some("Fluxus exchange.near@1 share".to_string())
+        );
+
+        let pemb_metadata = metadata[1].clone().unwrap();
+        assert_eq!(pemb_metadata.base.name, "Fluxus pembrock@token.near");
+        assert_eq!(pemb_metadata.base.decimals, None);
+
+        assert_eq!(metadata[2], None);
+        assert_eq!(
+            contract.mt_metadata_base_by_metadata_id(vec!["fft_share_1".to_string()]),
+            vec![Some(ref_metadata.base)]
+        );
+    }
+
+    #[test]
+    fn test_batch_transfer() {
+        let mut contract = create_contract();
+        testing_env!(get_context("user.near").build());
+
+        contract.mt_batch_transfer(
+            to_account_id("other.near"),
+            vec!["fft_share_1".to_string(), "fft_share_2".to_string()],
+            vec![U128(40), U128(60)],
+            None,
+            None,
+        );
+
+        assert_eq!(
+            contract.mt_batch_balance_of(
+                to_account_id("user.near"),
+                vec!["fft_share_1".to_string(), "fft_share_2".to_string()]
+            ),
+            vec![U128(60), U128(40)]
+        );
+        assert_eq!(
+            contract.mt_batch_balance_of(
+                to_account_id("other.near"),
+                vec!["fft_share_1".to_string(), "fft_share_2".to_string()]
+            ),
+            vec![U128(40), U128(60)]
+        );
+        // supply does not change on transfers
+        assert_eq!(
+            contract.mt_supply("fft_share_1".to_string()),
+            Some(U128(100))
+        );
+
+        assert_eq!(
+            events().last().unwrap(),
+            r#"EVENT_JSON:{"standard":"nep245","version":"1.0.0","event":"mt_transfer","data":[{"old_owner_id":"user.near","new_owner_id":"other.near","token_ids":["fft_share_1","fft_share_2"],"amounts":["40","60"]}]}"#
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR: approvals are not supported")]
+    fn test_transfer_with_approval() {
+        let mut contract = create_contract();
+        testing_env!(get_context("user.near").build());
+
+        contract.mt_transfer(
+            to_account_id("other.near"),
+            "fft_share_1".to_string(),
+            U128(1),
+            Some((to_account_id("user.near"), 0)),
+            None,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR: token_ids and amounts must have the same length")]
+    fn test_batch_transfer_length_mismatch() {
+        let mut contract = create_contract();
+        testing_env!(get_context("user.near").build());
+
+        contract.mt_batch_transfer(
+            to_account_id("other.near"),
+            vec!["fft_share_1".to_string(), "fft_share_2".to_string()],
+            vec![U128(1)],
+            None,
+            None,
+        );
+    }
+
+    #[test]
+    fn test_resolve_transfer_refunds_unused() {
+        let mut contract = create_contract();
+        testing_env!(get_context("user.near").build());
+
+        contract.mt_batch_transfer(
+            to_account_id("receiver.near"),
+            vec!["fft_share_1".to_string(), "fft_share_2".to_string()],
+            vec![U128(50), U128(50)],
+            None,
+            None,
+        );
+
+        // receiver.near keeps everything of fft_share_1 and returns 20 of fft_share_2
+        testing_env!(
+            get_context("auto_compounder.near").build(),
+            VMConfig::test(),
+            RuntimeFeesConfig::test(),
+            HashMap::default(),
+            vec![PromiseResult::Successful(
+                near_sdk::serde_json::to_vec(&vec![U128(0), U128(20)]).unwrap()
+            )],
+        );
+
+        let used = contract.mt_resolve_transfer(
+            vec![to_account_id("user.near"), to_account_id("user.near")],
+            to_account_id("receiver.near"),
+            vec!["fft_share_1".to_string(), "fft_share_2".to_string()],
+            vec![U128(50), U128(50)],
+        );
+
+        assert_eq!(used, vec![U128(50), U128(30)]);
+        assert_eq!(
+            contract.mt_batch_balance_of(
+                to_account_id("user.near"),
+                vec!["fft_share_1".to_string(), "fft_share_2".to_string()]
+            ),
+            vec![U128(50), U128(70)]
+        );
+        assert_eq!(
+            events(),
+            vec![
+                r#"EVENT_JSON:{"standard":"nep245","version":"1.0.0","event":"mt_transfer","data":[{"old_owner_id":"receiver.near","new_owner_id":"user.near","token_ids":["fft_share_2"],"amounts":["20"],"memo":"refund"}]}"#
+            ]
+        );
+    }
+
+    #[test]
+    fn test_resolve_transfer_failed_call() {
+        let mut contract = create_contract();
+        testing_env!(get_context("user.near").build());
+
+        contract.mt_transfer(
+            to_account_id("receiver.near"),
+            "fft_share_1".to_string(),
+            U128(50),
+            None,
+            None,
+        );
+
+        testing_env!(
+            get_context("auto_compounder.near").build(),
+            VMConfig::test(),
+            RuntimeFeesConfig::test(),
+            HashMap::default(),
+            vec![PromiseResult::Failed],
+        );
+
+        let used = contract.mt_resolve_transfer(
+            vec![to_account_id("user.near")],
+            to_account_id("receiver.near"),
+            vec!["fft_share_1".to_string()],
+            vec![U128(50)],
+        );
+
+        assert_eq!(used, vec![U128(0)]);
+        assert_eq!(
+            contract.mt_balance_of(to_account_id("user.near"), "fft_share_1".to_string()),
+            U128(100)
+        );
+    }
+}
@@ -6,6 +6,12 @@ impl Contract {
     pub fn update_contract_state(&mut self, state: RunningState) -> String {
         self.is_owner();
         self.data_mut().state = state;
+
+        Event::ContractStateUpdated(&[ContractStateUpdatedData {
+            state: self.data().state.to_string(),
+        }])
+        .emit();
+
         format!("{} is {:#?}", env::current_account_id(), self.data().state)
     }
 
@@ -16,22 +22,54 @@ impl Contract {
 
     /// Returns allowed_accounts
     pub fn get_allowed_accounts(&self) -> Vec<AccountId> {
-        self.is_owner_or_guardians();
+        assert!(self.is_owner_or_guardians(), "ERR: not allowed");
         self.data().allowed_accounts.clone()
     }
 
-    /// Returns all strategies without filtering
-    pub fn get_strats_info(self) -> Vec<VersionedStrategy> {
-        self.is_owner_or_guardians();
+    /// Returns strategies without filtering
+    /// Args:
+    ///   from_index: position of the first strategy, defaults to 0
+    ///   limit: max number of strategies returned, defaults to all
+    pub fn get_strats_info(
+        self,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<VersionedStrategy> {
+        assert!(self.is_owner_or_guardians(), "ERR: not allowed");
 
-        let mut info: Vec<VersionedStrategy> = Vec::new();
+        self.get_seed_ids(from_index, limit)
+            .iter()
+            .map(|seed_id| self.get_strat(seed_id))
+            .collect()
+    }
+
+    /// Upgrades stored strategies to their latest layout, in batches to fit the gas limit.
+    /// Strategies are upgraded anyway on their first mutable access.
+    /// Args:
+    ///   from_index: position of the first strategy
+    ///   limit: max number of strategies to check
+    /// Returns the number of strategies upgraded
+    pub fn upgrade_strategies(&mut self, from_index: u64, limit: u64) -> u64 {
+        assert!(self.is_owner_or_guardians(), "ERR: not allowed");
 
-        // TODO: should exist a `get_strategies` and upgrade everything at once if so?
-        for (_, strat) in self.data().strategies.clone() {
-            info.push(strat);
+        let mut upgraded: u64 = 0;
+
+        for seed_id in self.get_seed_ids(Some(from_index), Some(limit)) {
+            let strat = self
+                .data()
+                .strategies
+                .get(&seed_id)
+                .expect(ERR21_TOKEN_NOT_REG);
+
+            if strat.need_upgrade() {
+                self.save_strat(&seed_id, &strat.upgrade());
+                upgraded += 1;
+            }
         }
 
-        info
+        log!("Upgraded {} strategies", upgraded);
+
+        upgraded
     }
 
     /// Args:
@@ -42,18 +80,18 @@ impl Contract {
         farm_id_str: String,
         state: AutoCompounderState,
     ) -> String {
-        self.is_owner_or_guardians();
+        assert!(self.is_owner_or_guardians(), "ERR: not allowed");
 
         let (seed_id, _, farm_id) = get_ids_from_farm(farm_id_str);
-        // TODO: stable version
-        let compounder_mut = self.get_strat_mut(&seed_id).get_compounder_mut();
-        let farm_info_mut = compounder_mut.get_mut_farm_info(farm_id);
+        self.assert_not_emergency_exited(&seed_id);
 
-        if farm_info_mut.state != state {
-            farm_info_mut.state = state;
-        }
+        let mut strat = self.get_strat(&seed_id);
+        let current_state = strat.strategy_mut().set_farm_state(&farm_id, state);
+        self.save_strat(&seed_id, &strat);
+
+        emit_farm_state_updated(&seed_id, &farm_id, current_state.clone());
 
-        format!("The current state is {:#?}", farm_info_mut.state)
+        format!("The current state is {}", current_state)
     }
 
     /// Extend guardians. Only can be called by owner.
@@ -95,15 +133,13 @@ impl Contract {
         // Should not accept, say, 0 slippage
         let (seed_id, _, farm_id) = get_ids_from_farm(farm_id_str);
 
-        // TODO: stable versions
-        let compounder_mut = self.get_strat_mut(&seed_id).get_compounder_mut();
-        let farm_info_mut = compounder_mut.get_mut_farm_info(farm_id);
-        farm_info_mut.slippage = 100 - new_slippage;
+        let mut strat = self.get_strat(&seed_id);
+        let slippage = strat
+            .strategy_mut()
+            .set_farm_slippage(&farm_id, 100 - new_slippage);
+        self.save_strat(&seed_id, &strat);
 
-        format!(
-            "The current slippage for {} is {}",
-            seed_id, farm_info_mut.slippage
-        )
+        format!("The current slippage for {} is {}", seed_id, slippage)
     }
 
     /// Adds account_id to allowed_accounts if it is not already present
@@ -182,8 +218,9 @@ impl Contract {
         }
     }
 
+    /// Returns the code version followed by the layout version of the stored data
     pub fn contract_version(&self) -> String {
-        String::from(env!("CARGO_PKG_VERSION"))
+        format!("{} ({})", env!("CARGO_PKG_VERSION"), self.data.version())
     }
 
     // TODO: REMOVE
@@ -3,6 +3,8 @@ use crate::*;
 #[near_bindgen]
 impl Contract {
     // TODO: move to actions_of_pembrock
+    /// Mints fft_shares for the amount lent to pembrock.
+    /// Returns the unused amount, which is refunded to the depositor by the token.
     #[private]
     pub fn callback_pembrock_stake_result(
         &mut self,
@@ -10,43 +12,53 @@ impl Contract {
         seed_id: String,
         account_id: AccountId,
         shares: u128,
-    ) -> String {
-        if let Ok(amount) = transfer_result {
-            assert_ne!(amount.0, 0, "ERR_STAKE_FAILED");
-        }
-
-        //Total fft_share
-        let total_fft = self.total_supply_by_pool_id(seed_id.clone());
-
-        let fft_share_id = self.get_fft_share_id_from_seed(seed_id.clone());
-
-        let data = self.data_mut();
-
-        //Total seed_id
-        let total_seed = data.seed_id_amount.get(&seed_id).unwrap_or_default();
-
-        self.data_mut()
-            .seed_id_amount
-            .insert(&seed_id, &(total_seed + shares));
-
-        let fft_share_amount = if total_fft == 0 {
-            shares
-        } else {
-            (U256::from(shares) * U256::from(total_fft) / U256::from(total_seed)).as_u128()
+    ) -> U128 {
+        // ft_transfer_call returns the amount used by pembrock
+        let unused = match transfer_result {
+            Ok(amount) => shares - std::cmp::min(amount.0, shares),
+            Err(_) => shares,
         };
 
-        log!(
-            "{} {} will be minted for {}",
-            fft_share_amount,
-            fft_share_id,
-            account_id.to_string()
+        self.internal_resolve_stake(seed_id, account_id, shares, unused)
+    }
+
+    /// Sends the tokens withdrawn from pembrock to receiver_id.
+    /// If the transfer fails, the tokens are lent again and the fft_shares are kept.
+    #[private]
+    pub fn callback_pembrock_post_withdraw(
+        &mut self,
+        #[callback_result] withdraw_result: Result<U128, PromiseError>,
+        strat_name: String,
+        receiver_id: AccountId,
+        amount: u128,
+        fft_shares: u128,
+    ) -> Promise {
+        assert!(
+            withdraw_result.is_ok(),
+            "ERR: failed to withdraw {} from pembrock",
+            amount
         );
-        self.mft_mint(fft_share_id, fft_share_amount, account_id.to_string());
 
-        format!(
-            "The {} added {} to {}",
-            account_id, fft_share_amount, seed_id
+        let compounder = self.pemb_get_strat(&strat_name).pemb_get();
+
+        ext_reward_token::ft_transfer(
+            receiver_id.clone(),
+            U128(amount),
+            Some("".to_string()),
+            compounder.token_address,
+            1,
+            Gas(20_000_000_000_000),
         )
+        .then(callback_ref_finance::callback_withdraw_shares(
+            strat_name,
+            receiver_id,
+            amount,
+            fft_shares,
+            amount,
+            env::current_account_id(),
+            0,
+            Gas(70_000_000_000_000),
+        ))
     }
 
     #[private]
@@ -62,17 +74,25 @@ impl Contract {
 
         assert!(claimed > 0, "ERR: claimed zero amount for {}", strat_name);
 
-        let data_mut = self.data_mut();
-
-        let strat = data_mut
-            .strategies
-            .get_mut(&strat_name)
-            .expect(ERR21_TOKEN_NOT_REG);
+        let mut strat = self.get_strat(&strat_name);
 
         let compounder = strat.pemb_get_mut();
 
         let (remaining_amount, protocol_amount, sentry_amount, strat_creator_amount) =
-            compounder.compute_fees(claimed);
+            self.internal_compute_fees(&strat_name, &compounder.admin_fees, claimed);
+
+        emit_harvest_fees(
+            &strat_name,
+            None,
+            &compounder.reward_token,
+            claimed,
+            (
+                remaining_amount,
+                protocol_amount,
+                sentry_amount,
+                strat_creator_amount,
+            ),
+        );
 
         compounder.last_reward_amount += remaining_amount;
 
@@ -85,15 +105,19 @@ impl Contract {
             .insert(env::current_account_id(), sentry_amount);
 
         // increase protocol amount to cover the case that the last transfer failed
-        data_mut.treasury.current_amount += protocol_amount;
+        self.data_mut().treasury.current_amount += protocol_amount;
 
-        compounder.next_cycle();
+        compounder.next_cycle(&strat_name);
         log!(
             "last_reward_amount for {}: {}",
             strat_name,
             compounder.last_reward_amount
         );
 
+        self.save_strat(&strat_name, &strat);
+
+        let compounder = strat.pemb_get_ref();
+
         if protocol_amount > 0 {
             ext_reward_token::ft_transfer(
                 compounder.admin_fees.strat_creator.account_id.clone(),
@@ -156,11 +180,14 @@ impl Contract {
     ) {
         match transfer_result {
             Ok(_) => {
-                let compounder = self.pemb_get_strat_mut(&strat_name).pemb_get_mut();
+                let mut strat = self.get_strat(&strat_name);
+                let compounder = strat.pemb_get_mut();
 
                 // reset strat creator fees after successful transfer
                 compounder.admin_fees.strat_creator.current_amount = 0;
 
+                self.save_strat(&strat_name, &strat);
+
                 log!("Transfer to strategy creator succeeded")
             }
             Err(_) => {
@@ -199,7 +226,9 @@ impl Contract {
             ),
         }
 
-        let compounder = self.get_strat_mut(&strat_name).pemb_get_mut();
+        let mut strat = self.get_strat(&strat_name);
+
+        let compounder = strat.pemb_get_mut();
 
         // reset default sentry address and get last earned amount
         let amount = compounder
@@ -208,6 +237,10 @@ impl Contract {
             .remove(&env::current_account_id())
             .unwrap();
 
+        self.save_strat(&strat_name, &strat);
+
+        let compounder = strat.pemb_get_ref();
+
         log!("Sending {} to sentry account {}", amount, sentry_acc_id);
 
         if amount > 0 {
@@ -260,13 +293,17 @@ impl Contract {
         if ft_transfer_result.is_err() {
             log!("Transfer to sentry failed".to_string());
 
-            let compounder = self.get_strat_mut(&strat_name).pemb_get_mut();
+            let mut strat = self.get_strat(&strat_name);
+
+            let compounder = strat.pemb_get_mut();
 
             // store amount earned by sentry to be redeemed
             compounder
                 .admin_fees
                 .sentries
                 .insert(sentry_id, amount_earned);
+
+            self.save_strat(&strat_name, &strat);
         } else {
             log!("Transfer to sentry succeeded".to_string());
         }
@@ -312,7 +349,7 @@ impl Contract {
         &mut self,
         #[callback_result] swap_result: Result<U128, PromiseError>,
         strat_name: String,
-    ) -> Promise {
+    ) -> PromiseOrValue<U128> {
         assert!(swap_result.is_ok(), "ERR: failed to swap");
 
         let amount_to_transfer = swap_result.unwrap();
@@ -324,28 +361,42 @@ impl Contract {
             .seed_id_amount
             .insert(&strat_name, &(total_seed_amount + amount_to_transfer.0));
 
-        let strat = self.pemb_get_strat_mut(&strat_name);
+        self.internal_accrue_management_fee(&strat_name);
+        self.internal_charge_performance_fee(&strat_name);
+
+        // harvest started before an emergency exit, the tokens are held by the contract
+        if self.is_emergency_exited(&strat_name) {
+            return PromiseOrValue::Value(amount_to_transfer);
+        }
+
+        let mut strat = self.get_strat(&strat_name);
 
         let compounder = strat.pemb_get_mut();
 
         // after the swap, there's no more reward available to swap
         compounder.last_reward_amount = 0;
 
-        ext_pembrock::ft_transfer_call(
-            compounder.pembrock_contract_id.clone(),
-            amount_to_transfer,
-            "deposit".to_string(),
-            compounder.token_address.clone(),
-            1,
-            Gas(40_000_000_000_000),
+        self.save_strat(&strat_name, &strat);
+
+        let compounder = strat.pemb_get_ref();
+
+        PromiseOrValue::Promise(
+            ext_pembrock::ft_transfer_call(
+                compounder.pembrock_contract_id.clone(),
+                amount_to_transfer,
+                "deposit".to_string(),
+                compounder.token_address.clone(),
+                1,
+                Gas(40_000_000_000_000),
+            )
+            .then(callback_pembrock::callback_pembrock_post_lend(
+                strat_name,
+                amount_to_transfer.0,
+                env::current_account_id(),
+                0,
+                Gas(10_000_000_000_000),
+            )),
         )
-        .then(callback_pembrock::callback_pembrock_post_lend(
-            strat_name,
-            amount_to_transfer.0,
-            env::current_account_id(),
-            0,
-            Gas(10_000_000_000_000),
-        ))
     }
 
     #[private]
@@ -355,91 +406,172 @@ impl Contract {
         strat_name: String,
         amount: u128,
     ) {
-        let strat = self.pemb_get_strat_mut(&strat_name);
+        let mut strat = self.get_strat(&strat_name);
 
         let compounder = strat.pemb_get_mut();
 
-        if let Ok(_amount) = post_lend_result {
-            compounder.harvest_value_available_to_stake = 0;
-        } else {
-            compounder.harvest_value_available_to_stake += amount;
+        let lent = match post_lend_result {
+            Ok(used) => {
+                compounder.harvest_value_available_to_stake = 0;
+                Some(std::cmp::min(used.0, amount))
+            }
+            Err(_) => {
+                compounder.harvest_value_available_to_stake += amount;
+                None
+            }
+        };
+
+        self.save_strat(&strat_name, &strat);
+
+        if let Some(lent) = lent {
+            self.record_share_price(&strat_name, lent);
         }
     }
 
+    /// Kept for backwards compatibility, pembrock strategies are unstaked through `unstake`
     pub fn pembrock_unstake(
         &mut self,
         token_address: String,
         amount_withdrawal: Option<U128>,
     ) -> Promise {
-        let (caller_id, contract_id) = get_predecessor_and_current_account();
+        self.unstake(format!("pembrock@{}", token_address), amount_withdrawal)
+    }
+}
 
-        let seed_id: String = format!("pembrock@{}", token_address);
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::testing_env;
+
+    const STRAT_NAME: &str = "pembrock@token.near";
+
+    fn get_context() -> VMContextBuilder {
+        let mut builder = VMContextBuilder::new();
+        builder
+            .current_account_id(to_account_id("auto_compounder.near"))
+            .signer_account_id(to_account_id("auto_compounder.near"))
+            .predecessor_account_id(to_account_id("auto_compounder.near"));
+        builder
+    }
 
-        let fft_share_id = self.get_fft_share_id_from_seed(seed_id.clone());
-        let mut user_fft_shares =
-            self.users_fft_share_amount(fft_share_id.clone(), caller_id.to_string());
+    pub fn to_account_id(value: &str) -> AccountId {
+        value.parse().unwrap()
+    }
 
-        //Total fft_share
-        let total_fft = self.total_supply_amount(fft_share_id);
+    fn create_contract() -> Contract {
+        let mut contract = Contract::new(
+            to_account_id("auto_compounder.near"),
+            to_account_id("treasure.near"),
+        );
 
-        //Total seed_id
-        let total_seed = self.seed_total_amount(&seed_id);
+        let strat = VersionedStrategy::PembrockAutoCompounder(PembrockAutoCompounder::new(
+            10,
+            AccountFee::new(to_account_id("creator.near"), 5),
+            1,
+            to_account_id("exchange.near"),
+            to_account_id("pembrock.near"),
+            to_account_id("reward.pembrock.near"),
+            to_account_id("token.near"),
+            1,
+            to_account_id("reward.near"),
+        ));
+        contract.save_strat(STRAT_NAME, &strat);
+
+        contract.register_seed("fft_share_1".to_string());
+        contract
+            .data_mut()
+            .fft_share_by_seed_id
+            .insert(&STRAT_NAME.to_string(), &"fft_share_1".to_string());
+        contract
+            .data_mut()
+            .seed_id_by_fft_share
+            .insert(&"fft_share_1".to_string(), &STRAT_NAME.to_string());
+
+        contract
+    }
 
-        //Converting user total fft_shares in seed_id:
-        let user_shares = (U256::from(user_fft_shares) * U256::from(total_seed)
-            / U256::from(total_fft))
-        .as_u128();
+    #[test]
+    fn test_stake_result_mints_used_amount() {
+        testing_env!(get_context().build());
 
-        let strat = self
-            .data()
-            .strategies
-            .get(&seed_id)
-            .expect("ERR_TOKEN_ID_DOES_NOT_EXIST");
+        let mut contract = create_contract();
 
-        let compounder = strat.clone().pemb_get();
+        let unused = contract.callback_pembrock_stake_result(
+            Ok(U128(80)),
+            STRAT_NAME.to_string(),
+            to_account_id("user.near"),
+            100,
+        );
 
-        let amount: U128;
-        if let Some(amount_withdrawal) = amount_withdrawal {
-            amount = amount_withdrawal;
-            user_fft_shares = (U256::from(amount_withdrawal.0) * U256::from(total_fft)
-                / U256::from(total_seed))
-            .as_u128();
-        } else {
-            amount = U128(user_shares);
+        assert_eq!(unused, U128(20));
+        assert_eq!(
+            contract.users_fft_share_amount("fft_share_1".to_string(), to_account_id("user.near")),
+            80
+        );
+        assert_eq!(contract.seed_total_amount(&STRAT_NAME.to_string()), 80);
+    }
+
+    #[test]
+    fn test_stake_result_failed_transfer() {
+        testing_env!(get_context().build());
+
+        let mut contract = create_contract();
+
+        for transfer_result in [Ok(U128(0)), Err(PromiseError::Failed)] {
+            let unused = contract.callback_pembrock_stake_result(
+                transfer_result,
+                STRAT_NAME.to_string(),
+                to_account_id("user.near"),
+                100,
+            );
+            assert_eq!(unused, U128(100));
         }
-        assert!(
-            user_shares >= amount.0,
-            "{} is trying to withdrawal {} and only has {}",
-            caller_id,
-            amount.0,
-            user_shares
+
+        assert_eq!(
+            contract.users_fft_share_amount("fft_share_1".to_string(), to_account_id("user.near")),
+            0
         );
+        assert_eq!(contract.seed_total_amount(&STRAT_NAME.to_string()), 0);
+    }
 
-        log!("{} is trying to withdrawal {}", caller_id, amount.0);
+    #[test]
+    fn test_failed_withdraw_transfer_keeps_position() {
+        testing_env!(get_context().build());
 
-        ext_pembrock::withdraw(
-            compounder.token_address.clone(),
-            amount,
-            compounder.pembrock_contract_id,
-            1,
-            Gas(100_000_000_000_000),
-        )
-        .then(ext_reward_token::ft_transfer(
-            caller_id.clone(),
-            amount,
-            Some("".to_string()),
-            compounder.token_address,
-            1,
-            Gas(100_000_000_000_000),
-        ))
-        .then(callback_ref_finance::callback_withdraw_shares(
-            seed_id,
-            caller_id,
-            amount.0,
-            user_fft_shares,
-            contract_id,
-            0,
-            Gas(20_000_000_000_000),
-        ))
+        let mut contract = create_contract();
+        let user_fft_shares = |contract: &Contract| {
+            contract.users_fft_share_amount("fft_share_1".to_string(), to_account_id("user.near"))
+        };
+
+        contract.callback_pembrock_stake_result(
+            Ok(U128(100)),
+            STRAT_NAME.to_string(),
+            to_account_id("user.near"),
+            100,
+        );
+
+        // the tokens are lent again and nothing is burned
+        contract.callback_withdraw_shares(
+            Err(PromiseError::Failed),
+            STRAT_NAME.to_string(),
+            to_account_id("user.near"),
+            40,
+            40,
+            40,
+        );
+        assert_eq!(user_fft_shares(&contract), 100);
+        assert_eq!(contract.seed_total_amount(&STRAT_NAME.to_string()), 100);
+
+        contract.callback_withdraw_shares(
+            Ok(()),
+            STRAT_NAME.to_string(),
+            to_account_id("user.near"),
+            40,
+            40,
+            40,
+        );
+        assert_eq!(user_fft_shares(&contract), 60);
+        assert_eq!(contract.seed_total_amount(&STRAT_NAME.to_string()), 60);
     }
 }
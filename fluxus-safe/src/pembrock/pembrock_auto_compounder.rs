@@ -122,6 +122,16 @@ impl From<&PembAutoCompounderState> for String {
     }
 }
 
+impl From<AutoCompounderState> for PembAutoCompounderState {
+    fn from(status: AutoCompounderState) -> Self {
+        match status {
+            AutoCompounderState::Running => PembAutoCompounderState::Running,
+            AutoCompounderState::Ended => PembAutoCompounderState::Ended,
+            AutoCompounderState::Cleared => PembAutoCompounderState::Cleared,
+        }
+    }
+}
+
 #[derive(Debug, BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Clone)]
 #[serde(crate = "near_sdk::serde")]
 pub enum PembAutoCompounderCycle {
@@ -172,29 +182,6 @@ impl PembrockAutoCompounder {
         }
     }
 
-    pub(crate) fn compute_fees(&mut self, reward_amount: u128) -> (u128, u128, u128, u128) {
-        // apply fees to reward amount
-        let percent = Percentage::from(self.admin_fees.strategy_fee);
-        let all_fees_amount = percent.apply_to(reward_amount);
-
-        let percent = Percentage::from(self.admin_fees.sentries_fee);
-        let sentry_amount = percent.apply_to(all_fees_amount);
-
-        let percent = Percentage::from(self.admin_fees.strat_creator.fee_percentage);
-        let strat_creator_amount = percent.apply_to(all_fees_amount);
-        let treasury_amount = all_fees_amount - sentry_amount - strat_creator_amount;
-
-        let remaining_amount =
-            reward_amount - treasury_amount - sentry_amount - strat_creator_amount;
-
-        (
-            remaining_amount,
-            treasury_amount,
-            sentry_amount,
-            strat_creator_amount,
-        )
-    }
-
     pub fn stake_on_pembrock(
         &self,
         account_id: &AccountId,
@@ -230,6 +217,32 @@ impl PembrockAutoCompounder {
         ))
     }
 
+    /// Withdraw the lent tokens from pembrock and send it to receiver_id
+    pub(crate) fn unstake(
+        &self,
+        strat_name: String,
+        receiver_id: AccountId,
+        withdraw_amount: u128,
+        user_fft_shares: u128,
+    ) -> Promise {
+        ext_pembrock::withdraw(
+            self.token_address.clone(),
+            U128(withdraw_amount),
+            self.pembrock_contract_id.clone(),
+            1,
+            Gas(100_000_000_000_000),
+        )
+        .then(callback_pembrock::callback_pembrock_post_withdraw(
+            strat_name,
+            receiver_id,
+            withdraw_amount,
+            user_fft_shares,
+            env::current_account_id(),
+            0,
+            Gas(100_000_000_000_000),
+        ))
+    }
+
     pub fn claim_reward(&mut self, strat_name: String) -> Promise {
         ext_pembrock::claim(self.pembrock_reward_id.clone(), 1, Gas(100_000_000_000_000)).then(
             callback_pembrock::callback_pembrock_rewards(
@@ -260,7 +273,9 @@ impl PembrockAutoCompounder {
         ))
     }
 
-    pub(crate) fn next_cycle(&mut self) {
+    pub(crate) fn next_cycle(&mut self, strat_name: &str) {
+        let from = String::from(&self.cycle_stage);
+
         match self.cycle_stage {
             PembAutoCompounderCycle::ClaimReward => {
                 self.cycle_stage = PembAutoCompounderCycle::SwapAndLend
@@ -269,5 +284,174 @@ impl PembrockAutoCompounder {
                 self.cycle_stage = PembAutoCompounderCycle::ClaimReward
             }
         }
+
+        emit_harvest_step(strat_name, None, from, String::from(&self.cycle_stage));
+    }
+}
+
+impl Strategy for PembrockAutoCompounder {
+    fn kind(&self) -> String {
+        "PEMBROCK".to_string()
+    }
+
+    fn seed_min_deposit(&self) -> U128 {
+        U128(0)
+    }
+
+    fn admin_fees(&self) -> &AdminFees {
+        &self.admin_fees
+    }
+
+    fn admin_fees_mut(&mut self) -> &mut AdminFees {
+        &mut self.admin_fees
+    }
+
+    fn harvest_timestamp(&self) -> u64 {
+        self.harvest_timestamp
+    }
+
+    /// Pembrock lends a single token, which is handled as a single farm
+    fn farms_len(&self) -> u64 {
+        1
+    }
+
+    fn reward_tokens(&self) -> Vec<AccountId> {
+        vec![self.reward_token.clone()]
+    }
+
+    fn is_running(&self) -> bool {
+        self.state == PembAutoCompounderState::Running
+    }
+
+    fn farm_state(&self, _farm_id: &str) -> String {
+        String::from(&self.state)
+    }
+
+    fn farm_cycle_stage(&self, _farm_id: &str) -> String {
+        String::from(&self.cycle_stage)
+    }
+
+    fn set_farm_state(&mut self, _farm_id: &str, state: AutoCompounderState) -> String {
+        self.state = state.into();
+
+        String::from(&self.state)
+    }
+
+    fn set_farm_slippage(&mut self, _farm_id: &str, slippage: u128) -> u128 {
+        self.slippage = slippage;
+
+        self.slippage
+    }
+
+    /// There are no farms to be removed, the whole strategy should be deleted instead
+    fn remove_farm(&mut self, _farm_id: &str) -> bool {
+        false
+    }
+
+    fn clear_farms(&mut self) {
+        self.state = PembAutoCompounderState::Cleared;
+    }
+
+    fn farm_cycle_in_progress(&self, _farm_id: &str) -> bool {
+        self.cycle_in_progress()
+    }
+
+    fn cycle_in_progress(&self) -> bool {
+        self.cycle_stage != PembAutoCompounderCycle::ClaimReward
+    }
+
+    fn stake(
+        &self,
+        _token_id: String,
+        seed_id: String,
+        account_id: &AccountId,
+        shares: u128,
+    ) -> Promise {
+        self.stake_on_pembrock(account_id, shares, seed_id)
+    }
+
+    fn unstake(
+        &self,
+        seed_id: String,
+        receiver_id: AccountId,
+        withdraw_amount: u128,
+        user_fft_shares: u128,
+    ) -> Promise {
+        PembrockAutoCompounder::unstake(
+            self,
+            seed_id,
+            receiver_id,
+            withdraw_amount,
+            user_fft_shares,
+        )
+    }
+
+    fn restake(&self, shares: u128) -> Promise {
+        ext_pembrock::ft_transfer_call(
+            self.pembrock_contract_id.clone(),
+            U128(shares),
+            "deposit".to_string(),
+            self.token_address.clone(),
+            1,
+            Gas(40_000_000_000_000),
+        )
+    }
+
+    fn emergency_withdraw(&self, shares: u128) -> Promise {
+        ext_pembrock::withdraw(
+            self.token_address.clone(),
+            U128(shares),
+            self.pembrock_contract_id.clone(),
+            1,
+            Gas(100_000_000_000_000),
+        )
+    }
+
+    /// The tokens withdrawn from pembrock are held by the contract itself
+    fn withdraw_held(
+        &self,
+        seed_id: String,
+        receiver_id: AccountId,
+        withdraw_amount: u128,
+        user_fft_shares: u128,
+    ) -> Promise {
+        self.transfer_held(receiver_id.clone(), withdraw_amount)
+            .then(callback_ref_finance::callback_withdraw_shares(
+                seed_id,
+                receiver_id,
+                withdraw_amount,
+                user_fft_shares,
+                0,
+                env::current_account_id(),
+                0,
+                Gas(20_000_000_000_000),
+            ))
+    }
+
+    fn transfer_held(&self, receiver_id: AccountId, amount: u128) -> Promise {
+        ext_reward_token::ft_transfer(
+            receiver_id,
+            U128(amount),
+            Some("".to_string()),
+            self.token_address.clone(),
+            1,
+            Gas(20_000_000_000_000),
+        )
+    }
+
+    fn harvest_step(
+        &mut self,
+        _farm_id_str: String,
+        strat_name: String,
+        _treasure: AccountFee,
+    ) -> PromiseOrValue<u128> {
+        match self.cycle_stage {
+            PembAutoCompounderCycle::ClaimReward => {
+                PromiseOrValue::Promise(self.claim_reward(strat_name))
+            }
+            PembAutoCompounderCycle::SwapAndLend => {
+                PromiseOrValue::Promise(self.swap_and_lend(strat_name))
+            }
+        }
     }
 }
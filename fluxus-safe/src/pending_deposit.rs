@@ -0,0 +1,300 @@
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    /// Returns the shares of seed_id deposited below seed_min_deposit and not staked into the farm yet
+    pub fn get_pending_deposits(&self, seed_id: String) -> U128 {
+        U128(
+            self.data()
+                .pending_deposits_by_seed_id
+                .get(&seed_id)
+                .unwrap_or_default(),
+        )
+    }
+
+    /// Stops counting the shares used by the farm as pending.
+    /// If the restake failed, or the farm did not use them, they stay pending for the next harvest.
+    /// Returns refund, the part of the deposit above the caps.
+    #[private]
+    pub fn callback_post_pending_restake(
+        &mut self,
+        #[callback_result] restake_result: Result<U128, PromiseError>,
+        seed_id: String,
+        refund: U128,
+    ) -> U128 {
+        match restake_result {
+            Ok(used) => self.internal_stake_pending_deposits(&seed_id, used.0),
+            Err(_) => log!("ERR: failed to stake the pending shares of {}", seed_id),
+        }
+
+        refund
+    }
+}
+
+impl Contract {
+    /// Mints fft_shares for a deposit that joins the shares pending on the exchange.
+    /// Once the pending deposits reach seed_min_deposit, they are staked together, without minting fft_shares again.
+    /// Returns refund, resolved once the pending deposits are staked.
+    pub(crate) fn internal_pending_deposit(
+        &mut self,
+        strat: &VersionedStrategy,
+        seed_id: &str,
+        account_id: &AccountId,
+        shares: u128,
+        refund: u128,
+    ) -> PromiseOrValue<U128> {
+        let pending = self.get_pending_deposits(seed_id.to_string()).0 + shares;
+
+        self.internal_mint_staked_shares(seed_id, account_id, shares);
+
+        self.data_mut()
+            .pending_deposits_by_seed_id
+            .insert(&seed_id.to_string(), &pending);
+
+        if pending < strat.strategy().seed_min_deposit().0 {
+            log!(
+                "{} shares of {} are pending until they reach the minimum deposit",
+                pending,
+                seed_id
+            );
+            return PromiseOrValue::Value(U128(refund));
+        }
+
+        log!("Staking {} pending shares into {}", pending, seed_id);
+
+        // the shares are counted as pending until the farm accepts them
+        PromiseOrValue::Promise(strat.strategy().restake(pending).then(
+            callback_pending_deposit::callback_post_pending_restake(
+                seed_id.to_string(),
+                U128(refund),
+                env::current_account_id(),
+                0,
+                Gas(10_000_000_000_000),
+            ),
+        ))
+    }
+
+    /// Stops counting used shares as pending, once the farm accepted them with a restake or a harvest
+    pub(crate) fn internal_stake_pending_deposits(&mut self, seed_id: &str, used: u128) {
+        let pending = self.get_pending_deposits(seed_id.to_string()).0;
+        if pending <= used {
+            self.internal_clear_pending_deposits(seed_id);
+        } else {
+            self.data_mut()
+                .pending_deposits_by_seed_id
+                .insert(&seed_id.to_string(), &(pending - used));
+        }
+    }
+
+    /// Stops counting any share as pending, e.g. once the strategy left its farms
+    pub(crate) fn internal_clear_pending_deposits(&mut self, seed_id: &str) {
+        self.data_mut()
+            .pending_deposits_by_seed_id
+            .remove(&seed_id.to_string());
+    }
+
+    /// Withdrawals take the shares held on the exchange first
+    pub(crate) fn internal_withdraw_pending_deposits(&mut self, seed_id: &str, amount: u128) {
+        let pending = self.get_pending_deposits(seed_id.to_string()).0;
+        if pending == 0 {
+            return;
+        }
+
+        if pending <= amount {
+            self.internal_clear_pending_deposits(seed_id);
+        } else {
+            self.data_mut()
+                .pending_deposits_by_seed_id
+                .insert(&seed_id.to_string(), &(pending - amount));
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use near_sdk::testing_env;
+
+    /// Running ref strategy with a minimum deposit of 1_000 shares.
+    /// The 100 fft_shares of user.near are worth 100 shares of SEED_ID
+    fn create_contract() -> Contract {
+        create_contract_with_farm(1_000, 100, 100)
+    }
+
+    fn deposit(contract: &mut Contract, account_id: &str, amount: u128) -> PromiseOrValue<U128> {
+        testing_env!(get_context("exchange.near").build());
+        contract.mft_on_transfer(
+            ":1".to_string(),
+            to_account_id(account_id),
+            U128(amount),
+            "".to_string(),
+        )
+    }
+
+    fn refunded(result: PromiseOrValue<U128>) -> u128 {
+        match result {
+            PromiseOrValue::Value(amount) => amount.0,
+            PromiseOrValue::Promise(_) => panic!("expected a value"),
+        }
+    }
+
+    fn balance(contract: &Contract, account_id: &str) -> u128 {
+        contract.users_fft_share_amount("fft_share_1".to_string(), to_account_id(account_id))
+    }
+
+    #[test]
+    fn test_deposits_below_min_deposit() {
+        let mut contract = create_contract();
+
+        // fft_shares are minted right away, the shares wait on the exchange
+        assert_eq!(refunded(deposit(&mut contract, "alice.near", 300)), 0);
+        assert_eq!(balance(&contract, "alice.near"), 300);
+        assert_eq!(
+            contract.get_pending_deposits(SEED_ID.to_string()),
+            U128(300)
+        );
+
+        assert_eq!(refunded(deposit(&mut contract, "bob.near", 500)), 0);
+        assert_eq!(
+            contract.get_pending_deposits(SEED_ID.to_string()),
+            U128(800)
+        );
+
+        // the pending deposits reach the minimum and are staked together,
+        // they stay pending until the farm accepts them
+        assert!(matches!(
+            deposit(&mut contract, "bob.near", 200),
+            PromiseOrValue::Promise(_)
+        ));
+        assert_eq!(balance(&contract, "bob.near"), 700);
+        assert_eq!(
+            contract.get_pending_deposits(SEED_ID.to_string()),
+            U128(1_000)
+        );
+        assert_eq!(contract.seed_total_amount(&SEED_ID.to_string()), 1_100);
+
+        testing_env!(get_context("auto_compounder.near").build());
+        assert_eq!(
+            contract.callback_post_pending_restake(Ok(U128(1_000)), SEED_ID.to_string(), U128(0)),
+            U128(0)
+        );
+        assert_eq!(contract.get_pending_deposits(SEED_ID.to_string()), U128(0));
+
+        // deposits above the minimum are staked directly
+        assert!(matches!(
+            deposit(&mut contract, "bob.near", 1_000),
+            PromiseOrValue::Promise(_)
+        ));
+    }
+
+    #[test]
+    fn test_failed_restake_of_pending_deposits() {
+        let mut contract = create_contract();
+        deposit(&mut contract, "alice.near", 300);
+        deposit(&mut contract, "bob.near", 700);
+
+        testing_env!(get_context("auto_compounder.near").build());
+        contract.callback_post_pending_restake(
+            Err(PromiseError::Failed),
+            SEED_ID.to_string(),
+            U128(0),
+        );
+        assert_eq!(
+            contract.get_pending_deposits(SEED_ID.to_string()),
+            U128(1_000)
+        );
+
+        // the farm used part of the shares, the rest waits for the next harvest
+        contract.callback_post_pending_restake(Ok(U128(600)), SEED_ID.to_string(), U128(0));
+        assert_eq!(
+            contract.get_pending_deposits(SEED_ID.to_string()),
+            U128(400)
+        );
+    }
+
+    #[test]
+    fn test_deposit_joins_pending_deposits() {
+        let mut contract = create_contract();
+        deposit(&mut contract, "alice.near", 300);
+
+        // a deposit above the minimum is staked with the shares already pending
+        assert!(matches!(
+            deposit(&mut contract, "bob.near", 1_000),
+            PromiseOrValue::Promise(_)
+        ));
+        assert_eq!(balance(&contract, "bob.near"), 1_000);
+        assert_eq!(
+            contract.get_pending_deposits(SEED_ID.to_string()),
+            U128(1_300)
+        );
+
+        testing_env!(get_context("auto_compounder.near").build());
+        contract.callback_post_pending_restake(Ok(U128(1_300)), SEED_ID.to_string(), U128(0));
+        assert_eq!(contract.get_pending_deposits(SEED_ID.to_string()), U128(0));
+    }
+
+    #[test]
+    fn test_harvest_stakes_pending_deposits() {
+        let mut contract = create_contract();
+        deposit(&mut contract, "alice.near", 300);
+
+        // the harvest failed to stake, the shares are still pending
+        testing_env!(get_context("auto_compounder.near").build());
+        contract.callback_post_harvest_stake(
+            Err(PromiseError::Failed),
+            SEED_ID.to_string(),
+            U128(300),
+        );
+        assert_eq!(
+            contract.get_pending_deposits(SEED_ID.to_string()),
+            U128(300)
+        );
+
+        // a deposit arrived while the harvest staked the 300 pending shares with 50 harvested
+        deposit(&mut contract, "bob.near", 200);
+        testing_env!(get_context("auto_compounder.near").build());
+        contract.callback_post_harvest_stake(Ok(U128(350)), SEED_ID.to_string(), U128(300));
+        assert_eq!(
+            contract.get_pending_deposits(SEED_ID.to_string()),
+            U128(200)
+        );
+    }
+
+    #[test]
+    fn test_withdraw_from_pending_deposits() {
+        let mut contract = create_contract();
+        deposit(&mut contract, "alice.near", 300);
+
+        contract.internal_resolve_unstake(
+            true,
+            SEED_ID.to_string(),
+            to_account_id("alice.near"),
+            100,
+            100,
+            0,
+        );
+        assert_eq!(
+            contract.get_pending_deposits(SEED_ID.to_string()),
+            U128(200)
+        );
+
+        contract.internal_resolve_unstake(
+            true,
+            SEED_ID.to_string(),
+            to_account_id("user.near"),
+            100,
+            100,
+            0,
+        );
+        contract.internal_resolve_unstake(
+            true,
+            SEED_ID.to_string(),
+            to_account_id("alice.near"),
+            200,
+            200,
+            0,
+        );
+        assert_eq!(contract.get_pending_deposits(SEED_ID.to_string()), U128(0));
+    }
+}
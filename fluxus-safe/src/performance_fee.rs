@@ -0,0 +1,408 @@
+use crate::*;
+
+/// Performance fee mode of a strategy.
+/// Instead of a cut of every claimed reward, the treasury and the strategy creator are paid in fft_shares,
+/// minted only when the price per share grows above the high-water mark.
+/// Sentries keep their cut of the claimed rewards.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PerformanceFee {
+    /// False while the strategy takes its fees from every claimed reward again,
+    /// the high-water mark and the minted fft_shares are kept for the next time it is enabled
+    pub enabled: bool,
+
+    /// Highest price per share already charged, multiplied by SHARE_PRICE_PRECISION
+    pub high_water_mark: U128,
+
+    /// fft_shares minted to the treasury, cumulative
+    pub treasury_fft_shares: U128,
+
+    /// fft_shares minted to the strategy creator, cumulative
+    pub strat_creator_fft_shares: U128,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Switches the strategy of seed_id between the performance fee and the fee on every claimed reward.
+    /// The high-water mark starts at the current price per share. Enabling it again never lowers the mark,
+    /// the growth while it was disabled already paid its fees from the claimed rewards.
+    pub fn set_performance_fee(&mut self, seed_id: String, enabled: bool) {
+        self.is_owner();
+        // panics if the strategy does not exist
+        self.get_strat(&seed_id);
+
+        let (total_fft, total_seed) = self.fft_and_seed_totals(&seed_id);
+        let current_price = price_per_share(total_seed, total_fft);
+
+        let mut performance_fee = match self.data().performance_fee_by_seed_id.get(&seed_id) {
+            Some(performance_fee) if performance_fee.enabled == enabled => return,
+            Some(performance_fee) => performance_fee,
+            None if !enabled => return,
+            None => PerformanceFee {
+                enabled,
+                high_water_mark: U128(current_price),
+                treasury_fft_shares: U128(0),
+                strat_creator_fft_shares: U128(0),
+            },
+        };
+
+        performance_fee.enabled = enabled;
+        if enabled {
+            performance_fee.high_water_mark = U128(std::cmp::max(
+                performance_fee.high_water_mark.0,
+                current_price,
+            ));
+        }
+
+        self.data_mut()
+            .performance_fee_by_seed_id
+            .insert(&seed_id, &performance_fee);
+    }
+
+    /// Returns the performance fee of seed_id, None if it was never enabled
+    pub fn get_performance_fee(&self, seed_id: String) -> Option<PerformanceFee> {
+        self.data().performance_fee_by_seed_id.get(&seed_id)
+    }
+}
+
+impl Contract {
+    /// Splits reward_amount into (remaining, treasury, sentry, strat_creator) for the strategy of seed_id
+    pub(crate) fn internal_compute_fees(
+        &self,
+        seed_id: &str,
+        admin_fees: &AdminFees,
+        reward_amount: u128,
+    ) -> (u128, u128, u128, u128) {
+        if self
+            .data()
+            .performance_fee_by_seed_id
+            .get(&seed_id.to_string())
+            .map_or(false, |performance_fee| performance_fee.enabled)
+        {
+            admin_fees.compute_performance_mode_fees(reward_amount)
+        } else {
+            admin_fees.compute_fees(reward_amount)
+        }
+    }
+
+    /// Mints the performance fee of seed_id, called once the harvested shares were added to the strategy.
+    /// strategy_fee of the growth above the high-water mark is paid in fft_shares,
+    /// the creator gets its fee_percentage of them and the treasury the rest.
+    pub(crate) fn internal_charge_performance_fee(&mut self, seed_id: &str) {
+        let seed_id = seed_id.to_string();
+        let mut performance_fee = match self.data().performance_fee_by_seed_id.get(&seed_id) {
+            Some(performance_fee) if performance_fee.enabled => performance_fee,
+            _ => return,
+        };
+
+        let (total_fft, total_seed) = self.fft_and_seed_totals(&seed_id);
+        if total_fft == 0 {
+            return;
+        }
+
+        let high_water_mark = performance_fee.high_water_mark.0;
+        let current_price = price_per_share(total_seed, total_fft);
+        if current_price <= high_water_mark {
+            return;
+        }
+
+        let admin_fees = self.get_strat(&seed_id).strategy().admin_fees().clone();
+
+        // shares of seed_id earned above the high-water mark
+        let profit = (U256::from(current_price - high_water_mark) * U256::from(total_fft)
+            / U256::from(SHARE_PRICE_PRECISION))
+        .as_u128();
+        let fee_amount = apply_fee(profit, admin_fees.strategy_fee);
+
+        // fft_shares worth fee_amount once they are minted
+        let fee_fft_shares = if fee_amount == 0 {
+            0
+        } else {
+            (U256::from(fee_amount) * U256::from(total_fft) / U256::from(total_seed - fee_amount))
+                .as_u128()
+        };
+        let strat_creator_fft_shares =
+            apply_fee(fee_fft_shares, admin_fees.strat_creator.fee_percentage);
+        let treasury_fft_shares = fee_fft_shares - strat_creator_fft_shares;
+
+        let fft_share_id = self.fft_token_seed_id(seed_id.clone());
+        if treasury_fft_shares > 0 {
+            let treasury_id = self.data().treasury.account_id.clone();
+            self.mft_mint(fft_share_id.clone(), treasury_fft_shares, treasury_id);
+        }
+        if strat_creator_fft_shares > 0 {
+            self.mft_mint(
+                fft_share_id,
+                strat_creator_fft_shares,
+                admin_fees.strat_creator.account_id,
+            );
+        }
+
+        // the new mark is the price once the fee is paid
+        performance_fee.high_water_mark =
+            U128(price_per_share(total_seed, total_fft + fee_fft_shares));
+        performance_fee.treasury_fft_shares =
+            U128(performance_fee.treasury_fft_shares.0 + treasury_fft_shares);
+        performance_fee.strat_creator_fft_shares =
+            U128(performance_fee.strat_creator_fft_shares.0 + strat_creator_fft_shares);
+
+        Event::PerformanceFee(&[PerformanceFeeData {
+            seed_id: &seed_id,
+            price_per_share: U128(current_price),
+            high_water_mark: performance_fee.high_water_mark,
+            fee_amount: U128(fee_amount),
+            treasury_fft_shares: U128(treasury_fft_shares),
+            strat_creator_fft_shares: U128(strat_creator_fft_shares),
+        }])
+        .emit();
+
+        self.data_mut()
+            .performance_fee_by_seed_id
+            .insert(&seed_id, &performance_fee);
+    }
+}
+
+impl Contract {
+    /// Returns the current price per share of seed_id
+    pub(crate) fn internal_price_per_share(&self, seed_id: &str) -> u128 {
+        let (total_fft, total_seed) = self.fft_and_seed_totals(seed_id);
+        price_per_share(total_seed, total_fft)
+    }
+
+    /// Moves the high-water mark of seed_id with the price, after a deposit, withdrawal or management fee
+    /// changed it from price_before. Only the growth from harvested rewards pays the performance fee.
+    pub(crate) fn internal_move_high_water_mark(&mut self, seed_id: &str, price_before: u128) {
+        let seed_id = seed_id.to_string();
+        let mut performance_fee = match self.data().performance_fee_by_seed_id.get(&seed_id) {
+            Some(performance_fee) if performance_fee.enabled => performance_fee,
+            _ => return,
+        };
+
+        let (total_fft, total_seed) = self.fft_and_seed_totals(&seed_id);
+        let price_after = price_per_share(total_seed, total_fft);
+        // the last fft_shares were withdrawn, the mark is kept for the next deposits
+        if total_fft == 0 || price_before == 0 || price_after == price_before {
+            return;
+        }
+
+        performance_fee.high_water_mark = U128(
+            (U256::from(performance_fee.high_water_mark.0) * U256::from(price_after)
+                / U256::from(price_before))
+            .as_u128(),
+        );
+
+        self.data_mut()
+            .performance_fee_by_seed_id
+            .insert(&seed_id, &performance_fee);
+    }
+}
+
+/// Amount of seed_id owned by one fft_share, multiplied by SHARE_PRICE_PRECISION. 1:1 without fft_shares.
+fn price_per_share(total_seed: u128, total_fft: u128) -> u128 {
+    if total_fft == 0 {
+        return SHARE_PRICE_PRECISION;
+    }
+
+    (U256::from(total_seed) * U256::from(SHARE_PRICE_PRECISION) / U256::from(total_fft)).as_u128()
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use near_sdk::testing_env;
+
+    /// 10% strategy fee, 10% of it to the creator and 10% to sentries.
+    /// 1_000_000 fft_shares worth 1_000_000 shares of SEED_ID
+    fn create_contract() -> Contract {
+        testing_env!(get_context("auto_compounder.near").build());
+        let mut contract = create_safe();
+
+        let strat = VersionedStrategy::AutoCompounder(AutoCompounder::new(
+            1_000,
+            AccountFee::new(to_account_id("creator.near"), 1_000),
+            1_000,
+            to_account_id("exchange.near"),
+            to_account_id("farm.near"),
+            to_account_id("token1.near"),
+            to_account_id("token2.near"),
+            1,
+            SEED_ID.to_string(),
+            U128(1_000),
+        ));
+        register_strategy(&mut contract, SEED_ID, "fft_share_1", strat, 1_000_000);
+        contract.mft_mint(
+            "fft_share_1".to_string(),
+            1_000_000,
+            to_account_id("user.near"),
+        );
+
+        contract
+    }
+
+    fn set_seed_amount(contract: &mut Contract, amount: u128) {
+        contract
+            .data_mut()
+            .seed_id_amount
+            .insert(&SEED_ID.to_string(), &amount);
+    }
+
+    fn balance(contract: &Contract, account_id: &str) -> u128 {
+        contract.users_fft_share_amount("fft_share_1".to_string(), to_account_id(account_id))
+    }
+
+    #[test]
+    fn test_compute_fees_by_mode() {
+        let mut contract = create_contract();
+        let admin_fees = contract.get_strat(SEED_ID).strategy().admin_fees().clone();
+
+        assert_eq!(
+            contract.internal_compute_fees(SEED_ID, &admin_fees, 1_000_000),
+            (900_000, 80_000, 10_000, 10_000)
+        );
+
+        contract.set_performance_fee(SEED_ID.to_string(), true);
+
+        // only sentries are paid from the rewards
+        assert_eq!(
+            contract.internal_compute_fees(SEED_ID, &admin_fees, 1_000_000),
+            (990_000, 0, 10_000, 0)
+        );
+    }
+
+    #[test]
+    fn test_charge_above_high_water_mark() {
+        let mut contract = create_contract();
+        contract.set_performance_fee(SEED_ID.to_string(), true);
+        assert_eq!(
+            contract
+                .get_performance_fee(SEED_ID.to_string())
+                .unwrap()
+                .high_water_mark,
+            U128(SHARE_PRICE_PRECISION)
+        );
+
+        // 100_000 shares earned, 10% of them paid in fft_shares
+        set_seed_amount(&mut contract, 1_100_000);
+        contract.internal_charge_performance_fee(SEED_ID);
+
+        assert_eq!(balance(&contract, "treasure.near"), 8_257);
+        assert_eq!(balance(&contract, "creator.near"), 917);
+        assert_eq!(
+            contract.get_performance_fee(SEED_ID.to_string()),
+            Some(PerformanceFee {
+                enabled: true,
+                high_water_mark: U128(1_090_000_336_909_195_044_660_286),
+                treasury_fft_shares: U128(8_257),
+                strat_creator_fft_shares: U128(917),
+            })
+        );
+
+        // the price goes down, nothing is charged until it is above the high-water mark again
+        set_seed_amount(&mut contract, 1_050_000);
+        contract.internal_charge_performance_fee(SEED_ID);
+        assert_eq!(balance(&contract, "treasure.near"), 8_257);
+
+        // only the growth above the high-water mark is charged
+        set_seed_amount(&mut contract, 1_200_000);
+        contract.internal_charge_performance_fee(SEED_ID);
+        assert_eq!(balance(&contract, "creator.near"), 917 + 847);
+        assert_eq!(balance(&contract, "treasure.near"), 8_257 + 7_632);
+    }
+
+    #[test]
+    fn test_disabled_performance_fee() {
+        let mut contract = create_contract();
+        contract.set_performance_fee(SEED_ID.to_string(), true);
+        contract.set_performance_fee(SEED_ID.to_string(), false);
+
+        set_seed_amount(&mut contract, 1_100_000);
+        contract.internal_charge_performance_fee(SEED_ID);
+
+        assert!(
+            !contract
+                .get_performance_fee(SEED_ID.to_string())
+                .unwrap()
+                .enabled
+        );
+        assert_eq!(balance(&contract, "treasure.near"), 0);
+    }
+
+    #[test]
+    fn test_toggle_keeps_high_water_mark() {
+        let mut contract = create_contract();
+        contract.set_performance_fee(SEED_ID.to_string(), true);
+
+        set_seed_amount(&mut contract, 1_100_000);
+        contract.internal_charge_performance_fee(SEED_ID);
+        let charged = contract.get_performance_fee(SEED_ID.to_string()).unwrap();
+
+        // the price goes down while the fees are taken from the claimed rewards
+        contract.set_performance_fee(SEED_ID.to_string(), false);
+        set_seed_amount(&mut contract, 1_050_000);
+
+        // enabled again, the mark is not reset to the lower price
+        contract.set_performance_fee(SEED_ID.to_string(), true);
+        assert_eq!(
+            contract.get_performance_fee(SEED_ID.to_string()),
+            Some(PerformanceFee {
+                enabled: true,
+                ..charged.clone()
+            })
+        );
+
+        contract.internal_charge_performance_fee(SEED_ID);
+        assert_eq!(balance(&contract, "treasure.near"), 8_257);
+
+        // the price grows above the mark while disabled, the mark moves up to it when enabled again
+        contract.set_performance_fee(SEED_ID.to_string(), false);
+        set_seed_amount(&mut contract, 1_200_000);
+        contract.set_performance_fee(SEED_ID.to_string(), true);
+
+        contract.internal_charge_performance_fee(SEED_ID);
+        assert_eq!(balance(&contract, "treasure.near"), 8_257);
+        assert_eq!(
+            contract.get_performance_fee(SEED_ID.to_string()),
+            Some(PerformanceFee {
+                enabled: true,
+                high_water_mark: U128(1_189_091_276_628_212_775_993_039),
+                ..charged
+            })
+        );
+    }
+
+    #[test]
+    fn test_other_fees_move_high_water_mark() {
+        let mut contract = create_contract();
+        contract.set_performance_fee(SEED_ID.to_string(), true);
+
+        // a 1% deposit fee stays in the strategy and raises the price, it is not charged again
+        contract.set_entry_exit_fees(SEED_ID.to_string(), 100, 0, 0, 0);
+        contract.internal_mint_staked_shares(SEED_ID, &to_account_id("alice.near"), 100_000);
+        let high_water_mark = contract
+            .get_performance_fee(SEED_ID.to_string())
+            .unwrap()
+            .high_water_mark;
+        assert_eq!(
+            high_water_mark,
+            U128(contract.internal_price_per_share(SEED_ID))
+        );
+
+        contract.internal_charge_performance_fee(SEED_ID);
+        assert_eq!(balance(&contract, "treasure.near"), 0);
+
+        // a harvest is charged from the moved mark
+        set_seed_amount(&mut contract, 1_200_000);
+        contract.internal_charge_performance_fee(SEED_ID);
+        assert!(balance(&contract, "treasure.near") > 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_ALLOWED")]
+    fn test_set_performance_fee_not_allowed() {
+        let mut contract = create_contract();
+
+        testing_env!(get_context("user.near").build());
+        contract.set_performance_fee(SEED_ID.to_string(), true);
+    }
+}
@@ -0,0 +1,221 @@
+use crate::*;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct UserPosition {
+    pub seed_id: String,
+    pub fft_share: String,
+
+    /// Amount of fft_share held by the account
+    pub fft_shares: U128,
+
+    /// Amount of seed_id owned by the fft_shares, as returned by `user_share_seed_id`
+    pub seed_amount: U128,
+
+    /// Strategy kind, e.g. REF_REGULAR or PEMBROCK
+    pub kind: String,
+
+    /// True if at least one farm of the strategy is running
+    pub is_active: bool,
+
+    /// Part of the fft_share total supply held by the account, multiplied by SHARE_PRICE_PRECISION
+    pub share_of_supply: U128,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns every strategy where account_id holds fft_shares
+    /// Args:
+    ///   from_index: position of the first fft_share of the account, defaults to 0
+    ///   limit: max number of positions returned, defaults to all
+    pub fn get_user_positions(
+        &self,
+        account_id: AccountId,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<UserPosition> {
+        let fft_shares = match self.data().fft_shares_by_account.get(&account_id) {
+            Some(fft_shares) => fft_shares,
+            None => return vec![],
+        };
+
+        let from_index = from_index.unwrap_or(0) as usize;
+        let limit = limit.map_or(fft_shares.len() as usize, |limit| limit as usize);
+
+        fft_shares
+            .iter()
+            .skip(from_index)
+            .take(limit)
+            .filter_map(|fft_share| {
+                let seed_id = self.data().seed_id_by_fft_share.get(&fft_share)?;
+                let amount = self.users_fft_share_amount(fft_share.clone(), account_id.clone());
+                let total_supply = self.total_supply_amount(fft_share.clone());
+                let strat = self.data().strategies.get(&seed_id);
+
+                Some(UserPosition {
+                    seed_amount: U128(self.seed_amount_for_fft_shares(&seed_id, amount)),
+                    kind: strat.as_ref().map(|strat| strat.kind()).unwrap_or_default(),
+                    is_active: strat.map_or(false, |strat| strat.strategy().is_running()),
+                    share_of_supply: U128(if total_supply == 0 {
+                        0
+                    } else {
+                        (U256::from(amount) * U256::from(SHARE_PRICE_PRECISION)
+                            / U256::from(total_supply))
+                        .as_u128()
+                    }),
+                    fft_shares: U128(amount),
+                    seed_id,
+                    fft_share,
+                })
+            })
+            .collect()
+    }
+
+    /// Indexes the fft_shares held by account_ids before positions were tracked
+    pub fn index_user_positions(&mut self, account_ids: Vec<AccountId>) {
+        self.is_owner();
+
+        let fft_shares: Vec<String> = self.data().fft_share_by_seed_id.values().collect();
+
+        for account_id in account_ids.iter() {
+            for fft_share in fft_shares.iter() {
+                let amount = self.users_fft_share_amount(fft_share.clone(), account_id.clone());
+                self.internal_update_position(fft_share, account_id, amount);
+            }
+        }
+    }
+}
+
+impl Contract {
+    /// Keeps fft_share in the positions of account_id while its balance is not zero
+    pub(crate) fn internal_update_position(
+        &mut self,
+        fft_share: &str,
+        account_id: &AccountId,
+        balance: u128,
+    ) {
+        let fft_share = fft_share.to_string();
+        let mut positions = self
+            .data()
+            .fft_shares_by_account
+            .get(account_id)
+            .unwrap_or_else(|| {
+                UnorderedSet::new(StorageKey::AccountFftShares {
+                    account_id: account_id.clone(),
+                })
+            });
+
+        let changed = if balance > 0 {
+            positions.insert(&fft_share)
+        } else {
+            positions.remove(&fft_share)
+        };
+
+        if changed {
+            self.data_mut()
+                .fft_shares_by_account
+                .insert(account_id, &positions);
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use near_sdk::testing_env;
+
+    /// Two strategies, user.near holds 300 of the 1_000 fft_share_1 and 50 fft_share_2
+    fn create_contract() -> Contract {
+        testing_env!(get_context("auto_compounder.near").build());
+        let mut contract = create_safe();
+
+        for (seed_id, fft_share, seed_amount) in [
+            ("exchange.near@1", "fft_share_1", 2_000),
+            ("exchange.near@2", "fft_share_2", 50),
+        ] {
+            let strat = VersionedStrategy::AutoCompounder(create_compounder(seed_id, 1_000));
+            register_strategy(&mut contract, seed_id, fft_share, strat, seed_amount);
+        }
+
+        contract.mft_mint("fft_share_1".to_string(), 300, to_account_id("user.near"));
+        contract.mft_mint("fft_share_1".to_string(), 700, to_account_id("other.near"));
+        contract.mft_mint("fft_share_2".to_string(), 50, to_account_id("user.near"));
+
+        contract
+    }
+
+    fn position_ids(contract: &Contract, account_id: &str) -> Vec<String> {
+        contract
+            .get_user_positions(to_account_id(account_id), None, None)
+            .into_iter()
+            .map(|position| position.fft_share)
+            .collect()
+    }
+
+    #[test]
+    fn test_get_user_positions() {
+        let contract = create_contract();
+
+        let positions = contract.get_user_positions(to_account_id("user.near"), None, None);
+        assert_eq!(
+            positions[0],
+            UserPosition {
+                seed_id: "exchange.near@1".to_string(),
+                fft_share: "fft_share_1".to_string(),
+                fft_shares: U128(300),
+                seed_amount: U128(600),
+                kind: "REF_REGULAR".to_string(),
+                is_active: false,
+                share_of_supply: U128(300_000_000_000_000_000_000_000),
+            }
+        );
+        assert_eq!(positions[1].fft_share, "fft_share_2");
+        assert_eq!(positions[1].seed_amount, U128(50));
+
+        let page = contract.get_user_positions(to_account_id("user.near"), Some(1), Some(5));
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].fft_share, "fft_share_2");
+
+        assert!(position_ids(&contract, "nobody.near").is_empty());
+    }
+
+    #[test]
+    fn test_positions_follow_balances() {
+        let mut contract = create_contract();
+
+        contract.mft_burn("fft_share_2".to_string(), 50, to_account_id("user.near"));
+        assert_eq!(position_ids(&contract, "user.near"), vec!["fft_share_1"]);
+
+        testing_env!(get_context("user.near").build());
+        contract.mft_transfer(
+            "fft_share_1".to_string(),
+            to_account_id("receiver.near"),
+            U128(300),
+            None,
+        );
+        assert!(position_ids(&contract, "user.near").is_empty());
+        assert_eq!(
+            position_ids(&contract, "receiver.near"),
+            vec!["fft_share_1"]
+        );
+    }
+
+    #[test]
+    fn test_index_user_positions() {
+        let mut contract = create_contract();
+
+        // balances written before the positions were tracked
+        let mut balances = contract
+            .data()
+            .users_balance_by_fft_share
+            .get(&"fft_share_2".to_string())
+            .unwrap();
+        balances.insert(&to_account_id("legacy.near"), &10u128);
+        assert!(position_ids(&contract, "legacy.near").is_empty());
+
+        testing_env!(get_context("auto_compounder.near").build());
+        contract.index_user_positions(vec![to_account_id("legacy.near")]);
+        assert_eq!(position_ids(&contract, "legacy.near"), vec!["fft_share_2"]);
+    }
+}
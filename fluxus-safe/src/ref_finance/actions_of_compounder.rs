@@ -5,6 +5,8 @@ use crate::callback::*;
 /// Auto-compounder strategy methods
 #[near_bindgen]
 impl Contract {
+    /// Mints fft_shares for the amount staked into the farm.
+    /// Returns the unused amount, which is refunded to the depositor by the exchange.
     #[private]
     pub fn callback_stake_result(
         &mut self,
@@ -12,57 +14,28 @@ impl Contract {
         seed_id: String,
         account_id: AccountId,
         shares: u128,
-    ) -> String {
-        if let Ok(amount) = transfer_result {
-            assert_eq!(amount.0, 0, "ERR_STAKE_FAILED");
-        } else {
-            panic!("ERR_STAKE_FAILED");
-        }
-
-        //Total fft_share
-        let total_fft = self.total_supply_by_pool_id(seed_id.clone());
-
-        let fft_share_id = self.get_fft_share_id_from_seed(seed_id.clone());
-
-        let data = self.data_mut();
-
-        //Total seed_id
-        let total_seed = data.seed_id_amount.get(&seed_id).unwrap_or_default();
-
-        self.data_mut()
-            .seed_id_amount
-            .insert(&seed_id, &(total_seed + shares));
-
-        let fft_share_amount = if total_fft == 0 {
-            shares
-        } else {
-            (U256::from(shares) * U256::from(total_fft) / U256::from(total_seed)).as_u128()
+    ) -> U128 {
+        // mft_transfer_call returns the amount refunded by the farm
+        let unused = match transfer_result {
+            Ok(amount) => std::cmp::min(amount.0, shares),
+            Err(_) => shares,
         };
 
-        log!(
-            "{} {} will be minted for {}",
-            fft_share_amount,
-            fft_share_id,
-            account_id.to_string()
-        );
-        self.mft_mint(fft_share_id, fft_share_amount, account_id.to_string());
-
-        format!(
-            "The {} added {} to {}",
-            account_id, fft_share_amount, seed_id
-        )
+        self.internal_resolve_stake(seed_id, account_id, shares, unused)
     }
 
     /// Withdraw user lps and send it to the contract.
-    pub fn unstake(&self, seed_id: String, amount_withdrawal: Option<U128>) -> Promise {
+    pub fn unstake(&mut self, seed_id: String, amount_withdrawal: Option<U128>) -> Promise {
         let (caller_id, _) = get_predecessor_and_current_account();
 
+        self.internal_accrue_management_fee(&seed_id);
+
         let strat = self.get_strat(&seed_id);
 
         let fft_share_id = self.get_fft_share_id_from_seed(seed_id.clone());
 
-        let mut user_fft_shares =
-            self.users_fft_share_amount(fft_share_id.clone(), caller_id.to_string());
+        let user_fft_balance = self.users_fft_share_amount(fft_share_id, caller_id.clone());
+        let mut user_fft_shares = user_fft_balance;
 
         assert!(
             user_fft_shares > 0,
@@ -71,24 +44,22 @@ impl Contract {
             user_fft_shares
         );
 
-        //Total fft_share
-        let total_fft = self.total_supply_amount(fft_share_id);
-
-        //Total seed_id
-        let total_seed = self.seed_total_amount(&seed_id);
-
         //Converting user total fft_shares in seed_id:
-        let user_shares = (U256::from(user_fft_shares) * U256::from(total_seed)
-            / U256::from(total_fft))
-        .as_u128();
+        let user_shares = self.seed_amount_for_fft_shares(&seed_id, user_fft_shares);
 
         let amount: U128;
 
         if let Some(amount_withdrawal) = amount_withdrawal {
             amount = amount_withdrawal;
-            user_fft_shares = (U256::from(amount_withdrawal.0) * U256::from(total_fft)
-                / U256::from(total_seed))
-            .as_u128();
+            user_fft_shares = self.fft_shares_for_withdraw(&seed_id, amount_withdrawal.0);
+
+            assert!(
+                user_fft_shares <= user_fft_balance,
+                "{} is trying to burn {} fft_shares and only has {}",
+                caller_id,
+                user_fft_shares,
+                user_fft_balance
+            );
         } else {
             amount = U128(user_shares);
         }
@@ -101,7 +72,20 @@ impl Contract {
             user_shares
         );
 
-        strat.unstake(seed_id, caller_id, amount.0, user_fft_shares)
+        // the withdrawal fee stays in the strategy, user_fft_shares are burnt for the whole amount
+        let fee = self.internal_withdrawal_fee(&seed_id, &caller_id, amount.0);
+
+        // the strategy left its farm, the shares are already held by the contract
+        if self.is_emergency_exited(&seed_id) {
+            return strat.strategy().withdraw_held(
+                seed_id,
+                caller_id,
+                amount.0 - fee,
+                user_fft_shares,
+            );
+        }
+
+        strat.unstake(seed_id, caller_id, amount.0 - fee, user_fft_shares)
     }
 
     #[private]
@@ -121,7 +105,11 @@ impl Contract {
 
         let compounder = self.get_strat(&seed_id).get_compounder();
 
-        let shares_on_exchange: u128 = shares_result.unwrap().into();
+        // the claimable shares are held on the exchange too, but belong to their accounts
+        let shares_on_exchange: u128 = shares_result
+            .unwrap()
+            .0
+            .saturating_sub(self.get_total_claimable_shares(&seed_id));
 
         if shares_on_exchange >= withdraw_amount {
             ext_ref_exchange::mft_transfer(
@@ -138,6 +126,7 @@ impl Contract {
                 receiver_id,
                 withdraw_amount,
                 user_fft_shares,
+                0,
                 env::current_account_id(),
                 0,
                 Gas(20_000_000_000_000),
@@ -152,31 +141,65 @@ impl Contract {
                 U128(amount),
                 compounder.farm_contract_id.clone(),
                 1,
-                Gas(180_000_000_000_000),
+                Gas(120_000_000_000_000),
             )
-            // TODO: add callback and then call mft_transfer
-            // transfer the total amount required
-            .then(ext_ref_exchange::mft_transfer(
+            .then(callback_ref_finance::callback_post_unlock_seed(
                 token_id,
-                receiver_id.clone(),
-                U128(withdraw_amount),
-                Some("".to_string()),
-                compounder.exchange_contract_id,
-                1,
-                Gas(30_000_000_000_000),
-            ))
-            .then(callback_ref_finance::callback_withdraw_shares(
                 seed_id,
                 receiver_id,
                 withdraw_amount,
                 user_fft_shares,
+                amount,
                 env::current_account_id(),
                 0,
-                Gas(20_000_000_000_000),
+                Gas(130_000_000_000_000),
             ))
         }
     }
 
+    /// Transfers the total amount required once the missing amount was withdrawn from the farm.
+    /// Otherwise nothing left the farm, the unstake fails and the user position is kept.
+    #[private]
+    pub fn callback_post_unlock_seed(
+        &self,
+        #[callback_result] unlock_result: Result<bool, PromiseError>,
+        token_id: String,
+        seed_id: String,
+        receiver_id: AccountId,
+        withdraw_amount: u128,
+        user_fft_shares: u128,
+        withdrawn_from_farm: u128,
+    ) -> Promise {
+        assert!(
+            matches!(unlock_result, Ok(true)),
+            "ERR: failed to withdraw {} shares of {} from the farm",
+            withdrawn_from_farm,
+            seed_id
+        );
+
+        let compounder = self.get_strat(&seed_id).get_compounder();
+
+        ext_ref_exchange::mft_transfer(
+            token_id,
+            receiver_id.clone(),
+            U128(withdraw_amount),
+            Some("".to_string()),
+            compounder.exchange_contract_id,
+            1,
+            Gas(30_000_000_000_000),
+        )
+        .then(callback_ref_finance::callback_withdraw_shares(
+            seed_id,
+            receiver_id,
+            withdraw_amount,
+            user_fft_shares,
+            withdrawn_from_farm,
+            env::current_account_id(),
+            0,
+            Gas(95_000_000_000_000),
+        ))
+    }
+
     #[private]
     pub fn callback_withdraw_shares(
         &mut self,
@@ -185,28 +208,16 @@ impl Contract {
         account_id: AccountId,
         amount: Balance,
         fft_shares: Balance,
-    ) {
-        assert!(
+        withdrawn_from_farm: Balance,
+    ) -> PromiseOrValue<()> {
+        self.internal_resolve_unstake(
             mft_transfer_result.is_ok(),
-            "ERR: failed to transfer shares to {}",
-            account_id
-        );
-
-        let data = self.data_mut();
-        let total_seed = data.seed_id_amount.get(&seed_id).unwrap_or_default();
-
-        self.data_mut()
-            .seed_id_amount
-            .insert(&seed_id, &(total_seed - amount));
-
-        let fft_share_id = self
-            .data()
-            .fft_share_by_seed_id
-            .get(&seed_id)
-            .unwrap()
-            .clone();
-
-        self.mft_burn(fft_share_id, fft_shares, account_id.to_string());
+            seed_id,
+            account_id,
+            amount,
+            fft_shares,
+            withdrawn_from_farm,
+        )
     }
 
     #[private]
@@ -226,7 +237,11 @@ impl Contract {
 
         let stable_compounder = self.get_strat(&seed_id).get_stable_compounder();
 
-        let shares_on_exchange: u128 = shares_result.unwrap().into();
+        // the claimable shares are held on the exchange too, but belong to their accounts
+        let shares_on_exchange: u128 = shares_result
+            .unwrap()
+            .0
+            .saturating_sub(self.get_total_claimable_shares(&seed_id));
 
         if shares_on_exchange >= withdraw_amount {
             ext_ref_exchange::mft_transfer(
@@ -244,6 +259,7 @@ impl Contract {
                     receiver_id,
                     withdraw_amount,
                     user_fft_shares,
+                    0,
                     env::current_account_id(),
                     0,
                     Gas(20_000_000_000_000),
@@ -259,33 +275,69 @@ impl Contract {
                 U128(amount),
                 stable_compounder.farm_contract_id.clone(),
                 1,
-                Gas(180_000_000_000_000),
+                Gas(120_000_000_000_000),
             )
-            // TODO: add callback and then call mft_transfer
-            // transfer the total amount required
-            .then(ext_ref_exchange::mft_transfer(
-                token_id,
-                receiver_id.clone(),
-                U128(withdraw_amount),
-                Some("".to_string()),
-                stable_compounder.exchange_contract_id,
-                1,
-                Gas(30_000_000_000_000),
-            ))
             .then(
-                callback_stable_ref_finance::stable_callback_withdraw_shares(
+                callback_stable_ref_finance::stable_callback_post_unlock_seed(
+                    token_id,
                     seed_id,
                     receiver_id,
                     withdraw_amount,
                     user_fft_shares,
+                    amount,
                     env::current_account_id(),
                     0,
-                    Gas(20_000_000_000_000),
+                    Gas(130_000_000_000_000),
                 ),
             )
         }
     }
 
+    /// Transfers the total amount required once the missing amount was withdrawn from the farm.
+    /// Otherwise nothing left the farm, the unstake fails and the user position is kept.
+    #[private]
+    pub fn stable_callback_post_unlock_seed(
+        &self,
+        #[callback_result] unlock_result: Result<bool, PromiseError>,
+        token_id: String,
+        seed_id: String,
+        receiver_id: AccountId,
+        withdraw_amount: u128,
+        user_fft_shares: u128,
+        withdrawn_from_farm: u128,
+    ) -> Promise {
+        assert!(
+            matches!(unlock_result, Ok(true)),
+            "ERR: failed to withdraw {} shares of {} from the farm",
+            withdrawn_from_farm,
+            seed_id
+        );
+
+        let stable_compounder = self.get_strat(&seed_id).get_stable_compounder();
+
+        ext_ref_exchange::mft_transfer(
+            token_id,
+            receiver_id.clone(),
+            U128(withdraw_amount),
+            Some("".to_string()),
+            stable_compounder.exchange_contract_id,
+            1,
+            Gas(30_000_000_000_000),
+        )
+        .then(
+            callback_stable_ref_finance::stable_callback_withdraw_shares(
+                seed_id,
+                receiver_id,
+                withdraw_amount,
+                user_fft_shares,
+                withdrawn_from_farm,
+                env::current_account_id(),
+                0,
+                Gas(95_000_000_000_000),
+            ),
+        )
+    }
+
     #[private]
     pub fn stable_callback_withdraw_shares(
         &mut self,
@@ -294,28 +346,16 @@ impl Contract {
         account_id: AccountId,
         amount: Balance,
         fft_shares: Balance,
-    ) {
-        assert!(
+        withdrawn_from_farm: Balance,
+    ) -> PromiseOrValue<()> {
+        self.internal_resolve_unstake(
             mft_transfer_result.is_ok(),
-            "ERR: failed to transfer shares to {}",
-            account_id
-        );
-
-        let data = self.data_mut();
-        let total_seed = data.seed_id_amount.get(&seed_id).unwrap_or_default();
-
-        self.data_mut()
-            .seed_id_amount
-            .insert(&seed_id, &(total_seed - amount));
-
-        let fft_share_id = self
-            .data()
-            .fft_share_by_seed_id
-            .get(&seed_id)
-            .unwrap()
-            .clone();
-
-        self.mft_burn(fft_share_id, fft_shares, account_id.to_string());
+            seed_id,
+            account_id,
+            amount,
+            fft_shares,
+            withdrawn_from_farm,
+        )
     }
 }
 
@@ -1,6 +1,6 @@
 use crate::*;
 
-const MAX_SLIPPAGE_ALLOWED: u128 = 20;
+pub(crate) const MAX_SLIPPAGE_ALLOWED: u128 = 20;
 
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, PartialEq, Clone)]
 #[serde(crate = "near_sdk::serde")]
@@ -15,6 +15,9 @@ pub struct StratFarmInfo {
     /// Defaults to 5%. The value will be computed as 100 - slippage
     pub slippage: u128,
 
+    /// Maximum slippage accepted before the farm is Ended, range from 0 to 100
+    pub max_slippage: u128,
+
     /// Used to keep track of the rewards received from the farm during auto-compound cycle
     pub last_reward_amount: u128,
 
@@ -40,17 +43,26 @@ pub struct StratFarmInfo {
 }
 
 impl StratFarmInfo {
-    pub(crate) fn next_cycle(&mut self) {
+    pub(crate) fn next_cycle(&mut self, seed_id: &str) {
+        let from = String::from(&self.cycle_stage);
+
         match self.cycle_stage {
             AutoCompounderCycle::ClaimReward => self.cycle_stage = AutoCompounderCycle::Withdrawal,
             AutoCompounderCycle::Withdrawal => self.cycle_stage = AutoCompounderCycle::Swap,
             AutoCompounderCycle::Swap => self.cycle_stage = AutoCompounderCycle::Stake,
             AutoCompounderCycle::Stake => self.cycle_stage = AutoCompounderCycle::ClaimReward,
         }
+
+        emit_harvest_step(
+            seed_id,
+            Some(&self.id),
+            from,
+            String::from(&self.cycle_stage),
+        );
     }
 
-    pub(crate) fn increase_slippage(&mut self) {
-        if 100u128 - self.slippage < MAX_SLIPPAGE_ALLOWED {
+    pub(crate) fn increase_slippage(&mut self, seed_id: &str) {
+        if 100u128 - self.slippage < self.max_slippage {
             // increment slippage
             self.slippage -= 4;
 
@@ -61,6 +73,8 @@ impl StratFarmInfo {
         } else {
             self.state = AutoCompounderState::Ended;
             log!("Slippage too high. State was updated to Ended");
+
+            emit_farm_state_updated(seed_id, &self.id, String::from(&self.state));
         }
     }
 }
@@ -170,29 +184,6 @@ impl AutoCompounder {
         }
     }
 
-    pub(crate) fn compute_fees(&mut self, reward_amount: u128) -> (u128, u128, u128, u128) {
-        // apply fees to reward amount
-        let percent = Percentage::from(self.admin_fees.strategy_fee);
-        let all_fees_amount = percent.apply_to(reward_amount);
-
-        let percent = Percentage::from(self.admin_fees.sentries_fee);
-        let sentry_amount = percent.apply_to(all_fees_amount);
-
-        let percent = Percentage::from(self.admin_fees.strat_creator.fee_percentage);
-        let strat_creator_amount = percent.apply_to(all_fees_amount);
-        let treasury_amount = all_fees_amount - sentry_amount - strat_creator_amount;
-
-        let remaining_amount =
-            reward_amount - treasury_amount - sentry_amount - strat_creator_amount;
-
-        (
-            remaining_amount,
-            treasury_amount,
-            sentry_amount,
-            strat_creator_amount,
-        )
-    }
-
     pub(crate) fn get_farm_info(&self, farm_id: &str) -> StratFarmInfo {
         for farm in self.farms.iter() {
             if farm.id == farm_id {
@@ -570,43 +561,270 @@ impl AutoCompounder {
     }
 }
 
-// Versioned Farmer, used for lazy upgrade.
-// Which means this structure would upgrade automatically when used.
-// To achieve that, each time the new version comes in,
-// each function of this enum should be carefully re-code!
-// #[derive(BorshSerialize, BorshDeserialize)]
-// pub enum VersionedCompounder {
-//     V101(AutoCompounder),
-// }
-
-// impl VersionedCompounder {
-//     #[allow(dead_code)]
-//     pub(crate) fn new(
-//         strategy_fee: u128,
-//         treasury: AccountFee,
-//         strat_creator: AccountFee,
-//         sentry_fee: u128,
-//         exchange_contract_id: AccountId,
-//         farm_contract_id: AccountId,
-//         token1_address: AccountId,
-//         token2_address: AccountId,
-//         pool_id: u64,
-//         seed_id: String,
-//         seed_min_deposit: U128,
-//     ) -> Self {
-//         let admin_fee = AdminFees::new(strat_creator, sentry_fee, strategy_fee);
-
-//         VersionedCompounder::V101(AutoCompounder {
-//             admin_fees: admin_fee,
-//             exchange_contract_id,
-//             farm_contract_id,
-//             token1_address,
-//             token2_address,
-//             pool_id,
-//             seed_min_deposit,
-//             seed_id,
-//             farms: Vec::new(),
-//             harvest_timestamp: 0u64,
-//         })
-//     }
-// }
+impl Strategy for AutoCompounder {
+    fn kind(&self) -> String {
+        "REF_REGULAR".to_string()
+    }
+
+    fn seed_min_deposit(&self) -> U128 {
+        self.seed_min_deposit
+    }
+
+    fn admin_fees(&self) -> &AdminFees {
+        &self.admin_fees
+    }
+
+    fn admin_fees_mut(&mut self) -> &mut AdminFees {
+        &mut self.admin_fees
+    }
+
+    fn harvest_timestamp(&self) -> u64 {
+        self.harvest_timestamp
+    }
+
+    fn farms_len(&self) -> u64 {
+        self.farms.len() as u64
+    }
+
+    fn reward_tokens(&self) -> Vec<AccountId> {
+        self.farms
+            .iter()
+            .map(|farm| farm.reward_token.clone())
+            .collect()
+    }
+
+    fn is_running(&self) -> bool {
+        self.farms
+            .iter()
+            .any(|farm| farm.state == AutoCompounderState::Running)
+    }
+
+    fn farm_state(&self, farm_id: &str) -> String {
+        String::from(&self.get_farm_info(farm_id).state)
+    }
+
+    fn farm_cycle_stage(&self, farm_id: &str) -> String {
+        String::from(&self.get_farm_info(farm_id).cycle_stage)
+    }
+
+    fn set_farm_state(&mut self, farm_id: &str, state: AutoCompounderState) -> String {
+        let farm_info_mut = self.get_mut_farm_info(farm_id.to_string());
+        farm_info_mut.state = state;
+
+        String::from(&farm_info_mut.state)
+    }
+
+    fn set_farm_slippage(&mut self, farm_id: &str, slippage: u128) -> u128 {
+        let farm_info_mut = self.get_mut_farm_info(farm_id.to_string());
+        farm_info_mut.slippage = slippage;
+
+        farm_info_mut.slippage
+    }
+
+    fn remove_farm(&mut self, farm_id: &str) -> bool {
+        let len = self.farms.len();
+        self.farms.retain(|farm| farm.id != farm_id);
+
+        self.farms.len() != len
+    }
+
+    fn clear_farms(&mut self) {
+        for farm in self.farms.iter_mut() {
+            farm.state = AutoCompounderState::Cleared;
+        }
+    }
+
+    fn farm_cycle_in_progress(&self, farm_id: &str) -> bool {
+        self.farms
+            .iter()
+            .any(|farm| farm.id == farm_id && farm.cycle_stage != AutoCompounderCycle::ClaimReward)
+    }
+
+    fn cycle_in_progress(&self) -> bool {
+        self.farms
+            .iter()
+            .any(|farm| farm.cycle_stage != AutoCompounderCycle::ClaimReward)
+    }
+
+    fn stake(
+        &self,
+        token_id: String,
+        seed_id: String,
+        account_id: &AccountId,
+        shares: u128,
+    ) -> Promise {
+        AutoCompounder::stake(self, token_id, seed_id, account_id, shares)
+    }
+
+    fn unstake(
+        &self,
+        seed_id: String,
+        receiver_id: AccountId,
+        withdraw_amount: u128,
+        user_fft_shares: u128,
+    ) -> Promise {
+        AutoCompounder::unstake(
+            self,
+            wrap_mft_token_id(&self.pool_id.to_string()),
+            seed_id,
+            receiver_id,
+            withdraw_amount,
+            user_fft_shares,
+        )
+    }
+
+    fn restake(&self, shares: u128) -> Promise {
+        ext_ref_exchange::mft_transfer_call(
+            self.farm_contract_id.clone(),
+            wrap_mft_token_id(&self.pool_id.to_string()),
+            U128(shares),
+            "\"Free\"".to_string(),
+            self.exchange_contract_id.clone(),
+            1,
+            Gas(70_000_000_000_000),
+        )
+    }
+
+    fn emergency_withdraw(&self, shares: u128) -> Promise {
+        ext_ref_farming::unlock_and_withdraw_seed(
+            self.seed_id.clone(),
+            U128(0),
+            U128(shares),
+            self.farm_contract_id.clone(),
+            1,
+            Gas(120_000_000_000_000),
+        )
+    }
+
+    fn withdraw_held(
+        &self,
+        seed_id: String,
+        receiver_id: AccountId,
+        withdraw_amount: u128,
+        user_fft_shares: u128,
+    ) -> Promise {
+        self.transfer_held(receiver_id.clone(), withdraw_amount)
+            .then(callback_ref_finance::callback_withdraw_shares(
+                seed_id,
+                receiver_id,
+                withdraw_amount,
+                user_fft_shares,
+                0,
+                env::current_account_id(),
+                0,
+                Gas(20_000_000_000_000),
+            ))
+    }
+
+    fn transfer_held(&self, receiver_id: AccountId, amount: u128) -> Promise {
+        ext_ref_exchange::mft_transfer(
+            wrap_mft_token_id(&self.pool_id.to_string()),
+            receiver_id,
+            U128(amount),
+            Some("".to_string()),
+            self.exchange_contract_id.clone(),
+            1,
+            Gas(30_000_000_000_000),
+        )
+    }
+
+    fn harvest_step(
+        &mut self,
+        farm_id_str: String,
+        _strat_name: String,
+        treasure: AccountFee,
+    ) -> PromiseOrValue<u128> {
+        let (_, _, farm_id) = get_ids_from_farm(farm_id_str.to_string());
+        let farm_info = self.get_farm_info(&farm_id);
+
+        assert_strategy_not_cleared(farm_info.state);
+
+        match farm_info.cycle_stage {
+            AutoCompounderCycle::ClaimReward => {
+                PromiseOrValue::Promise(self.claim_reward(farm_id_str))
+            }
+            AutoCompounderCycle::Withdrawal => PromiseOrValue::Promise(
+                self.withdraw_of_reward(farm_id_str, treasure.current_amount),
+            ),
+            AutoCompounderCycle::Swap => {
+                PromiseOrValue::Promise(self.autocompounds_swap(farm_id_str, treasure))
+            }
+            AutoCompounderCycle::Stake => {
+                PromiseOrValue::Promise(self.autocompounds_liquidity_and_stake(farm_id_str))
+            }
+        }
+    }
+}
+
+/// Layout of StratFarmInfo used by AutoCompounderV1.
+/// Must not be changed, it is only used to read strategies created by previous versions.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StratFarmInfoV1 {
+    pub state: AutoCompounderState,
+    pub cycle_stage: AutoCompounderCycle,
+    pub slippage: u128,
+    pub last_reward_amount: u128,
+    pub last_fee_amount: u128,
+    pub pool_id_token1_reward: u64,
+    pub pool_id_token2_reward: u64,
+    pub reward_token: AccountId,
+    pub available_balance: Vec<Balance>,
+    pub id: String,
+}
+
+impl From<StratFarmInfoV1> for StratFarmInfo {
+    fn from(farm: StratFarmInfoV1) -> Self {
+        StratFarmInfo {
+            state: farm.state,
+            cycle_stage: farm.cycle_stage,
+            slippage: farm.slippage,
+            max_slippage: MAX_SLIPPAGE_ALLOWED,
+            last_reward_amount: farm.last_reward_amount,
+            last_fee_amount: farm.last_fee_amount,
+            pool_id_token1_reward: farm.pool_id_token1_reward,
+            pool_id_token2_reward: farm.pool_id_token2_reward,
+            reward_token: farm.reward_token,
+            available_balance: farm.available_balance,
+            id: farm.id,
+        }
+    }
+}
+
+/// Layout of AutoCompounder stored as VersionedStrategy::AutoCompounderV1.
+/// Must not be changed, it is upgraded to AutoCompounder when the strategy is accessed.
+#[derive(Debug, BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AutoCompounderV1 {
+    pub admin_fees: AdminFees,
+    pub exchange_contract_id: AccountId,
+    pub farm_contract_id: AccountId,
+    pub token1_address: AccountId,
+    pub token2_address: AccountId,
+    pub pool_id: u64,
+    pub seed_min_deposit: U128,
+    pub seed_id: String,
+    pub farms: Vec<StratFarmInfoV1>,
+    pub harvest_timestamp: u64,
+}
+
+impl From<AutoCompounderV1> for AutoCompounder {
+    fn from(compounder: AutoCompounderV1) -> Self {
+        AutoCompounder {
+            admin_fees: compounder.admin_fees,
+            exchange_contract_id: compounder.exchange_contract_id,
+            farm_contract_id: compounder.farm_contract_id,
+            token1_address: compounder.token1_address,
+            token2_address: compounder.token2_address,
+            pool_id: compounder.pool_id,
+            seed_min_deposit: compounder.seed_min_deposit,
+            seed_id: compounder.seed_id,
+            farms: compounder
+                .farms
+                .into_iter()
+                .map(|farm| farm.into())
+                .collect(),
+            harvest_timestamp: compounder.harvest_timestamp,
+        }
+    }
+}
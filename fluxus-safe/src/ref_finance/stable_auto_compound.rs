@@ -24,13 +24,21 @@ impl Contract {
         // Try to unclaim before change to Ended
         for farm in farms.iter() {
             if farm.farm_id == farm_id && farm.status != *"Running" {
-                let stable_compounder = self.get_strat_mut(&seed_id).get_stable_compounder_mut();
+                let mut strat = self.get_strat(&seed_id);
+                let stable_compounder = strat.get_stable_compounder_mut();
 
                 for strat_farm in stable_compounder.farms.iter_mut() {
                     if strat_farm.id == farm_id {
                         strat_farm.state = AutoCompounderState::Ended;
+                        emit_farm_state_updated(
+                            &seed_id,
+                            &strat_farm.id,
+                            String::from(&strat_farm.state),
+                        );
                     }
                 }
+
+                self.save_strat(&seed_id, &strat);
             }
         }
 
@@ -71,7 +79,9 @@ impl Contract {
 
         let (seed_id, _, farm_id) = get_ids_from_farm(farm_id_str.to_string());
 
-        let compounder = self.get_strat_mut(&seed_id).get_stable_compounder_mut();
+        let mut strat = self.get_strat(&seed_id);
+
+        let compounder = strat.get_stable_compounder_mut();
 
         let farm_info = compounder.get_mut_farm_info(&farm_id);
 
@@ -92,6 +102,8 @@ impl Contract {
             // if farm is ended, there is no more actions to do
             if farm_info.state == AutoCompounderState::Ended {
                 farm_info.state = AutoCompounderState::Cleared;
+                emit_farm_state_updated(&seed_id, &farm_info.id, String::from(&farm_info.state));
+                self.save_strat(&seed_id, &strat);
                 return PromiseOrValue::Value(0u128);
             } else {
                 panic!("ERR: zero rewards earned")
@@ -131,14 +143,17 @@ impl Contract {
         let (seed_id, _, farm_id) = get_ids_from_farm(farm_id_str);
 
         // update strategies with the same seed
-        let compounder = self.get_strat_mut(&seed_id).get_stable_compounder_mut();
+        let mut strat = self.get_strat(&seed_id);
+        let compounder = strat.get_stable_compounder_mut();
         compounder.update_strats_by_seed(rewards_map);
 
         // store the amount of reward earned
         let farm_info = compounder.get_mut_farm_info(&farm_id);
         farm_info.last_reward_amount += reward_amount.0;
 
-        farm_info.next_cycle();
+        farm_info.next_cycle(&seed_id);
+
+        self.save_strat(&seed_id, &strat);
 
         reward_amount.0
     }
@@ -153,18 +168,26 @@ impl Contract {
 
         let (seed_id, _, farm_id) = get_ids_from_farm(farm_id_str.to_string());
 
-        let data_mut = self.data_mut();
-
-        let compounder = data_mut
-            .strategies
-            .get_mut(&seed_id)
-            .expect(ERR21_TOKEN_NOT_REG)
-            .get_stable_compounder_mut();
+        let mut strat = self.get_strat(&seed_id);
+        let compounder = strat.get_stable_compounder_mut();
 
         let last_reward_amount = compounder.get_mut_farm_info(&farm_id).last_reward_amount;
 
         let (remaining_amount, protocol_amount, sentry_amount, strat_creator_amount) =
-            compounder.compute_fees(last_reward_amount);
+            self.internal_compute_fees(&seed_id, &compounder.admin_fees, last_reward_amount);
+
+        emit_harvest_fees(
+            &seed_id,
+            Some(&farm_id),
+            &compounder.get_mut_farm_info(&farm_id).reward_token,
+            last_reward_amount,
+            (
+                remaining_amount,
+                protocol_amount,
+                sentry_amount,
+                strat_creator_amount,
+            ),
+        );
 
         // storing the amount earned by the strat creator
         compounder.admin_fees.strat_creator.current_amount += strat_creator_amount;
@@ -176,7 +199,7 @@ impl Contract {
             .insert(env::current_account_id(), sentry_amount);
 
         // increase protocol amount to cover the case that the last transfer failed
-        data_mut.treasury.current_amount += protocol_amount;
+        self.data_mut().treasury.current_amount += protocol_amount;
 
         // remaining amount to reinvest
         compounder.get_mut_farm_info(&farm_id).last_reward_amount = remaining_amount;
@@ -184,12 +207,17 @@ impl Contract {
         // amount sent to ref, both remaining value and treasury
         let amount = remaining_amount + protocol_amount;
 
+        let exchange_contract_id = compounder.exchange_contract_id.clone();
+        let reward_token = compounder.get_mut_farm_info(&farm_id).reward_token.clone();
+
+        self.save_strat(&seed_id, &strat);
+
         PromiseOrValue::Promise(
             ext_reward_token::ft_transfer_call(
-                compounder.exchange_contract_id.clone(),
+                exchange_contract_id,
                 U128(amount), //Amount after withdraw the rewards
                 "".to_string(),
-                compounder.get_mut_farm_info(&farm_id).reward_token.clone(),
+                reward_token,
                 1,
                 Gas(40_000_000_000_000),
             )
@@ -217,10 +245,14 @@ impl Contract {
 
         let (seed_id, _, farm_id) = get_ids_from_farm(farm_id_str);
 
-        let compounder = self.get_strat_mut(&seed_id).get_stable_compounder_mut();
+        let mut strat = self.get_strat(&seed_id);
+
+        let compounder = strat.get_stable_compounder_mut();
         let farm_info_mut = compounder.get_mut_farm_info(&farm_id);
 
-        farm_info_mut.next_cycle();
+        farm_info_mut.next_cycle(&seed_id);
+
+        self.save_strat(&seed_id, &strat);
     }
 
     /// Callback to verify that transfer to treasure succeeded
@@ -255,13 +287,17 @@ impl Contract {
             return;
         }
 
-        let compounder = self.get_strat_mut(&seed_id).get_stable_compounder_mut();
+        let mut strat = self.get_strat(&seed_id);
+
+        let compounder = strat.get_stable_compounder_mut();
 
         // what if a new value was added to this var during the completion of this execution?
         // tx0 (add strat_creator_fees) -> tx1 (send strat_creator fees) -> tx2 -> (add strat_creator_fees) -> tx3 (update current amount to 0, because value was already sent)
         // this means that the value from tx2 was never sent to the strat_creator, losing the earned tokens
         compounder.admin_fees.strat_creator.current_amount = 0;
 
+        self.save_strat(&seed_id, &strat);
+
         log!("Transfer fees to the creator of the strategy succeeded");
     }
 
@@ -295,9 +331,12 @@ impl Contract {
 
         if min_amount_out.0 == 0u128 {
             log!("ERR_COULD_NOT_GET_TOKEN_RETURN");
-            let stable_compounder = self.get_strat_mut(&seed_id).get_stable_compounder_mut();
+            let mut strat = self.get_strat(&seed_id);
+            let stable_compounder = strat.get_stable_compounder_mut();
             let farm_info_mut = stable_compounder.get_mut_farm_info(&farm_id);
-            farm_info_mut.increase_slippage();
+            farm_info_mut.increase_slippage(&seed_id);
+
+            self.save_strat(&seed_id, &strat);
 
             return PromiseOrValue::Value(0u128);
         }
@@ -328,12 +367,14 @@ impl Contract {
         farm_id_str: String,
     ) {
         let (seed_id, _, farm_id) = get_ids_from_farm(farm_id_str);
-        let compounder_mut = self.get_strat_mut(&seed_id).get_stable_compounder_mut();
+        let mut strat = self.get_strat(&seed_id);
+        let compounder_mut = strat.get_stable_compounder_mut();
         let farm_info_mut = compounder_mut.get_mut_farm_info(&farm_id);
 
         // Do not panic if err == true, otherwise the slippage update will not be applied
         if swap_result.is_err() {
-            farm_info_mut.increase_slippage();
+            farm_info_mut.increase_slippage(&seed_id);
+            self.save_strat(&seed_id, &strat);
             log!("ERR_SECOND_SWAP_FAILED");
             return;
         }
@@ -349,7 +390,9 @@ impl Contract {
         farm_info_mut.slippage = 100 - MIN_SLIPPAGE_ALLOWED;
 
         // after both swaps succeeded, it's ready to stake
-        farm_info_mut.next_cycle();
+        farm_info_mut.next_cycle(&seed_id);
+
+        self.save_strat(&seed_id, &strat);
     }
 
     #[private]
@@ -387,7 +430,8 @@ impl Contract {
         }
 
         let (seed_id, _, _) = get_ids_from_farm(farm_id_str.clone());
-        let compounder = self.get_strat_mut(&seed_id).get_stable_compounder_mut();
+        let mut strat = self.get_strat(&seed_id);
+        let compounder = strat.get_stable_compounder_mut();
 
         // reset default sentry address and get last earned amount
         let amount = compounder
@@ -396,6 +440,8 @@ impl Contract {
             .remove(&env::current_account_id())
             .unwrap();
 
+        self.save_strat(&seed_id, &strat);
+
         log!("Sending {} to sentry account {}", amount, sentry_acc_id);
 
         ext_reward_token::ft_transfer(
@@ -433,13 +479,17 @@ impl Contract {
         if ft_transfer_result.is_err() {
             log!("Transfer to sentry failed".to_string());
 
-            let compounder = self.get_strat_mut(&seed_id).get_stable_compounder_mut();
+            let mut strat = self.get_strat(&seed_id);
+
+            let compounder = strat.get_stable_compounder_mut();
 
             // store amount earned by sentry to be redeemed
             compounder
                 .admin_fees
                 .sentries
                 .insert(sentry_id, amount_earned);
+
+            self.save_strat(&seed_id, &strat);
         } else {
             log!("Transfer to sentry succeeded".to_string());
         }
@@ -449,9 +499,12 @@ impl Contract {
 
         // if farm is ended, there is no more actions to do
         if farm_info.state == AutoCompounderState::Ended {
-            let compounder = self.get_strat_mut(&seed_id).get_stable_compounder_mut();
+            let mut strat = self.get_strat(&seed_id);
+            let compounder = strat.get_stable_compounder_mut();
             let farm_info = compounder.get_mut_farm_info(&farm_id);
             farm_info.state = AutoCompounderState::Cleared;
+            emit_farm_state_updated(&seed_id, &farm_info.id, String::from(&farm_info.state));
+            self.save_strat(&seed_id, &strat);
 
             log!("There farm {} ended. Strategy is now Cleared.", farm_id_str);
             return PromiseOrValue::Value(0u64);
@@ -508,16 +561,23 @@ impl Contract {
         data.seed_id_amount
             .insert(&seed_id, &(total_seed + shares_received));
 
-        let compounder_mut = self.get_strat_mut(&seed_id).get_stable_compounder_mut();
+        let mut strat = self.get_strat(&seed_id);
+
+        let compounder_mut = strat.get_stable_compounder_mut();
         let farm_info_mut = compounder_mut.get_mut_farm_info(&farm_id);
 
         // ensure that in the next run we won't have a balance unless previous steps succeeds
         farm_info_mut.available_balance[farm_info_mut.token_position as usize] = 0u128;
 
+        let pool_id = compounder_mut.pool_id;
+        let exchange_contract_id = compounder_mut.exchange_contract_id.clone();
+
+        self.save_strat(&seed_id, &strat);
+
         ext_ref_exchange::get_pool_shares(
-            compounder_mut.pool_id,
+            pool_id,
             env::current_account_id(),
-            compounder_mut.exchange_contract_id.clone(),
+            exchange_contract_id,
             0,
             Gas(10_000_000_000_000),
         )
@@ -546,7 +606,8 @@ impl Contract {
         );
 
         let (seed_id, token_id, farm_id) = get_ids_from_farm(farm_id_str);
-        let compounder_mut = self.get_strat_mut(&seed_id).get_stable_compounder_mut();
+        let mut strat = self.get_strat(&seed_id);
+        let compounder_mut = strat.get_stable_compounder_mut();
 
         let exchange_contract_id: AccountId = compounder_mut.exchange_contract_id.clone();
         let farm_contract_id: AccountId = compounder_mut.farm_contract_id.clone();
@@ -555,12 +616,27 @@ impl Contract {
 
         let farm_info_mut = compounder_mut.get_mut_farm_info(&farm_id);
 
-        farm_info_mut.next_cycle();
+        farm_info_mut.next_cycle(&seed_id);
 
-        let accumulated_shares = total_shares_result.unwrap().0;
+        // the claimable shares are held on the exchange too, but are not staked anymore
+        let accumulated_shares = total_shares_result
+            .unwrap()
+            .0
+            .saturating_sub(self.get_total_claimable_shares(&seed_id));
 
         // Prevents failing on stake if below minimum deposit
         let min_deposit = compounder_mut.seed_min_deposit;
+
+        self.save_strat(&seed_id, &strat);
+
+        self.internal_accrue_management_fee(&seed_id);
+        self.internal_charge_performance_fee(&seed_id);
+
+        // harvest started before an emergency exit, the shares stay on the exchange
+        if self.is_emergency_exited(&seed_id) {
+            return PromiseOrValue::Value(0u128);
+        }
+
         log!(
             "min_deposit {} and shares {}",
             min_deposit.0,
@@ -574,12 +650,25 @@ impl Contract {
             return PromiseOrValue::Value(0u128);
         }
 
-        PromiseOrValue::Promise(self.call_stake(
-            exchange_contract_id,
-            farm_contract_id,
-            token_id,
-            U128(accumulated_shares),
-            "\"Free\"".to_string(),
-        ))
+        // the pending deposits held on the exchange are staked with the harvested shares,
+        // they stay pending until the farm accepts them
+        let pending = self.get_pending_deposits(seed_id.clone());
+
+        PromiseOrValue::Promise(
+            self.call_stake(
+                exchange_contract_id,
+                farm_contract_id,
+                token_id,
+                U128(accumulated_shares),
+                "\"Free\"".to_string(),
+            )
+            .then(callback_share_price::callback_post_harvest_stake(
+                seed_id,
+                pending,
+                env::current_account_id(),
+                0,
+                Gas(10_000_000_000_000),
+            )),
+        )
     }
 }
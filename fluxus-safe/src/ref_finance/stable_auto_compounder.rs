@@ -46,16 +46,25 @@ pub struct StableStratFarmInfo {
 }
 
 impl StableStratFarmInfo {
-    pub(crate) fn next_cycle(&mut self) {
+    pub(crate) fn next_cycle(&mut self, seed_id: &str) {
+        let from = String::from(&self.cycle_stage);
+
         match self.cycle_stage {
             AutoCompounderCycle::ClaimReward => self.cycle_stage = AutoCompounderCycle::Withdrawal,
             AutoCompounderCycle::Withdrawal => self.cycle_stage = AutoCompounderCycle::Swap,
             AutoCompounderCycle::Swap => self.cycle_stage = AutoCompounderCycle::Stake,
             AutoCompounderCycle::Stake => self.cycle_stage = AutoCompounderCycle::ClaimReward,
         }
+
+        emit_harvest_step(
+            seed_id,
+            Some(&self.id),
+            from,
+            String::from(&self.cycle_stage),
+        );
     }
 
-    pub fn increase_slippage(&mut self) {
+    pub fn increase_slippage(&mut self, seed_id: &str) {
         if 100u128 - self.slippage < MAX_SLIPPAGE_ALLOWED {
             // increment slippage
             self.slippage -= 4;
@@ -67,6 +76,8 @@ impl StableStratFarmInfo {
         } else {
             self.state = AutoCompounderState::Ended;
             log!("Slippage too high. State was updated to Ended");
+
+            emit_farm_state_updated(seed_id, &self.id, String::from(&self.state));
         }
     }
 }
@@ -126,29 +137,6 @@ impl StableAutoCompounder {
         }
     }
 
-    pub(crate) fn compute_fees(&mut self, reward_amount: u128) -> (u128, u128, u128, u128) {
-        // apply fees to reward amount
-        let percent = Percentage::from(self.admin_fees.strategy_fee);
-        let all_fees_amount = percent.apply_to(reward_amount);
-
-        let percent = Percentage::from(self.admin_fees.sentries_fee);
-        let sentry_amount = percent.apply_to(all_fees_amount);
-
-        let percent = Percentage::from(self.admin_fees.strat_creator.fee_percentage);
-        let strat_creator_amount = percent.apply_to(all_fees_amount);
-        let treasury_amount = all_fees_amount - sentry_amount - strat_creator_amount;
-
-        let remaining_amount =
-            reward_amount - treasury_amount - sentry_amount - strat_creator_amount;
-
-        (
-            remaining_amount,
-            treasury_amount,
-            sentry_amount,
-            strat_creator_amount,
-        )
-    }
-
     pub fn get_farm_info(&self, farm_id: &str) -> StableStratFarmInfo {
         for farm in self.farms.iter() {
             if farm.id == farm_id {
@@ -380,7 +368,7 @@ impl StableAutoCompounder {
             )
             .then(
                 callback_stable_ref_finance::stable_callback_post_creator_ft_transfer(
-                    seed_id,
+                    seed_id.clone(),
                     env::current_account_id(),
                     0,
                     Gas(10_000_000_000_000),
@@ -396,7 +384,7 @@ impl StableAutoCompounder {
 
             farm_info_mut.available_balance[farm_info_mut.token_position as usize] = reward_amount;
 
-            farm_info_mut.next_cycle();
+            farm_info_mut.next_cycle(&seed_id);
             return PromiseOrValue::Value(0u128);
         }
 
@@ -450,3 +438,198 @@ impl StableAutoCompounder {
         ))
     }
 }
+
+impl Strategy for StableAutoCompounder {
+    fn kind(&self) -> String {
+        "REF_STABLE".to_string()
+    }
+
+    fn seed_min_deposit(&self) -> U128 {
+        self.seed_min_deposit
+    }
+
+    fn admin_fees(&self) -> &AdminFees {
+        &self.admin_fees
+    }
+
+    fn admin_fees_mut(&mut self) -> &mut AdminFees {
+        &mut self.admin_fees
+    }
+
+    fn harvest_timestamp(&self) -> u64 {
+        self.harvest_timestamp
+    }
+
+    fn farms_len(&self) -> u64 {
+        self.farms.len() as u64
+    }
+
+    fn reward_tokens(&self) -> Vec<AccountId> {
+        self.farms
+            .iter()
+            .map(|farm| farm.reward_token.clone())
+            .collect()
+    }
+
+    fn is_running(&self) -> bool {
+        self.farms
+            .iter()
+            .any(|farm| farm.state == AutoCompounderState::Running)
+    }
+
+    fn farm_state(&self, farm_id: &str) -> String {
+        String::from(&self.get_farm_info(farm_id).state)
+    }
+
+    fn farm_cycle_stage(&self, farm_id: &str) -> String {
+        String::from(&self.get_farm_info(farm_id).cycle_stage)
+    }
+
+    fn set_farm_state(&mut self, farm_id: &str, state: AutoCompounderState) -> String {
+        let farm_info_mut = self.get_mut_farm_info(&farm_id.to_string());
+        farm_info_mut.state = state;
+
+        String::from(&farm_info_mut.state)
+    }
+
+    fn set_farm_slippage(&mut self, farm_id: &str, slippage: u128) -> u128 {
+        let farm_info_mut = self.get_mut_farm_info(&farm_id.to_string());
+        farm_info_mut.slippage = slippage;
+
+        farm_info_mut.slippage
+    }
+
+    fn remove_farm(&mut self, farm_id: &str) -> bool {
+        let len = self.farms.len();
+        self.farms.retain(|farm| farm.id != farm_id);
+
+        self.farms.len() != len
+    }
+
+    fn clear_farms(&mut self) {
+        for farm in self.farms.iter_mut() {
+            farm.state = AutoCompounderState::Cleared;
+        }
+    }
+
+    fn farm_cycle_in_progress(&self, farm_id: &str) -> bool {
+        self.farms
+            .iter()
+            .any(|farm| farm.id == farm_id && farm.cycle_stage != AutoCompounderCycle::ClaimReward)
+    }
+
+    fn cycle_in_progress(&self) -> bool {
+        self.farms
+            .iter()
+            .any(|farm| farm.cycle_stage != AutoCompounderCycle::ClaimReward)
+    }
+
+    fn stake(
+        &self,
+        token_id: String,
+        seed_id: String,
+        account_id: &AccountId,
+        shares: u128,
+    ) -> Promise {
+        StableAutoCompounder::stake(self, token_id, seed_id, account_id, shares)
+    }
+
+    fn unstake(
+        &self,
+        seed_id: String,
+        receiver_id: AccountId,
+        withdraw_amount: u128,
+        user_fft_shares: u128,
+    ) -> Promise {
+        StableAutoCompounder::unstake(
+            self,
+            wrap_mft_token_id(&self.pool_id.to_string()),
+            seed_id,
+            receiver_id,
+            withdraw_amount,
+            user_fft_shares,
+        )
+    }
+
+    fn restake(&self, shares: u128) -> Promise {
+        ext_ref_exchange::mft_transfer_call(
+            self.farm_contract_id.clone(),
+            wrap_mft_token_id(&self.pool_id.to_string()),
+            U128(shares),
+            "\"Free\"".to_string(),
+            self.exchange_contract_id.clone(),
+            1,
+            Gas(70_000_000_000_000),
+        )
+    }
+
+    fn emergency_withdraw(&self, shares: u128) -> Promise {
+        ext_ref_farming::unlock_and_withdraw_seed(
+            self.seed_id.clone(),
+            U128(0),
+            U128(shares),
+            self.farm_contract_id.clone(),
+            1,
+            Gas(120_000_000_000_000),
+        )
+    }
+
+    fn withdraw_held(
+        &self,
+        seed_id: String,
+        receiver_id: AccountId,
+        withdraw_amount: u128,
+        user_fft_shares: u128,
+    ) -> Promise {
+        self.transfer_held(receiver_id.clone(), withdraw_amount)
+            .then(
+                callback_stable_ref_finance::stable_callback_withdraw_shares(
+                    seed_id,
+                    receiver_id,
+                    withdraw_amount,
+                    user_fft_shares,
+                    0,
+                    env::current_account_id(),
+                    0,
+                    Gas(20_000_000_000_000),
+                ),
+            )
+    }
+
+    fn transfer_held(&self, receiver_id: AccountId, amount: u128) -> Promise {
+        ext_ref_exchange::mft_transfer(
+            wrap_mft_token_id(&self.pool_id.to_string()),
+            receiver_id,
+            U128(amount),
+            Some("".to_string()),
+            self.exchange_contract_id.clone(),
+            1,
+            Gas(30_000_000_000_000),
+        )
+    }
+
+    fn harvest_step(
+        &mut self,
+        farm_id_str: String,
+        _strat_name: String,
+        treasure: AccountFee,
+    ) -> PromiseOrValue<u128> {
+        let (_, _, farm_id) = get_ids_from_farm(farm_id_str.to_string());
+        let farm_info = self.get_farm_info(&farm_id);
+
+        assert_strategy_not_cleared(farm_info.state);
+
+        match farm_info.cycle_stage {
+            AutoCompounderCycle::ClaimReward => {
+                PromiseOrValue::Promise(self.claim_reward(farm_id_str))
+            }
+            AutoCompounderCycle::Withdrawal => PromiseOrValue::Promise(
+                self.withdraw_of_reward(farm_id_str, treasure.current_amount),
+            ),
+            AutoCompounderCycle::Swap => self.autocompounds_swap(farm_id_str, treasure),
+            AutoCompounderCycle::Stake => {
+                PromiseOrValue::Promise(self.autocompounds_liquidity_and_stake(farm_id_str))
+            }
+        }
+    }
+}
@@ -0,0 +1,378 @@
+use crate::*;
+use near_sdk::collections::Vector;
+
+/// Max number of snapshots kept for each seed_id, the oldest one is overwritten when full
+pub const SHARE_PRICE_HISTORY_SIZE: usize = 720;
+
+/// Harvests in the same interval update the same snapshot, so the history covers at least 30 days
+pub const SHARE_PRICE_SNAPSHOT_INTERVAL_MS: u64 = 60 * 60 * 1000;
+
+/// Precision of the price per share, 1:1 is 10^24
+pub const SHARE_PRICE_PRECISION: u128 = 1_000_000_000_000_000_000_000_000;
+
+const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+pub(crate) const YEAR_MS: u64 = 365 * DAY_MS;
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SharePriceSnapshot {
+    /// Block timestamp, in ms, of the latest harvest of the interval
+    pub timestamp: u64,
+
+    /// Amount of seed_id owned by one fft_share, multiplied by SHARE_PRICE_PRECISION
+    pub price_per_share: U128,
+
+    /// Amount of seed_id added by the harvests of the interval
+    pub shares_added: U128,
+}
+
+/// Ring buffer of the share price snapshots of a seed_id.
+/// Each snapshot is a storage entry of its own, a harvest only writes the one it updates.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct SharePriceHistory {
+    snapshots: Vector<SharePriceSnapshot>,
+
+    /// Position of the oldest snapshot once the buffer is full
+    oldest: u32,
+}
+
+impl SharePriceHistory {
+    fn new(seed_id: &str) -> Self {
+        Self {
+            snapshots: Vector::new(StorageKey::SharePriceSnapshots {
+                seed_id: seed_id.to_string(),
+            }),
+            oldest: 0,
+        }
+    }
+
+    fn push(&mut self, snapshot: SharePriceSnapshot) {
+        if let Some((index, mut latest)) = self.latest() {
+            if latest.timestamp / SHARE_PRICE_SNAPSHOT_INTERVAL_MS
+                == snapshot.timestamp / SHARE_PRICE_SNAPSHOT_INTERVAL_MS
+            {
+                latest.timestamp = snapshot.timestamp;
+                latest.price_per_share = snapshot.price_per_share;
+                latest.shares_added = U128(latest.shares_added.0 + snapshot.shares_added.0);
+                self.snapshots.replace(index, &latest);
+                return;
+            }
+        }
+
+        if self.snapshots.len() < SHARE_PRICE_HISTORY_SIZE as u64 {
+            self.snapshots.push(&snapshot);
+        } else {
+            self.snapshots.replace(self.oldest as u64, &snapshot);
+            self.oldest = (self.oldest + 1) % SHARE_PRICE_HISTORY_SIZE as u32;
+        }
+    }
+
+    /// Returns the position and the value of the latest snapshot
+    fn latest(&self) -> Option<(u64, SharePriceSnapshot)> {
+        let len = self.snapshots.len();
+        if len == 0 {
+            return None;
+        }
+
+        let latest = (self.oldest as u64 + len - 1) % len;
+        self.snapshots
+            .get(latest)
+            .map(|snapshot| (latest, snapshot))
+    }
+
+    /// Returns the snapshots from the oldest to the latest
+    fn ordered(&self) -> Vec<SharePriceSnapshot> {
+        let len = self.snapshots.len();
+        (0..len)
+            .map(|i| self.snapshots.get((self.oldest as u64 + i) % len).unwrap())
+            .collect()
+    }
+}
+
+/// Realized APY of a seed_id, in basis points.
+/// None when the history does not cover the window yet.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SeedApy {
+    pub one_day: Option<u32>,
+    pub seven_days: Option<u32>,
+    pub thirty_days: Option<u32>,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns the share price snapshots of seed_id, from the oldest to the latest
+    pub fn get_share_price_history(&self, seed_id: String) -> Vec<SharePriceSnapshot> {
+        self.data()
+            .share_price_history_by_seed_id
+            .get(&seed_id)
+            .map(|history| history.ordered())
+            .unwrap_or_default()
+    }
+
+    /// Returns the APY realized by seed_id over the last 1, 7 and 30 days, as a simple yearly rate.
+    /// Clients can compound it from the snapshots of get_share_price_history.
+    pub fn get_apy(&self, seed_id: String) -> SeedApy {
+        let snapshots = self.get_share_price_history(seed_id);
+
+        SeedApy {
+            one_day: realized_apy(&snapshots, DAY_MS),
+            seven_days: realized_apy(&snapshots, 7 * DAY_MS),
+            thirty_days: realized_apy(&snapshots, 30 * DAY_MS),
+        }
+    }
+
+    /// Records the share price once the farm accepted the shares staked by a harvest.
+    /// The pending deposits staked with them were not added by the harvest, they stop being pending.
+    #[private]
+    pub fn callback_post_harvest_stake(
+        &mut self,
+        #[callback_result] stake_result: Result<U128, PromiseError>,
+        seed_id: String,
+        pending: U128,
+    ) -> U128 {
+        let used = match stake_result {
+            Ok(used) => used.0,
+            Err(_) => {
+                log!("ERR: failed to stake the harvested shares of {}", seed_id);
+                return U128(0);
+            }
+        };
+
+        self.internal_stake_pending_deposits(&seed_id, std::cmp::min(used, pending.0));
+        self.record_share_price(&seed_id, used.saturating_sub(pending.0));
+
+        U128(used)
+    }
+}
+
+impl Contract {
+    /// Records the current price per share of seed_id, called once the farm accepted the
+    /// shares_added by the harvest
+    pub(crate) fn record_share_price(&mut self, seed_id: &str, shares_added: u128) {
+        let seed_id = seed_id.to_string();
+        let fft_share_id = match self.data().fft_share_by_seed_id.get(&seed_id) {
+            Some(fft_share_id) => fft_share_id,
+            None => return,
+        };
+
+        let total_fft = self.total_supply_amount(fft_share_id);
+        if total_fft == 0 {
+            return;
+        }
+
+        let price_per_share = (U256::from(self.seed_total_amount(&seed_id))
+            * U256::from(SHARE_PRICE_PRECISION)
+            / U256::from(total_fft))
+        .as_u128();
+
+        let mut history = self
+            .data()
+            .share_price_history_by_seed_id
+            .get(&seed_id)
+            .unwrap_or_else(|| SharePriceHistory::new(&seed_id));
+
+        history.push(SharePriceSnapshot {
+            timestamp: env::block_timestamp_ms(),
+            price_per_share: U128(price_per_share),
+            shares_added: U128(shares_added),
+        });
+
+        self.data_mut()
+            .share_price_history_by_seed_id
+            .insert(&seed_id, &history);
+    }
+
+    /// Removes the snapshots of seed_id, called when its strategy is deleted
+    pub(crate) fn internal_remove_share_price_history(&mut self, seed_id: &str) {
+        if let Some(mut history) = self
+            .data_mut()
+            .share_price_history_by_seed_id
+            .remove(&seed_id.to_string())
+        {
+            history.snapshots.clear();
+        }
+    }
+}
+
+/// Compares the latest snapshot with the newest one that is at least window older
+fn realized_apy(snapshots: &[SharePriceSnapshot], window: u64) -> Option<u32> {
+    let latest = snapshots.last()?;
+    let start = snapshots
+        .iter()
+        .rev()
+        .find(|snapshot| snapshot.timestamp + window <= latest.timestamp)?;
+
+    if start.price_per_share.0 == 0 {
+        return None;
+    }
+
+    let growth = latest
+        .price_per_share
+        .0
+        .saturating_sub(start.price_per_share.0);
+    let apy = U256::from(growth) * U256::from(10_000u128) * U256::from(YEAR_MS)
+        / (U256::from(start.price_per_share.0) * U256::from(latest.timestamp - start.timestamp));
+
+    // the management fee dilutes the price between harvests, a lower price is reported as 0
+    Some(std::cmp::min(apy, U256::from(u32::MAX)).as_u32())
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::testing_env;
+
+    const SEED_ID: &str = "exchange.near@1";
+
+    fn get_context(timestamp_ms: u64) -> VMContextBuilder {
+        let mut builder = VMContextBuilder::new();
+        builder
+            .current_account_id(to_account_id("auto_compounder.near"))
+            .predecessor_account_id(to_account_id("auto_compounder.near"))
+            .block_timestamp(timestamp_ms * 1_000_000);
+        builder
+    }
+
+    pub fn to_account_id(value: &str) -> AccountId {
+        value.parse().unwrap()
+    }
+
+    /// 1_000 fft_shares worth 1_000 shares of SEED_ID
+    fn create_contract() -> Contract {
+        testing_env!(get_context(0).build());
+        let mut contract = Contract::new(
+            to_account_id("auto_compounder.near"),
+            to_account_id("treasure.near"),
+        );
+
+        contract
+            .data_mut()
+            .fft_share_by_seed_id
+            .insert(&SEED_ID.to_string(), &"fft_share_1".to_string());
+        contract
+            .data_mut()
+            .seed_id_by_fft_share
+            .insert(&"fft_share_1".to_string(), &SEED_ID.to_string());
+        contract.register_seed("fft_share_1".to_string());
+        contract.mft_mint("fft_share_1".to_string(), 1_000, to_account_id("user.near"));
+        contract
+            .data_mut()
+            .seed_id_amount
+            .insert(&SEED_ID.to_string(), &1_000u128);
+
+        contract
+    }
+
+    /// Harvests shares_added at timestamp_ms
+    fn harvest(contract: &mut Contract, timestamp_ms: u64, shares_added: u128) {
+        testing_env!(get_context(timestamp_ms).build());
+        let total_seed = contract.seed_total_amount(&SEED_ID.to_string());
+        contract
+            .data_mut()
+            .seed_id_amount
+            .insert(&SEED_ID.to_string(), &(total_seed + shares_added));
+        contract.record_share_price(SEED_ID, shares_added);
+    }
+
+    #[test]
+    fn test_record_share_price() {
+        let mut contract = create_contract();
+
+        harvest(&mut contract, DAY_MS, 10);
+        // same interval, merged into the previous snapshot
+        harvest(&mut contract, DAY_MS + 1_000, 10);
+        harvest(&mut contract, 2 * DAY_MS, 0);
+
+        assert_eq!(
+            contract.get_share_price_history(SEED_ID.to_string()),
+            vec![
+                SharePriceSnapshot {
+                    timestamp: DAY_MS + 1_000,
+                    price_per_share: U128(1_020_000_000_000_000_000_000_000),
+                    shares_added: U128(20),
+                },
+                SharePriceSnapshot {
+                    timestamp: 2 * DAY_MS,
+                    price_per_share: U128(1_020_000_000_000_000_000_000_000),
+                    shares_added: U128(0),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_record_after_harvest_stake() {
+        let mut contract = create_contract();
+        testing_env!(get_context(DAY_MS).build());
+        contract
+            .data_mut()
+            .seed_id_amount
+            .insert(&SEED_ID.to_string(), &1_050u128);
+
+        // the farm refused the shares, nothing was added yet
+        contract.callback_post_harvest_stake(
+            Err(PromiseError::Failed),
+            SEED_ID.to_string(),
+            U128(0),
+        );
+        assert!(contract
+            .get_share_price_history(SEED_ID.to_string())
+            .is_empty());
+
+        // 30 pending shares were staked with the 50 harvested
+        assert_eq!(
+            contract.callback_post_harvest_stake(Ok(U128(80)), SEED_ID.to_string(), U128(30)),
+            U128(80)
+        );
+        let history = contract.get_share_price_history(SEED_ID.to_string());
+        assert_eq!(history[0].shares_added, U128(50));
+        assert_eq!(
+            history[0].price_per_share,
+            U128(1_050_000_000_000_000_000_000_000)
+        );
+    }
+
+    #[test]
+    fn test_history_is_bounded() {
+        let mut contract = create_contract();
+
+        for i in 0..(SHARE_PRICE_HISTORY_SIZE as u64 + 2) {
+            harvest(&mut contract, i * SHARE_PRICE_SNAPSHOT_INTERVAL_MS, 1);
+        }
+
+        let history = contract.get_share_price_history(SEED_ID.to_string());
+        assert_eq!(history.len(), SHARE_PRICE_HISTORY_SIZE);
+        assert_eq!(history[0].timestamp, 2 * SHARE_PRICE_SNAPSHOT_INTERVAL_MS);
+        assert_eq!(
+            history.last().unwrap().timestamp,
+            (SHARE_PRICE_HISTORY_SIZE as u64 + 1) * SHARE_PRICE_SNAPSHOT_INTERVAL_MS
+        );
+    }
+
+    #[test]
+    fn test_apy() {
+        let mut contract = create_contract();
+
+        harvest(&mut contract, 0, 0);
+        assert_eq!(
+            contract.get_apy(SEED_ID.to_string()),
+            SeedApy {
+                one_day: None,
+                seven_days: None,
+                thirty_days: None
+            }
+        );
+
+        // the price grows 10% in 7 days, then stays flat for a day
+        harvest(&mut contract, 7 * DAY_MS, 100);
+        harvest(&mut contract, 8 * DAY_MS, 0);
+
+        let apy = contract.get_apy(SEED_ID.to_string());
+        assert_eq!(apy.one_day, Some(0));
+        // 10% * 365 / 8
+        assert_eq!(apy.seven_days, Some(45_625));
+        assert_eq!(apy.thirty_days, None);
+    }
+}
@@ -0,0 +1,458 @@
+use crate::*;
+use near_contract_standards::fungible_token::metadata::{FungibleTokenMetadata, FT_METADATA_SPEC};
+use near_sdk::json_types::Base64VecU8;
+
+/// NEAR transferred to a new share token account, covers the storage of its code and state.
+/// Holders pay their own storage on the share token with storage_deposit.
+pub const SHARE_TOKEN_DEPLOY_DEPOSIT: Balance = 5_000_000_000_000_000_000_000_000;
+
+/// Decimals of the exchanges LP shares, used by the share tokens deployed on create_strategy
+pub const LP_SHARE_DECIMALS: u8 = 24;
+
+pub const GAS_FOR_SHARE_TOKEN_INIT: Gas = Gas(20_000_000_000_000);
+/// Gas of each mint, burn or transfer mirrored on a share token
+pub const GAS_FOR_SHARE_TOKEN_MIRROR: Gas = Gas(5_000_000_000_000);
+pub const GAS_FOR_SHARE_TOKEN_BALANCE: Gas = Gas(5_000_000_000_000);
+pub const GAS_FOR_SHARE_TOKEN_CALLBACK: Gas = Gas(10_000_000_000_000);
+
+/// Arguments of the share token `new` method
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct ShareTokenInitArgs<'a> {
+    owner_id: &'a AccountId,
+    fft_share: &'a str,
+    metadata: FungibleTokenMetadata,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Stores the wasm deployed for the next share tokens, built from fluxus-share-token.
+    /// Once set, creating a LP strategy also deploys its share token.
+    pub fn set_share_token_code(&mut self, code: Base64VecU8) {
+        self.is_owner();
+        let code: Vec<u8> = code.into();
+        self.data_mut().share_token_code.set(&code);
+    }
+
+    /// Deploys the share token of an existing strategy, the attached deposit funds the new account.
+    /// Args:
+    ///   seed_id: exchange@pool_id or pembrock@token
+    ///   decimals: decimals of the strategy shares
+    #[payable]
+    pub fn deploy_share_token(&mut self, seed_id: String, decimals: u8) -> Promise {
+        self.is_owner();
+        self.internal_deploy_share_token(&seed_id, decimals)
+    }
+
+    /// Returns the NEP-141 share token of fft_share, if it was deployed
+    pub fn get_share_token(&self, fft_share: String) -> Option<AccountId> {
+        self.data().share_token_by_fft_share.get(&fft_share)
+    }
+
+    /// Sets the share token balance of account_id to its fft_shares, repairing a mirrored call
+    /// that failed, e.g. a mint to an account that had not called storage_deposit on the token.
+    /// Also gives the share token to the holders of fft_share from before it was deployed.
+    pub fn sync_share_token(&mut self, fft_share: String, account_id: AccountId) -> Promise {
+        let share_token_id = self
+            .get_share_token(fft_share.clone())
+            .expect("ERR: share token not deployed");
+
+        ext_share_token::ft_balance_of(
+            account_id.clone(),
+            share_token_id,
+            NO_DEPOSIT,
+            GAS_FOR_SHARE_TOKEN_BALANCE,
+        )
+        .then(callback_share_token::callback_post_share_token_balance(
+            fft_share,
+            account_id,
+            env::current_account_id(),
+            NO_DEPOSIT,
+            GAS_FOR_SHARE_TOKEN_CALLBACK,
+        ))
+    }
+
+    /// Moves the fft_shares of a transfer made on the share token, which reverts it if this fails
+    pub fn on_share_token_transfer(
+        &mut self,
+        fft_share: String,
+        sender_id: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+    ) {
+        self.assert_contract_running();
+        require!(
+            self.get_share_token(fft_share.clone()) == Some(env::predecessor_account_id()),
+            "ERR_NOT_ALLOWED"
+        );
+
+        self.internal_share_balance_transfer(&fft_share, &sender_id, &receiver_id, amount.0);
+
+        MtEvent::MtTransfer(&[MtTransferData {
+            authorized_id: None,
+            old_owner_id: &sender_id,
+            new_owner_id: &receiver_id,
+            token_ids: &[&fft_share],
+            amounts: &[amount],
+            memo: Some("share token"),
+        }])
+        .emit();
+    }
+
+    /// Registers the share token once it was deployed and initialized
+    #[private]
+    pub fn callback_post_share_token_deploy(
+        &mut self,
+        #[callback_result] deploy_result: Result<(), PromiseError>,
+        fft_share: String,
+        share_token_id: AccountId,
+    ) {
+        if deploy_result.is_err() {
+            log!(
+                "ERR_SHARE_TOKEN_DEPLOY: {} was not deployed for {}",
+                share_token_id,
+                fft_share
+            );
+            return;
+        }
+
+        self.data_mut()
+            .share_token_by_fft_share
+            .insert(&fft_share, &share_token_id);
+    }
+
+    /// Mints or burns the difference between the fft_shares of account_id and its share token
+    #[private]
+    pub fn callback_post_share_token_balance(
+        &mut self,
+        #[callback_result] balance_result: Result<U128, PromiseError>,
+        fft_share: String,
+        account_id: AccountId,
+    ) {
+        let token_balance = match balance_result {
+            Ok(balance) => balance.0,
+            Err(_) => env::panic_str("ERR: failed to get the share token balance"),
+        };
+        let share_token_id = self.get_share_token(fft_share.clone()).unwrap();
+        let fft_balance = self.users_fft_share_amount(fft_share, account_id.clone());
+
+        if fft_balance > token_balance {
+            ext_share_token::mint(
+                account_id,
+                U128(fft_balance - token_balance),
+                share_token_id,
+                NO_DEPOSIT,
+                GAS_FOR_SHARE_TOKEN_MIRROR,
+            );
+        } else if fft_balance < token_balance {
+            ext_share_token::burn(
+                account_id,
+                U128(token_balance - fft_balance),
+                share_token_id,
+                NO_DEPOSIT,
+                GAS_FOR_SHARE_TOKEN_MIRROR,
+            );
+        }
+    }
+}
+
+impl Contract {
+    /// Mints amount on the share token of fft_share, if it was deployed.
+    /// A failed mirror is repaired by sync_share_token.
+    pub(crate) fn internal_mirror_mint(
+        &self,
+        fft_share: &str,
+        account_id: &AccountId,
+        amount: u128,
+    ) {
+        if let Some(share_token_id) = self.get_share_token(fft_share.to_string()) {
+            ext_share_token::mint(
+                account_id.clone(),
+                U128(amount),
+                share_token_id,
+                NO_DEPOSIT,
+                GAS_FOR_SHARE_TOKEN_MIRROR,
+            );
+        }
+    }
+
+    /// Burns amount on the share token of fft_share, if it was deployed
+    pub(crate) fn internal_mirror_burn(
+        &self,
+        fft_share: &str,
+        account_id: &AccountId,
+        amount: u128,
+    ) {
+        if let Some(share_token_id) = self.get_share_token(fft_share.to_string()) {
+            ext_share_token::burn(
+                account_id.clone(),
+                U128(amount),
+                share_token_id,
+                NO_DEPOSIT,
+                GAS_FOR_SHARE_TOKEN_MIRROR,
+            );
+        }
+    }
+
+    /// Transfers amount on the share token of fft_share, if it was deployed
+    pub(crate) fn internal_mirror_transfer(
+        &self,
+        fft_share: &str,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        amount: u128,
+    ) {
+        if let Some(share_token_id) = self.get_share_token(fft_share.to_string()) {
+            ext_share_token::transfer(
+                sender_id.clone(),
+                receiver_id.clone(),
+                U128(amount),
+                share_token_id,
+                NO_DEPOSIT,
+                GAS_FOR_SHARE_TOKEN_MIRROR,
+            );
+        }
+    }
+
+    /// Deploys the share token of a LP strategy, if the share token code was set
+    pub(crate) fn deploy_share_token_on_create(&mut self, seed_id: &str) {
+        if self.data().share_token_code.is_some() {
+            self.internal_deploy_share_token(seed_id, LP_SHARE_DECIMALS);
+        }
+    }
+
+    /// Creates the share token account, fft_share_1 of safe.near is deployed at fft-1.safe.near.
+    /// The share token is registered by callback_post_share_token_deploy.
+    pub(crate) fn internal_deploy_share_token(&mut self, seed_id: &str, decimals: u8) -> Promise {
+        let code = self
+            .data()
+            .share_token_code
+            .get()
+            .expect("ERR: share token code not set");
+
+        let attached_deposit = env::attached_deposit();
+        require!(
+            attached_deposit >= SHARE_TOKEN_DEPLOY_DEPOSIT,
+            format!(
+                "ERR: requires attached deposit of at least {} yoctoNEAR to deploy the share token",
+                SHARE_TOKEN_DEPLOY_DEPOSIT
+            )
+        );
+
+        let fft_share = self
+            .data()
+            .fft_share_by_seed_id
+            .get(&seed_id.to_string())
+            .expect("E1: seed_id doesn't exist");
+        require!(
+            self.data()
+                .share_token_by_fft_share
+                .get(&fft_share)
+                .is_none(),
+            "ERR: share token already deployed"
+        );
+
+        let share_token_id: AccountId = format!(
+            "{}.{}",
+            fft_share.replace("fft_share_", "fft-"),
+            env::current_account_id()
+        )
+        .parse()
+        .expect("ERR: invalid share token account");
+
+        let args = near_sdk::serde_json::to_vec(&ShareTokenInitArgs {
+            owner_id: &env::current_account_id(),
+            fft_share: &fft_share,
+            metadata: FungibleTokenMetadata {
+                spec: FT_METADATA_SPEC.to_string(),
+                name: format!("Fluxus {}", seed_id),
+                symbol: "FFT".to_string(),
+                icon: None,
+                reference: None,
+                reference_hash: None,
+                decimals,
+            },
+        })
+        .expect("ERR: share token args");
+
+        log!("Deploying {} for {}", share_token_id, fft_share);
+
+        Promise::new(share_token_id.clone())
+            .create_account()
+            .transfer(attached_deposit)
+            .deploy_contract(code)
+            .function_call(
+                "new".to_string(),
+                args,
+                NO_DEPOSIT,
+                GAS_FOR_SHARE_TOKEN_INIT,
+            )
+            .then(callback_share_token::callback_post_share_token_deploy(
+                fft_share,
+                share_token_id,
+                env::current_account_id(),
+                NO_DEPOSIT,
+                GAS_FOR_SHARE_TOKEN_CALLBACK,
+            ))
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::testing_env;
+
+    fn get_context(predecessor_id: &str) -> VMContextBuilder {
+        let mut builder = VMContextBuilder::new();
+        builder
+            .current_account_id(to_account_id("safe.near"))
+            .predecessor_account_id(to_account_id(predecessor_id))
+            .attached_deposit(1);
+        builder
+    }
+
+    pub fn to_account_id(value: &str) -> AccountId {
+        value.parse().unwrap()
+    }
+
+    /// fft_share_1 has its share token deployed, user.near holds 100 fft_share_1
+    fn create_contract() -> Contract {
+        testing_env!(get_context("safe.near").build());
+        let mut contract =
+            Contract::new(to_account_id("owner.near"), to_account_id("treasure.near"));
+
+        contract
+            .data_mut()
+            .fft_share_by_seed_id
+            .insert(&"exchange.near@1".to_string(), &"fft_share_1".to_string());
+        contract
+            .data_mut()
+            .seed_id_by_fft_share
+            .insert(&"fft_share_1".to_string(), &"exchange.near@1".to_string());
+        contract.register_seed("fft_share_1".to_string());
+        contract.mft_mint("fft_share_1".to_string(), 100, to_account_id("user.near"));
+
+        testing_env!(get_context("safe.near").build());
+        contract.callback_post_share_token_deploy(
+            Ok(()),
+            "fft_share_1".to_string(),
+            to_account_id("fft-1.safe.near"),
+        );
+
+        contract
+    }
+
+    fn balance(contract: &Contract, account_id: &str) -> u128 {
+        contract.users_fft_share_amount("fft_share_1".to_string(), to_account_id(account_id))
+    }
+
+    #[test]
+    fn test_share_token_transfer() {
+        let mut contract = create_contract();
+        assert_eq!(
+            contract.get_share_token("fft_share_1".to_string()),
+            Some(to_account_id("fft-1.safe.near"))
+        );
+
+        testing_env!(get_context("fft-1.safe.near").build());
+        contract.on_share_token_transfer(
+            "fft_share_1".to_string(),
+            to_account_id("user.near"),
+            to_account_id("other.near"),
+            U128(40),
+        );
+
+        assert_eq!(balance(&contract, "user.near"), 60);
+        assert_eq!(balance(&contract, "other.near"), 40);
+        assert_eq!(contract.total_supply_amount("fft_share_1".to_string()), 100);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_ALLOWED")]
+    fn test_share_token_transfer_not_token() {
+        let mut contract = create_contract();
+
+        testing_env!(get_context("user.near").build());
+        contract.on_share_token_transfer(
+            "fft_share_1".to_string(),
+            to_account_id("other.near"),
+            to_account_id("user.near"),
+            U128(40),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR: share token not deployed")]
+    fn test_sync_without_share_token() {
+        let mut contract = create_contract();
+
+        testing_env!(get_context("user.near").build());
+        contract.sync_share_token("fft_share_2".to_string(), to_account_id("user.near"));
+    }
+
+    #[test]
+    #[should_panic(expected = "to deploy the share token")]
+    fn test_deploy_without_deposit() {
+        let mut contract = create_contract();
+
+        testing_env!(get_context("owner.near").build());
+        contract.set_share_token_code(Base64VecU8(vec![0u8; 8]));
+        contract.deploy_share_token("exchange.near@1".to_string(), 24);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR: share token already deployed")]
+    fn test_deploy_twice() {
+        let mut contract = create_contract();
+
+        testing_env!(get_context("owner.near")
+            .attached_deposit(SHARE_TOKEN_DEPLOY_DEPOSIT)
+            .build());
+        contract.set_share_token_code(Base64VecU8(vec![0u8; 8]));
+        contract.deploy_share_token("exchange.near@1".to_string(), 24);
+    }
+
+    #[test]
+    fn test_create_strategy_without_code() {
+        testing_env!(get_context("owner.near").attached_deposit(0).build());
+        let mut contract =
+            Contract::new(to_account_id("owner.near"), to_account_id("treasure.near"));
+
+        contract.create_strategy(
+            String::new(),
+            10,
+            AccountFee::new(to_account_id("creator.near"), 5),
+            1,
+            to_account_id("exchange.near"),
+            to_account_id("farm.near"),
+            to_account_id("token1.near"),
+            to_account_id("token2.near"),
+            1,
+            U128(1_000),
+        );
+
+        assert_eq!(contract.get_share_token("fft_share_1".to_string()), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR: not allowed")]
+    fn test_create_strategy_not_allowed() {
+        testing_env!(get_context("user.near").attached_deposit(0).build());
+        let mut contract =
+            Contract::new(to_account_id("owner.near"), to_account_id("treasure.near"));
+
+        contract.create_strategy(
+            String::new(),
+            10,
+            AccountFee::new(to_account_id("creator.near"), 5),
+            1,
+            to_account_id("exchange.near"),
+            to_account_id("farm.near"),
+            to_account_id("token1.near"),
+            to_account_id("token2.near"),
+            1,
+            U128(1_000),
+        );
+    }
+}
@@ -0,0 +1,209 @@
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    /// Retires seed_id: every farm is cleared and the strategy is moved to the archive, where it is read-only.
+    /// Deposits, harvests and updates are rejected, users can still unstake their fft_shares.
+    /// Once all of them are withdrawn, the strategy can be deleted with `delete_strategy_by_strat_name`.
+    pub fn archive_strategy(&mut self, seed_id: String) {
+        assert!(self.is_owner_or_guardians(), "ERR: not allowed");
+        self.assert_not_archived(&seed_id);
+
+        let mut strat = self.get_strat(&seed_id);
+        assert!(
+            !strat.strategy().cycle_in_progress(),
+            "ERR: {} is in the middle of a harvest",
+            seed_id
+        );
+
+        strat.strategy_mut().clear_farms();
+
+        // the management fee is settled once, an archived strategy does not accrue it anymore
+        self.internal_accrue_management_fee(&seed_id);
+
+        self.data_mut().strategies.remove(&seed_id);
+        self.data_mut().archived_strategies.insert(&seed_id, &strat);
+
+        Event::StrategyArchived(&[StrategyArchivedData { seed_id: &seed_id }]).emit();
+    }
+
+    /// Returns true if seed_id was retired to the archive
+    pub fn is_strategy_archived(&self, seed_id: String) -> bool {
+        self.data().archived_strategies.get(&seed_id).is_some()
+    }
+
+    /// Returns the seed_ids of the archived strategies
+    /// Args:
+    ///   from_index: position of the first strategy, defaults to 0
+    ///   limit: max number of strategies returned, defaults to all
+    pub fn get_archived_strategies(
+        &self,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<String> {
+        let keys = self.data().archived_strategies.keys_as_vector();
+
+        let from_index = from_index.unwrap_or(0);
+        let limit = limit.unwrap_or(keys.len());
+
+        (from_index..std::cmp::min(from_index.saturating_add(limit), keys.len()))
+            .map(|index| keys.get(index).unwrap())
+            .collect()
+    }
+}
+
+impl Contract {
+    pub(crate) fn assert_not_archived(&self, seed_id: &str) {
+        assert!(
+            !self.is_strategy_archived(seed_id.to_string()),
+            "ERR: {} is archived",
+            seed_id
+        );
+    }
+
+    /// Panics while accounts, the treasury included, hold fft_shares or claimable shares of seed_id
+    pub(crate) fn assert_no_user_funds(&self, seed_id: &str) {
+        let claimable = self.get_total_claimable_shares(seed_id);
+        assert!(
+            claimable == 0,
+            "ERR: {} still holds {} claimable shares",
+            seed_id,
+            claimable
+        );
+
+        let fft_share_id = match self.data().fft_share_by_seed_id.get(&seed_id.to_string()) {
+            Some(fft_share_id) => fft_share_id,
+            None => return,
+        };
+
+        let total_fft = self.total_supply_amount(fft_share_id);
+        assert!(
+            total_fft == 0,
+            "ERR: {} still holds {} fft_shares",
+            seed_id,
+            total_fft
+        );
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use near_sdk::testing_env;
+
+    /// Running ref strategy, the 100 fft_shares of user.near are worth 100 shares of SEED_ID
+    fn create_contract() -> Contract {
+        create_contract_with_farm(10, 100, 100)
+    }
+
+    fn start_harvest(contract: &mut Contract) {
+        let mut strat = contract.get_strat(SEED_ID);
+        strat.get_compounder_mut().farms[0].cycle_stage = AutoCompounderCycle::Withdrawal;
+        contract.save_strat(SEED_ID, &strat);
+    }
+
+    /// Unstakes all the fft_shares of user.near
+    fn withdraw_all(contract: &mut Contract) {
+        testing_env!(get_context("user.near").build());
+        contract.unstake(SEED_ID.to_string(), None);
+
+        testing_env!(get_context("auto_compounder.near").build());
+        contract.callback_withdraw_shares(
+            Ok(()),
+            SEED_ID.to_string(),
+            to_account_id("user.near"),
+            100,
+            100,
+            0,
+        );
+    }
+
+    #[test]
+    fn test_archive_and_delete_strategy() {
+        let mut contract = create_contract();
+        contract.record_share_price(SEED_ID, 0);
+
+        contract.archive_strategy(SEED_ID.to_string());
+        assert!(contract.is_strategy_archived(SEED_ID.to_string()));
+        assert_eq!(
+            contract.get_archived_strategies(None, None),
+            vec![SEED_ID.to_string()]
+        );
+        assert_eq!(contract.get_seed_ids(None, None), Vec::<String>::new());
+        assert!(!contract.get_strat(SEED_ID).strategy().is_running());
+
+        // users can still withdraw from the archived strategy
+        withdraw_all(&mut contract);
+        assert_eq!(
+            contract.users_fft_share_amount("fft_share_1".to_string(), to_account_id("user.near")),
+            0
+        );
+
+        contract.delete_strategy_by_strat_name(SEED_ID.to_string());
+        assert!(!contract.is_strategy_archived(SEED_ID.to_string()));
+        assert_eq!(
+            contract.get_archived_strategies(None, None),
+            Vec::<String>::new()
+        );
+
+        // nothing of the strategy is left behind
+        let data = contract.data();
+        assert!(data
+            .fft_share_by_seed_id
+            .get(&SEED_ID.to_string())
+            .is_none());
+        assert!(data
+            .seed_id_by_fft_share
+            .get(&"fft_share_1".to_string())
+            .is_none());
+        assert!(data.seed_id_amount.get(&SEED_ID.to_string()).is_none());
+        assert!(contract
+            .get_share_price_history(SEED_ID.to_string())
+            .is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR: exchange.near@1 is archived")]
+    fn test_archived_strategy_is_read_only() {
+        let mut contract = create_contract();
+        contract.archive_strategy(SEED_ID.to_string());
+
+        contract.update_compounder_state(format!("{}#0", SEED_ID), AutoCompounderState::Running);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR: exchange.near@1 still holds 100 fft_shares")]
+    fn test_delete_strategy_with_user_funds() {
+        let mut contract = create_contract();
+        contract.delete_strategy_by_strat_name(SEED_ID.to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR: exchange.near@1 is in the middle of a harvest")]
+    fn test_archive_strategy_during_harvest() {
+        let mut contract = create_contract();
+        start_harvest(&mut contract);
+
+        contract.archive_strategy(SEED_ID.to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR: exchange.near@1#0 is in the middle of a harvest")]
+    fn test_delete_farm_during_harvest() {
+        let mut contract = create_contract();
+        start_harvest(&mut contract);
+
+        contract.delete_strategy_by_farm_id(format!("{}#0", SEED_ID));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR: not allowed")]
+    fn test_delete_strategy_not_allowed() {
+        let mut contract = create_contract();
+        withdraw_all(&mut contract);
+
+        testing_env!(get_context("user.near").build());
+        contract.delete_strategy_by_strat_name(SEED_ID.to_string());
+    }
+}
@@ -0,0 +1,114 @@
+use crate::*;
+use near_sdk::test_utils::VMContextBuilder;
+use near_sdk::testing_env;
+
+/// Seed of the ref strategy built by the factories below
+pub const SEED_ID: &str = "exchange.near@1";
+
+pub fn to_account_id(value: &str) -> AccountId {
+    value.parse().unwrap()
+}
+
+/// Call of predecessor_id to auto_compounder.near, with 1 yocto attached
+pub fn get_context(predecessor_id: &str) -> VMContextBuilder {
+    let mut builder = VMContextBuilder::new();
+    builder
+        .current_account_id(to_account_id("auto_compounder.near"))
+        .predecessor_account_id(to_account_id(predecessor_id))
+        .attached_deposit(1);
+    builder
+}
+
+/// Safe owned by auto_compounder.near, with treasure.near as treasury
+pub fn create_safe() -> Contract {
+    Contract::new(
+        to_account_id("auto_compounder.near"),
+        to_account_id("treasure.near"),
+    )
+}
+
+/// Ref strategy of seed_id without farms: 10% strategy fee, 5% of it to creator.near and 1% to sentries
+pub fn create_compounder(seed_id: &str, seed_min_deposit: u128) -> AutoCompounder {
+    AutoCompounder::new(
+        10,
+        AccountFee::new(to_account_id("creator.near"), 5),
+        1,
+        to_account_id("exchange.near"),
+        to_account_id("farm.near"),
+        to_account_id("token1.near"),
+        to_account_id("token2.near"),
+        1,
+        seed_id.to_string(),
+        U128(seed_min_deposit),
+    )
+}
+
+/// Running farm of a ref strategy, waiting for the next harvest
+pub fn running_farm(id: &str) -> StratFarmInfo {
+    StratFarmInfo {
+        state: AutoCompounderState::Running,
+        cycle_stage: AutoCompounderCycle::ClaimReward,
+        slippage: 99,
+        max_slippage: MAX_SLIPPAGE_ALLOWED,
+        last_reward_amount: 0,
+        last_fee_amount: 0,
+        pool_id_token1_reward: 1,
+        pool_id_token2_reward: 2,
+        reward_token: to_account_id("reward.near"),
+        available_balance: vec![0, 0],
+        id: id.to_string(),
+    }
+}
+
+/// Saves strat for seed_id, which holds seed_amount shares, and registers its fft_share
+pub fn register_strategy(
+    contract: &mut Contract,
+    seed_id: &str,
+    fft_share: &str,
+    strat: VersionedStrategy,
+    seed_amount: u128,
+) {
+    contract.save_strat(seed_id, &strat);
+
+    contract.register_seed(fft_share.to_string());
+    contract
+        .data_mut()
+        .fft_share_by_seed_id
+        .insert(&seed_id.to_string(), &fft_share.to_string());
+    contract
+        .data_mut()
+        .seed_id_by_fft_share
+        .insert(&fft_share.to_string(), &seed_id.to_string());
+    contract
+        .data_mut()
+        .seed_id_amount
+        .insert(&seed_id.to_string(), &seed_amount);
+}
+
+/// Running ref strategy of SEED_ID with a farm, holding seed_amount shares.
+/// user.near holds user_fft_shares of fft_share_1
+pub fn create_contract_with_farm(
+    seed_min_deposit: u128,
+    seed_amount: u128,
+    user_fft_shares: u128,
+) -> Contract {
+    testing_env!(get_context("auto_compounder.near").build());
+    let mut contract = create_safe();
+
+    let mut compounder = create_compounder(SEED_ID, seed_min_deposit);
+    compounder.farms.push(running_farm("0"));
+    register_strategy(
+        &mut contract,
+        SEED_ID,
+        "fft_share_1",
+        VersionedStrategy::AutoCompounder(compounder),
+        seed_amount,
+    );
+    contract.mft_mint(
+        "fft_share_1".to_string(),
+        user_fft_shares,
+        to_account_id("user.near"),
+    );
+
+    contract
+}
@@ -5,18 +5,32 @@ use near_sdk::{ext_contract, PromiseOrValue};
 use crate::*;
 
 /// Message parameters to receive via token function call.
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
 #[serde(crate = "near_sdk::serde")]
 #[serde(untagged)]
 enum TokenReceiverMessage {
-    /// Alternative to deposit + execute actions call.
-    Execute {
+    /// Deposit the received tokens into a single token strategy.
+    Deposit {
+        /// Strategy receiving the tokens, such as pembrock@token
+        strat_name: String,
+        /// Account credited with the fft_shares, defaults to the sender
+        receiver_id: Option<AccountId>,
+        /// Minimum amount of fft_shares to be minted, otherwise the tokens are refunded
+        min_shares_out: Option<U128>,
         referral_id: Option<AccountId>,
-        // List of sequential actions.
-        //actions: Vec<Action>,
     },
 }
 
+/// Message parameters to receive via mft_transfer_call, an empty `msg` uses the defaults.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Default)]
+#[serde(crate = "near_sdk::serde")]
+struct MFTReceiverMessage {
+    /// Account credited with the fft_shares, defaults to the sender
+    receiver_id: Option<AccountId>,
+    /// Minimum amount of fft_shares to be minted, otherwise the LP is refunded
+    min_shares_out: Option<U128>,
+}
+
 #[ext_contract(ext_self)]
 pub trait RefExchange {
     fn exchange_callback_post_withdraw(
@@ -47,8 +61,8 @@ pub trait MultiFungibleToken {
 #[near_bindgen]
 impl FungibleTokenReceiver for Contract {
     /// Callback on receiving tokens by this contract.
-    /// `msg` format is either "" for deposit or `TokenReceiverMessage`.
-    #[allow(unreachable_code)]
+    /// `msg` format is `TokenReceiverMessage`, the whole amount is refunded
+    /// if it is invalid or if the token is not used by the target strategy.
     fn ft_on_transfer(
         &mut self,
         sender_id: AccountId,
@@ -58,17 +72,78 @@ impl FungibleTokenReceiver for Contract {
         self.assert_contract_running();
 
         let token_in = env::predecessor_account_id();
-        // TODO: assert pembrock strat is running
-        //self.assert_strategy_is_running(&seed_id);
-        let strat_name: String = format!("pembrock@{}", token_in);
 
-        let compounder = self.pemb_get_strat(&strat_name).pemb_get();
+        let message = match near_sdk::serde_json::from_str::<TokenReceiverMessage>(&msg) {
+            Ok(message) => message,
+            Err(_) => {
+                log!("ERR: invalid msg, refunding {} {}", amount.0, token_in);
+                return PromiseOrValue::Value(amount);
+            }
+        };
+
+        match message {
+            TokenReceiverMessage::Deposit {
+                strat_name,
+                receiver_id,
+                min_shares_out,
+                referral_id,
+            } => {
+                if let Some(referral_id) = referral_id {
+                    log!("Deposit referred by {}", referral_id);
+                }
+
+                self.internal_token_deposit(
+                    token_in,
+                    strat_name,
+                    receiver_id.unwrap_or(sender_id),
+                    amount,
+                    min_shares_out,
+                )
+            }
+        }
+    }
+}
+
+impl Contract {
+    /// Stakes amount of token_in into strat_name on behalf of receiver_id.
+    /// Returns the amount to be refunded to the sender.
+    fn internal_token_deposit(
+        &mut self,
+        token_in: AccountId,
+        strat_name: String,
+        receiver_id: AccountId,
+        amount: U128,
+        min_shares_out: Option<U128>,
+    ) -> PromiseOrValue<U128> {
+        // single token strategies, such as pembrock, are named after the token they receive
+        if strat_name != format!("pembrock@{}", token_in) {
+            log!("ERR: {} is not accepted by {}", token_in, strat_name);
+            return PromiseOrValue::Value(amount);
+        }
+
+        let strat = match self.data().strategies.get(&strat_name) {
+            Some(strat) if strat.need_upgrade() => strat.upgrade(),
+            Some(strat) => strat,
+            None => {
+                log!("ERR: {} is not registered", strat_name);
+                return PromiseOrValue::Value(amount);
+            }
+        };
 
-        // initiate stake process
-        let amount_in_u128: u128 = amount.into();
-        compounder.stake_on_pembrock(&sender_id, amount_in_u128, strat_name);
+        if !strat.strategy().is_running() {
+            log!("ERR: {} is not running", strat_name);
+            return PromiseOrValue::Value(amount);
+        }
 
-        PromiseOrValue::Value(U128(0))
+        // initiate stake process, the amount above the caps or not used by the strategy is refunded
+        self.internal_capped_stake(
+            strat,
+            token_in.to_string(),
+            strat_name,
+            &receiver_id,
+            amount.0,
+            min_shares_out,
+        )
     }
 }
 
@@ -86,7 +161,9 @@ pub trait MFTTokenReceiver {
 #[near_bindgen]
 impl MFTTokenReceiver for Contract {
     /// Callback on receiving tokens by this contract.
-    #[allow(unused)]
+    /// `msg` format is either "" or `MFTReceiverMessage`, the whole amount is refunded
+    /// if it is invalid or if less than min_shares_out fft_shares would be minted.
+    /// Only the part below the deposit caps of the strategy is staked, the rest is refunded.
     fn mft_on_transfer(
         &mut self,
         token_id: String,
@@ -99,8 +176,207 @@ impl MFTTokenReceiver for Contract {
         let seed_id: String = format!("{}@{}", caller_id, unwrap_token_id(&token_id));
         self.assert_strategy_is_running(&seed_id);
 
+        let message = if msg.is_empty() {
+            MFTReceiverMessage::default()
+        } else {
+            match near_sdk::serde_json::from_str::<MFTReceiverMessage>(&msg) {
+                Ok(message) => message,
+                Err(_) => {
+                    log!("ERR: invalid msg, refunding {} {}", amount.0, token_id);
+                    return PromiseOrValue::Value(amount);
+                }
+            }
+        };
+
+        let receiver_id = message.receiver_id.unwrap_or(sender_id);
+
         let strat = self.get_strat(&seed_id);
 
-        PromiseOrValue::Promise(strat.stake(token_id, seed_id, &sender_id, amount.0))
+        self.internal_capped_stake(
+            strat,
+            token_id,
+            seed_id,
+            &receiver_id,
+            amount.0,
+            message.min_shares_out,
+        )
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::testing_env;
+
+    fn get_context() -> VMContextBuilder {
+        let mut builder = VMContextBuilder::new();
+        builder
+            .current_account_id(to_account_id("auto_compounder.near"))
+            .signer_account_id(to_account_id("user.near"))
+            .predecessor_account_id(to_account_id("token.near"));
+        builder
+    }
+
+    pub fn to_account_id(value: &str) -> AccountId {
+        value.parse().unwrap()
+    }
+
+    fn create_contract() -> Contract {
+        Contract::new(
+            to_account_id("auto_compounder.near"),
+            to_account_id("treasure.near"),
+        )
+    }
+
+    /// Registers a running ref strategy for exchange.near@1, holding 100 shares for 50 fft_shares
+    fn register_ref_strategy(contract: &mut Contract) {
+        let mut compounder = AutoCompounder::new(
+            10,
+            AccountFee::new(to_account_id("creator.near"), 5),
+            1,
+            to_account_id("exchange.near"),
+            to_account_id("farm.near"),
+            to_account_id("token1.near"),
+            to_account_id("token2.near"),
+            1,
+            "exchange.near@1".to_string(),
+            U128(10),
+        );
+        compounder.farms.push(StratFarmInfo {
+            state: AutoCompounderState::Running,
+            cycle_stage: AutoCompounderCycle::ClaimReward,
+            slippage: 99,
+            max_slippage: MAX_SLIPPAGE_ALLOWED,
+            last_reward_amount: 0,
+            last_fee_amount: 0,
+            pool_id_token1_reward: 1,
+            pool_id_token2_reward: 2,
+            reward_token: to_account_id("reward.near"),
+            available_balance: vec![0, 0],
+            id: "0".to_string(),
+        });
+
+        let seed_id = "exchange.near@1".to_string();
+        contract
+            .data_mut()
+            .strategies
+            .insert(&seed_id, &VersionedStrategy::AutoCompounder(compounder));
+
+        contract.register_seed("fft_share_1".to_string());
+        contract
+            .data_mut()
+            .fft_share_by_seed_id
+            .insert(&seed_id, &"fft_share_1".to_string());
+        contract
+            .data_mut()
+            .seed_id_by_fft_share
+            .insert(&"fft_share_1".to_string(), &seed_id);
+        contract.data_mut().seed_id_amount.insert(&seed_id, &100);
+        contract.mft_mint("fft_share_1".to_string(), 50, to_account_id("user.near"));
+    }
+
+    fn refunded(result: PromiseOrValue<U128>) -> u128 {
+        match result {
+            PromiseOrValue::Value(amount) => amount.0,
+            PromiseOrValue::Promise(_) => panic!("expected a value"),
+        }
+    }
+
+    #[test]
+    fn test_parse_deposit_message() {
+        let message: TokenReceiverMessage = near_sdk::serde_json::from_str(
+            r#"{"strat_name":"pembrock@token.near","receiver_id":"alice.near","min_shares_out":"10"}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            message,
+            TokenReceiverMessage::Deposit {
+                strat_name: "pembrock@token.near".to_string(),
+                receiver_id: Some(to_account_id("alice.near")),
+                min_shares_out: Some(U128(10)),
+                referral_id: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_refund_invalid_message() {
+        testing_env!(get_context().build());
+
+        let mut contract = create_contract();
+
+        for msg in ["", "deposit", r#"{"receiver_id":"alice.near"}"#] {
+            let result =
+                contract.ft_on_transfer(to_account_id("user.near"), U128(100), msg.to_string());
+            assert_eq!(refunded(result), 100);
+        }
+    }
+
+    #[test]
+    fn test_refund_unregistered_token() {
+        testing_env!(get_context().build());
+
+        let mut contract = create_contract();
+
+        // the strategy does not exist
+        let msg = r#"{"strat_name":"pembrock@token.near"}"#;
+        let result = contract.ft_on_transfer(to_account_id("user.near"), U128(100), msg.into());
+        assert_eq!(refunded(result), 100);
+
+        // the token is not used by the strategy
+        let msg = r#"{"strat_name":"pembrock@other_token.near"}"#;
+        let result = contract.ft_on_transfer(to_account_id("user.near"), U128(100), msg.into());
+        assert_eq!(refunded(result), 100);
+    }
+
+    #[test]
+    fn test_mft_refund_invalid_message() {
+        testing_env!(get_context()
+            .predecessor_account_id(to_account_id("exchange.near"))
+            .build());
+
+        let mut contract = create_contract();
+        register_ref_strategy(&mut contract);
+
+        let result = contract.mft_on_transfer(
+            ":1".to_string(),
+            to_account_id("user.near"),
+            U128(100),
+            "deposit".to_string(),
+        );
+        assert_eq!(refunded(result), 100);
+    }
+
+    #[test]
+    fn test_mft_refund_below_min_shares_out() {
+        testing_env!(get_context()
+            .predecessor_account_id(to_account_id("exchange.near"))
+            .build());
+
+        let mut contract = create_contract();
+        register_ref_strategy(&mut contract);
+
+        // 100 shares are worth 50 fft_shares
+        assert_eq!(contract.fft_shares_for_deposit("exchange.near@1", 100), 50);
+
+        let msg = r#"{"receiver_id":"alice.near","min_shares_out":"51"}"#;
+        let result = contract.mft_on_transfer(
+            ":1".to_string(),
+            to_account_id("user.near"),
+            U128(100),
+            msg.to_string(),
+        );
+        assert_eq!(refunded(result), 100);
+
+        let msg = r#"{"receiver_id":"alice.near","min_shares_out":"50"}"#;
+        let result = contract.mft_on_transfer(
+            ":1".to_string(),
+            to_account_id("user.near"),
+            U128(100),
+            msg.to_string(),
+        );
+        assert!(matches!(result, PromiseOrValue::Promise(_)));
     }
 }
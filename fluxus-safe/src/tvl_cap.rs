@@ -0,0 +1,270 @@
+use crate::*;
+
+/// Max amounts of seed_id a strategy accepts, used to limit the exposure while a farm integration is unproven.
+/// Only deposits are checked, the amounts can grow above the caps with the harvests and fft_share transfers.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TvlCap {
+    /// Max seed_id_amount of the strategy, no limit if None
+    pub max_total: Option<U128>,
+
+    /// Max amount of seed_id owned by the fft_shares of an account, no limit if None
+    pub max_per_account: Option<U128>,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Sets the deposit caps of seed_id, both of them at None removes the caps
+    pub fn set_tvl_cap(
+        &mut self,
+        seed_id: String,
+        max_total: Option<U128>,
+        max_per_account: Option<U128>,
+    ) {
+        assert!(self.is_owner_or_guardians(), "ERR: not allowed");
+        // panics if the strategy does not exist
+        self.get_strat(&seed_id);
+
+        if max_total.is_none() && max_per_account.is_none() {
+            self.data_mut().tvl_cap_by_seed_id.remove(&seed_id);
+            return;
+        }
+
+        let tvl_cap = TvlCap {
+            max_total,
+            max_per_account,
+        };
+
+        self.data_mut()
+            .tvl_cap_by_seed_id
+            .insert(&seed_id, &tvl_cap);
+    }
+
+    /// Returns the deposit caps of seed_id, None if it is not capped
+    pub fn get_tvl_cap(&self, seed_id: String) -> Option<TvlCap> {
+        self.data().tvl_cap_by_seed_id.get(&seed_id)
+    }
+
+    /// Returns the refund of a deposit staked up to the caps: the part above them
+    /// plus the part of the staked amount that was not used by the strategy, all of it if the stake failed
+    #[private]
+    pub fn callback_post_capped_stake(
+        &mut self,
+        #[callback_result] stake_result: Result<U128, PromiseError>,
+        over_cap: U128,
+        accepted: U128,
+    ) -> U128 {
+        let unused = match stake_result {
+            Ok(unused) => unused.0,
+            Err(_) => accepted.0,
+        };
+
+        U128(over_cap.0 + unused)
+    }
+}
+
+impl Contract {
+    /// Returns the amount of seed_id that account_id can still deposit, u128::MAX if it is not capped
+    pub(crate) fn internal_remaining_capacity(
+        &self,
+        seed_id: &str,
+        account_id: &AccountId,
+    ) -> u128 {
+        let tvl_cap = match self.data().tvl_cap_by_seed_id.get(&seed_id.to_string()) {
+            Some(tvl_cap) => tvl_cap,
+            None => return u128::MAX,
+        };
+
+        let total_capacity = tvl_cap.max_total.map_or(u128::MAX, |max_total| {
+            max_total
+                .0
+                .saturating_sub(self.seed_total_amount(&seed_id.to_string()))
+        });
+
+        let account_capacity = tvl_cap
+            .max_per_account
+            .map_or(u128::MAX, |max_per_account| {
+                let fft_share_id = self.fft_token_seed_id(seed_id.to_string());
+                let fft_shares = self.users_fft_share_amount(fft_share_id, account_id.clone());
+
+                max_per_account
+                    .0
+                    .saturating_sub(self.seed_amount_for_fft_shares(seed_id, fft_shares))
+            });
+
+        std::cmp::min(total_capacity, account_capacity)
+    }
+
+    /// Stakes the part of amount below the caps of seed_id for account_id.
+    /// Returns the amount to be refunded to the sender.
+    pub(crate) fn internal_capped_stake(
+        &mut self,
+        strat: VersionedStrategy,
+        token_id: String,
+        seed_id: String,
+        account_id: &AccountId,
+        amount: u128,
+        min_shares_out: Option<U128>,
+    ) -> PromiseOrValue<U128> {
+        let accepted = std::cmp::min(
+            amount,
+            self.internal_remaining_capacity(&seed_id, account_id),
+        );
+        if accepted == 0 {
+            log!("ERR: {} reached its deposit cap", seed_id);
+            return PromiseOrValue::Value(U128(amount));
+        }
+
+        // checked against the share price at the time of deposit, for the accepted amount
+        if let Some(min_shares_out) = min_shares_out {
+            let fee = self.internal_deposit_fee(&seed_id, accepted);
+            let fft_shares = self.fft_shares_for_deposit(&seed_id, accepted - fee);
+            if fft_shares < min_shares_out.0 {
+                log!(
+                    "ERR: {} fft_shares is lower than min_shares_out {}",
+                    fft_shares,
+                    min_shares_out.0
+                );
+                return PromiseOrValue::Value(U128(amount));
+            }
+        }
+
+        // the farm would reject it, or shares already wait on the exchange:
+        // the deposit joins them and they are staked together once they reach seed_min_deposit
+        if accepted < strat.strategy().seed_min_deposit().0
+            || self.get_pending_deposits(seed_id.clone()).0 > 0
+        {
+            return self.internal_pending_deposit(
+                &strat,
+                &seed_id,
+                account_id,
+                accepted,
+                amount - accepted,
+            );
+        }
+
+        let stake = strat.stake(token_id, seed_id.clone(), account_id, accepted);
+        if accepted == amount {
+            return PromiseOrValue::Promise(stake);
+        }
+
+        log!(
+            "{} reached its deposit cap, refunding {}",
+            seed_id,
+            amount - accepted
+        );
+
+        PromiseOrValue::Promise(stake.then(callback_tvl_cap::callback_post_capped_stake(
+            U128(amount - accepted),
+            U128(accepted),
+            env::current_account_id(),
+            0,
+            Gas(5_000_000_000_000),
+        )))
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use near_sdk::testing_env;
+
+    /// Running ref strategy, the 50 fft_shares of user.near are worth 100 shares of SEED_ID
+    fn create_contract() -> Contract {
+        create_contract_with_farm(10, 100, 50)
+    }
+
+    fn deposit(contract: &mut Contract, amount: u128) -> PromiseOrValue<U128> {
+        testing_env!(get_context("exchange.near").build());
+        contract.mft_on_transfer(
+            ":1".to_string(),
+            to_account_id("user.near"),
+            U128(amount),
+            "".to_string(),
+        )
+    }
+
+    #[test]
+    fn test_remaining_capacity() {
+        let mut contract = create_contract();
+        let user = to_account_id("user.near");
+        let alice = to_account_id("alice.near");
+
+        assert_eq!(
+            contract.internal_remaining_capacity(SEED_ID, &user),
+            u128::MAX
+        );
+
+        contract.set_tvl_cap(SEED_ID.to_string(), Some(U128(1_000)), Some(U128(300)));
+
+        assert_eq!(contract.internal_remaining_capacity(SEED_ID, &user), 200);
+        assert_eq!(contract.internal_remaining_capacity(SEED_ID, &alice), 300);
+        assert_eq!(contract.max_deposit(SEED_ID.to_string(), user), U128(200));
+
+        contract.set_tvl_cap(SEED_ID.to_string(), Some(U128(150)), None);
+        assert_eq!(contract.max_deposit(SEED_ID.to_string(), alice), U128(50));
+    }
+
+    #[test]
+    fn test_deposit_above_cap() {
+        let mut contract = create_contract();
+        contract.set_tvl_cap(SEED_ID.to_string(), Some(U128(100)), None);
+
+        // the strategy is full, the whole amount is refunded
+        match deposit(&mut contract, 10) {
+            PromiseOrValue::Value(refund) => assert_eq!(refund, U128(10)),
+            PromiseOrValue::Promise(_) => panic!("expected a refund"),
+        }
+
+        // the part below the cap is staked
+        contract.set_tvl_cap(SEED_ID.to_string(), Some(U128(150)), None);
+        assert!(matches!(
+            deposit(&mut contract, 100),
+            PromiseOrValue::Promise(_)
+        ));
+    }
+
+    #[test]
+    fn test_callback_post_capped_stake() {
+        let mut contract = create_contract();
+
+        assert_eq!(
+            contract.callback_post_capped_stake(Ok(U128(10)), U128(50), U128(100)),
+            U128(60)
+        );
+        // the stake failed, the accepted amount is refunded too
+        assert_eq!(
+            contract.callback_post_capped_stake(Err(PromiseError::Failed), U128(50), U128(100)),
+            U128(150)
+        );
+    }
+
+    #[test]
+    fn test_guardian_sets_tvl_cap() {
+        let mut contract = create_contract();
+        contract.extend_guardians(vec![to_account_id("guardian.near")]);
+
+        testing_env!(get_context("guardian.near").build());
+        contract.set_tvl_cap(SEED_ID.to_string(), None, Some(U128(500)));
+        assert_eq!(
+            contract.get_tvl_cap(SEED_ID.to_string()),
+            Some(TvlCap {
+                max_total: None,
+                max_per_account: Some(U128(500)),
+            })
+        );
+
+        contract.set_tvl_cap(SEED_ID.to_string(), None, None);
+        assert_eq!(contract.get_tvl_cap(SEED_ID.to_string()), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR: not allowed")]
+    fn test_set_tvl_cap_not_allowed() {
+        let mut contract = create_contract();
+
+        testing_env!(get_context("user.near").build());
+        contract.set_tvl_cap(SEED_ID.to_string(), Some(U128(1_000)), None);
+    }
+}
@@ -0,0 +1,265 @@
+use crate::*;
+
+/// ERC-4626 style views of the strategies.
+/// Assets are the shares of seed_id (exchange LP shares or pembrock tokens)
+/// and shares are the fft_shares minted by the strategy.
+/// The conversions use the same math as the deposits and `unstake`: rounding down,
+/// except for the fft_shares burnt by a withdrawal, which are rounded up.
+#[near_bindgen]
+impl Contract {
+    /// Returns the amount of assets managed by the strategy of seed_id
+    pub fn total_assets(&self, seed_id: String) -> U128 {
+        self.assert_vault_exists(&seed_id);
+        U128(self.seed_total_amount(&seed_id))
+    }
+
+    /// Returns the fft_shares worth assets, at the current share price
+    pub fn convert_to_shares(&self, seed_id: String, assets: U128) -> U128 {
+        self.assert_vault_exists(&seed_id);
+        U128(self.fft_shares_for_deposit(&seed_id, assets.0))
+    }
+
+    /// Returns the assets worth shares, at the current share price
+    pub fn convert_to_assets(&self, seed_id: String, shares: U128) -> U128 {
+        self.assert_vault_exists(&seed_id);
+        U128(self.seed_amount_for_fft_shares(&seed_id, shares.0))
+    }
+
+    /// Returns the fft_shares minted by a deposit of assets, after the deposit fee, without checking the deposit limits
+    pub fn preview_deposit(&self, seed_id: String, assets: U128) -> U128 {
+        self.assert_vault_exists(&seed_id);
+        let fee = self.internal_deposit_fee(&seed_id, assets.0);
+        U128(self.fft_shares_for_deposit(&seed_id, assets.0 - fee))
+    }
+
+    /// Returns the fft_shares of owner_id burnt by `unstake` to receive assets,
+    /// the withdrawal and early exit fees of owner_id included
+    pub fn preview_withdraw(&self, seed_id: String, assets: U128, owner_id: AccountId) -> U128 {
+        self.assert_vault_exists(&seed_id);
+        let amount = self.internal_gross_withdrawal(&seed_id, &owner_id, assets.0);
+        U128(self.fft_shares_for_withdraw(&seed_id, amount))
+    }
+
+    /// Returns the max assets that can be deposited for receiver_id, below the deposit caps of the strategy.
+    /// 0 if deposits are not accepted.
+    pub fn max_deposit(&self, seed_id: String, receiver_id: AccountId) -> U128 {
+        self.assert_vault_exists(&seed_id);
+
+        let is_accepting = self.data().state == RunningState::Running
+            && self
+                .data()
+                .strategies
+                .get(&seed_id)
+                .map_or(false, |strat| strat.strategy().is_running());
+
+        if is_accepting {
+            U128(self.internal_remaining_capacity(&seed_id, &receiver_id))
+        } else {
+            U128(0)
+        }
+    }
+
+    /// Returns the max assets that owner_id can receive with `unstake`, after the withdrawal and early exit fees
+    pub fn max_withdraw(&self, seed_id: String, owner_id: AccountId) -> U128 {
+        let fft_share_id = self.fft_token_seed_id(seed_id.clone());
+        let fft_shares = self.users_fft_share_amount(fft_share_id, owner_id.clone());
+
+        let amount = self.seed_amount_for_fft_shares(&seed_id, fft_shares);
+        U128(amount - self.internal_withdrawal_fee(&seed_id, &owner_id, amount))
+    }
+}
+
+impl Contract {
+    fn assert_vault_exists(&self, seed_id: &str) {
+        require!(
+            self.data()
+                .fft_share_by_seed_id
+                .get(&seed_id.to_string())
+                .is_some(),
+            "E1: seed_id doesn't exist"
+        );
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::testing_env;
+
+    fn get_context() -> VMContextBuilder {
+        let mut builder = VMContextBuilder::new();
+        builder
+            .current_account_id(to_account_id("auto_compounder.near"))
+            .predecessor_account_id(to_account_id("auto_compounder.near"));
+        builder
+    }
+
+    /// 1_000 fft_shares of user.near are worth 1_500 shares of SEED_ID
+    fn create_contract() -> Contract {
+        testing_env!(get_context().build());
+        let mut contract = create_safe();
+
+        let mut compounder = create_compounder(SEED_ID, 1_000);
+        compounder.farms.push(running_farm("0"));
+        register_strategy(
+            &mut contract,
+            SEED_ID,
+            "fft_share_1",
+            VersionedStrategy::AutoCompounder(compounder),
+            1_500,
+        );
+        contract.mft_mint("fft_share_1".to_string(), 1_000, to_account_id("user.near"));
+
+        contract
+    }
+
+    #[test]
+    fn test_conversions() {
+        let contract = create_contract();
+        let seed_id = SEED_ID.to_string();
+
+        assert_eq!(contract.total_assets(seed_id.clone()), U128(1_500));
+        assert_eq!(
+            contract.convert_to_shares(seed_id.clone(), U128(300)),
+            U128(200)
+        );
+        assert_eq!(
+            contract.convert_to_assets(seed_id.clone(), U128(200)),
+            U128(300)
+        );
+        assert_eq!(
+            contract.preview_deposit(seed_id.clone(), U128(300)),
+            U128(200)
+        );
+        assert_eq!(
+            contract.preview_withdraw(seed_id.clone(), U128(300), to_account_id("user.near")),
+            U128(200)
+        );
+
+        // rounded down, as deposits and unstake do
+        assert_eq!(
+            contract.convert_to_shares(seed_id.clone(), U128(2)),
+            U128(1)
+        );
+        assert_eq!(
+            contract.convert_to_assets(seed_id.clone(), U128(1)),
+            U128(1)
+        );
+
+        // rounded up, a withdrawal always burns fft_shares
+        assert_eq!(
+            contract.preview_withdraw(seed_id, U128(1), to_account_id("user.near")),
+            U128(1)
+        );
+    }
+
+    #[test]
+    fn test_conversions_of_empty_vault() {
+        let mut contract = create_contract();
+        contract
+            .data_mut()
+            .seed_id_amount
+            .insert(&SEED_ID.to_string(), &0u128);
+
+        // the first deposit mints fft_shares 1:1
+        assert_eq!(
+            contract.preview_deposit(SEED_ID.to_string(), U128(300)),
+            U128(300)
+        );
+        assert_eq!(
+            contract.convert_to_assets(SEED_ID.to_string(), U128(300)),
+            U128(0)
+        );
+    }
+
+    #[test]
+    fn test_max_deposit_and_withdraw() {
+        let mut contract = create_contract();
+        let seed_id = SEED_ID.to_string();
+
+        assert_eq!(
+            contract.max_withdraw(seed_id.clone(), to_account_id("user.near")),
+            U128(1_500)
+        );
+        assert_eq!(
+            contract.max_withdraw(seed_id.clone(), to_account_id("other.near")),
+            U128(0)
+        );
+        assert_eq!(
+            contract.max_deposit(seed_id.clone(), to_account_id("user.near")),
+            U128(u128::MAX)
+        );
+
+        contract.update_contract_state(RunningState::Paused);
+        assert_eq!(
+            contract.max_deposit(seed_id, to_account_id("user.near")),
+            U128(0)
+        );
+    }
+
+    #[test]
+    fn test_withdraw_with_fees() {
+        let mut contract = create_contract();
+        let seed_id = SEED_ID.to_string();
+        let user = to_account_id("user.near");
+
+        // 1% withdrawal fee
+        contract.set_entry_exit_fees(seed_id.clone(), 0, 100, 0, 0);
+
+        assert_eq!(
+            contract.max_withdraw(seed_id.clone(), user.clone()),
+            U128(1_485)
+        );
+
+        // 303 shares are withdrawn for 300 to be received, 3 of them are kept as the fee
+        let fft_shares = contract.preview_withdraw(seed_id.clone(), U128(300), user.clone());
+        assert_eq!(fft_shares, U128(202));
+        assert_eq!(contract.internal_gross_withdrawal(SEED_ID, &user, 300), 303);
+        assert_eq!(
+            303 - contract.internal_withdrawal_fee(SEED_ID, &user, 303),
+            300
+        );
+
+        // the whole position is received as max_withdraw
+        assert_eq!(
+            contract.preview_withdraw(seed_id, U128(1_485), user),
+            U128(1_000)
+        );
+    }
+
+    #[test]
+    fn test_withdraw_with_early_exit_fee() {
+        let mut contract = create_contract();
+        let seed_id = SEED_ID.to_string();
+        let user = to_account_id("user.near");
+
+        // user.near withdraws right after its deposit, the early exit fee of 5% is charged in full
+        contract.set_entry_exit_fees(seed_id.clone(), 0, 0, 500, 1_000);
+        assert_eq!(
+            contract.get_withdrawal_fee(seed_id.clone(), user.clone()),
+            500
+        );
+
+        assert_eq!(
+            contract.max_withdraw(seed_id.clone(), user.clone()),
+            U128(1_425)
+        );
+        assert_eq!(
+            contract.internal_gross_withdrawal(SEED_ID, &user, 1_425),
+            1_500
+        );
+        assert_eq!(
+            contract.preview_withdraw(seed_id, U128(1_425), user),
+            U128(1_000)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "E1: seed_id doesn't exist")]
+    fn test_unknown_seed_id() {
+        let contract = create_contract();
+        contract.total_assets("exchange.near@2".to_string());
+    }
+}
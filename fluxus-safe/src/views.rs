@@ -45,14 +45,7 @@ impl Contract {
 
     /// Returns the minimum value accepted for given token_id
     pub fn get_seed_min_deposit(self, seed_id: String) -> U128 {
-        let strat = self.get_strat(&seed_id);
-
-        match strat {
-            VersionedStrategy::AutoCompounder(compounder) => compounder.seed_min_deposit,
-            VersionedStrategy::StableAutoCompounder(compounder) => compounder.seed_min_deposit,
-            VersionedStrategy::JumboAutoCompounder(compounder) => compounder.seed_min_deposit,
-            VersionedStrategy::PembrockAutoCompounder(_) => U128(0),
-        }
+        self.get_strat(&seed_id).strategy().seed_min_deposit()
     }
 
     /// Returns the total amount of near that was deposited
@@ -67,13 +60,7 @@ impl Contract {
     /// Returns all seeds ids/strat_names
     // TODO: refactor, should be get seeds
     pub fn get_allowed_tokens(&self) -> Vec<String> {
-        let mut seeds: Vec<String> = Vec::new();
-
-        for (seed_id, _) in self.data().strategies.iter() {
-            seeds.push(seed_id.clone());
-        }
-
-        seeds
+        self.data().strategies.keys().collect()
     }
 
     // TODO
@@ -94,91 +81,47 @@ impl Contract {
     //     running_strategies
     // }
 
-    /// Return all Strategies
-    pub fn get_strategies(self) -> Vec<AutoCompounderInfo> {
+    /// Return Strategies
+    /// Args:
+    ///   from_index: position of the first strategy, defaults to 0
+    ///   limit: max number of strategies returned, defaults to all
+    pub fn get_strategies(
+        self,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<AutoCompounderInfo> {
         let mut info: Vec<AutoCompounderInfo> = Vec::new();
 
-        // TODO: stable version
-        for (seed_id, strat) in self.data().strategies.clone() {
-            match strat {
-                VersionedStrategy::AutoCompounder(compounder) => {
-                    let mut seed_info = AutoCompounderInfo {
-                        seed_id,
-                        is_active: false,
-                        reward_tokens: vec![],
-                    };
-                    for farm_info in compounder.farms.iter() {
-                        if farm_info.state == AutoCompounderState::Running {
-                            seed_info.is_active = true;
-                        }
-                        seed_info
-                            .reward_tokens
-                            .push(farm_info.reward_token.to_string());
-                    }
-
-                    info.push(seed_info)
-                }
-                VersionedStrategy::StableAutoCompounder(compounder) => {
-                    let mut seed_info = AutoCompounderInfo {
-                        seed_id,
-                        is_active: false,
-                        reward_tokens: vec![],
-                    };
-                    for farm_info in compounder.farms.iter() {
-                        if farm_info.state == AutoCompounderState::Running {
-                            seed_info.is_active = true;
-                        }
-                        seed_info
-                            .reward_tokens
-                            .push(farm_info.reward_token.to_string());
-                    }
-
-                    info.push(seed_info)
-                }
-                VersionedStrategy::JumboAutoCompounder(compounder) => {
-                    let mut seed_info = AutoCompounderInfo {
-                        seed_id,
-                        is_active: false,
-                        reward_tokens: vec![],
-                    };
-                    for farm_info in compounder.farms.iter() {
-                        if farm_info.state == JumboAutoCompounderState::Running {
-                            seed_info.is_active = true;
-                        }
-                        seed_info
-                            .reward_tokens
-                            .push(farm_info.reward_token.to_string());
-                    }
-
-                    info.push(seed_info)
-                }
-                VersionedStrategy::PembrockAutoCompounder(compounder) => {
-                    let mut seed_info = AutoCompounderInfo {
-                        seed_id,
-                        is_active: false,
-                        reward_tokens: vec![],
-                    };
-
-                    if compounder.state == PembAutoCompounderState::Running {
-                        seed_info.is_active = true;
-                    }
-                    seed_info
-                        .reward_tokens
-                        .push(compounder.reward_token.to_string());
-
-                    info.push(seed_info)
-                }
-            };
+        for seed_id in self.get_seed_ids(from_index, limit) {
+            let strat = self.get_strat(&seed_id);
+            let strategy = strat.strategy();
+
+            info.push(AutoCompounderInfo {
+                seed_id,
+                is_active: strategy.is_running(),
+                reward_tokens: strategy
+                    .reward_tokens()
+                    .iter()
+                    .map(|token| token.to_string())
+                    .collect(),
+            })
         }
 
         info
     }
 
-    pub fn get_strategies_info_for_ref_finance(&self) -> Vec<StratFarmInfo> {
+    /// Args:
+    ///   from_index: position of the first strategy, defaults to 0
+    ///   limit: max number of strategies checked, defaults to all
+    pub fn get_strategies_info_for_ref_finance(
+        &self,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<StratFarmInfo> {
         let mut info: Vec<StratFarmInfo> = Vec::new();
-        for (_, strat) in self.data().strategies.iter() {
-            if strat.kind() == *"AUTO_COMPOUNDER" {
-                let compounder = strat.get_compounder_ref();
+        for seed_id in self.get_seed_ids(from_index, limit) {
+            let strat = self.get_strat(&seed_id);
+            if let VersionedStrategy::AutoCompounder(compounder) = strat {
                 for farm in compounder.farms.iter() {
                     info.push(farm.clone())
                 }
@@ -187,11 +130,18 @@ impl Contract {
         info
     }
 
-    pub fn get_strategies_info_for_stable_ref_finance(&self) -> Vec<StableStratFarmInfo> {
+    /// Args:
+    ///   from_index: position of the first strategy, defaults to 0
+    ///   limit: max number of strategies checked, defaults to all
+    pub fn get_strategies_info_for_stable_ref_finance(
+        &self,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<StableStratFarmInfo> {
         let mut info: Vec<StableStratFarmInfo> = Vec::new();
-        for (_, strat) in self.data().strategies.iter() {
-            if strat.kind() == *"STABLE_AUTO_COMPOUNDER" {
-                let compounder = strat.get_stable_compounder_ref();
+        for seed_id in self.get_seed_ids(from_index, limit) {
+            let strat = self.get_strat(&seed_id);
+            if let VersionedStrategy::StableAutoCompounder(compounder) = strat {
                 for farm in compounder.farms.iter() {
                     info.push(farm.clone())
                 }
@@ -201,11 +151,19 @@ impl Contract {
         info
     }
 
-    pub fn get_strategies_info_for_jumbo(&self) -> Vec<JumboStratFarmInfo> {
+    /// Args:
+    ///   from_index: position of the first strategy, defaults to 0
+    ///   limit: max number of strategies checked, defaults to all
+    pub fn get_strategies_info_for_jumbo(
+        &self,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<JumboStratFarmInfo> {
         let mut info: Vec<JumboStratFarmInfo> = Vec::new();
-        for (_, strat) in self.data().strategies.iter() {
-            if strat.kind() == *"JUMBO_AUTO_COMPOUNDER" {
-                for farm in strat.get_jumbo_ref().farms.iter() {
+        for seed_id in self.get_seed_ids(from_index, limit) {
+            let strat = self.get_strat(&seed_id);
+            if let VersionedStrategy::JumboAutoCompounder(compounder) = strat {
+                for farm in compounder.farms.iter() {
                     info.push(farm.clone());
                 }
             }
@@ -214,11 +172,19 @@ impl Contract {
         info
     }
 
-    pub fn get_strategies_info_for_pembrock(&self) -> Vec<PembrockAutoCompounder> {
+    /// Args:
+    ///   from_index: position of the first strategy, defaults to 0
+    ///   limit: max number of strategies checked, defaults to all
+    pub fn get_strategies_info_for_pembrock(
+        &self,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<PembrockAutoCompounder> {
         let mut info: Vec<PembrockAutoCompounder> = Vec::new();
-        for (_, strat) in self.data().strategies.iter() {
-            if strat.kind() == *"PEMBROCK_AUTO_COMPOUNDER" {
-                info.push(strat.pemb_get_ref().clone());
+        for seed_id in self.get_seed_ids(from_index, limit) {
+            let strat = self.get_strat(&seed_id);
+            if let VersionedStrategy::PembrockAutoCompounder(compounder) = strat {
+                info.push(compounder);
             }
         }
 
@@ -240,20 +206,28 @@ impl Contract {
     pub fn get_strategy_for_ref_finance(self, farm_id_str: String) -> AutoCompounderState {
         let (seed_id, _, farm_id) = get_ids_from_farm(farm_id_str);
 
-        let strat = self.get_strat(&seed_id);
-
-        match strat {
+        match self.get_strat(&seed_id) {
             VersionedStrategy::AutoCompounder(compounder) => {
-                let farm_info = compounder.get_farm_info(&farm_id);
-
-                farm_info.state
+                compounder.get_farm_info(&farm_id).state
             }
             VersionedStrategy::StableAutoCompounder(compounder) => {
-                let farm_info = compounder.get_farm_info(&farm_id);
+                compounder.get_farm_info(&farm_id).state
+            }
+            strat => env::panic_str(&format!("ERR: {} is a {} strategy", seed_id, strat.kind())),
+        }
+    }
 
-                farm_info.state
+    /// Returns the state of any kind of strategy
+    /// Args:
+    ///   farm_id_str: exchange@pool_id#farm_id, empty for strategies without farms
+    ///   strat_name: used by strategies without farms, such as pembrock@token
+    pub fn get_strategy_state(&self, farm_id_str: String, strat_name: String) -> String {
+        match strat_name.is_empty() {
+            false => self.get_strat(&strat_name).strategy().farm_state(""),
+            true => {
+                let (seed_id, _, farm_id) = get_ids_from_farm(farm_id_str);
+                self.get_strat(&seed_id).strategy().farm_state(&farm_id)
             }
-            _ => unimplemented!(),
         }
     }
 
@@ -282,129 +256,51 @@ impl Contract {
     pub fn get_contract_amount(self) -> U128 {
         let mut amount: u128 = 0;
 
-        for (seed_id, _) in self.data().strategies.iter() {
-            amount += self.seed_total_amount(seed_id);
+        for seed_id in self.data().strategies.keys() {
+            amount += self.seed_total_amount(&seed_id);
         }
         U128(amount)
     }
 
     ///Return the u128 number of strategies that we have for a specific seed_id.
     pub fn number_of_strategies_by_seed(&self, seed_id: String) -> String {
-        let strat = self.get_strat(&seed_id);
-
-        match strat {
-            VersionedStrategy::AutoCompounder(compounder) => compounder.farms.len().to_string(),
-            VersionedStrategy::StableAutoCompounder(compounder) => {
-                compounder.farms.len().to_string()
-            }
-            VersionedStrategy::JumboAutoCompounder(compounder) => {
-                compounder.farms.len().to_string()
-            }
-            VersionedStrategy::PembrockAutoCompounder(_) => 1.to_string(),
-        }
+        self.get_strat(&seed_id).strategy().farms_len().to_string()
     }
 
     /// Return the total number of strategies created, running or others
     pub fn number_of_strategies(&self) -> U128 {
         let mut count: u128 = 0;
 
-        for (_, strat) in self.data().strategies.iter() {
-            count += match strat {
-                VersionedStrategy::AutoCompounder(compounder) => compounder.farms.len() as u128,
-                VersionedStrategy::StableAutoCompounder(compounder) => {
-                    compounder.farms.len() as u128
-                }
-                VersionedStrategy::JumboAutoCompounder(compounder) => {
-                    compounder.farms.len() as u128
-                }
-                VersionedStrategy::PembrockAutoCompounder(_) => 1,
-            }
+        for seed_id in self.data().strategies.keys() {
+            count += self.get_strat(&seed_id).strategy().farms_len() as u128;
         }
 
         U128(count)
     }
 
+    /// Returns the strategy fee of seed_id as a percentage, e.g. "10.50%"
     pub fn check_fee_by_strategy(&self, seed_id: String) -> String {
-        // let compounder = self.get_strat(&seed_id).get_compounder();
+        let fee = self
+            .get_strat(&seed_id)
+            .strategy()
+            .admin_fees()
+            .strategy_fee;
 
-        let strat = self.get_strat(&seed_id);
-
-        let fee = match strat {
-            VersionedStrategy::AutoCompounder(compounder) => compounder.admin_fees.strategy_fee,
-            VersionedStrategy::StableAutoCompounder(compounder) => {
-                compounder.admin_fees.strategy_fee
-            }
-            VersionedStrategy::JumboAutoCompounder(compounder) => {
-                compounder.admin_fees.strategy_fee
-            }
-            VersionedStrategy::PembrockAutoCompounder(compounder) => {
-                compounder.admin_fees.strategy_fee
-            }
-        };
-
-        format!("{}%", fee)
+        format!("{}.{:02}%", fee / 100, fee % 100)
     }
 
     pub fn is_strategy_active(&self, seed_id: String) -> bool {
-        let strat = self.get_strat(&seed_id);
-
-        match strat {
-            VersionedStrategy::AutoCompounder(compounder) => {
-                for farm in compounder.farms.iter() {
-                    if farm.state == AutoCompounderState::Running {
-                        return true;
-                    }
-                }
-            }
-            VersionedStrategy::StableAutoCompounder(compounder) => {
-                for farm in compounder.farms.iter() {
-                    if farm.state == AutoCompounderState::Running {
-                        return true;
-                    }
-                }
-            }
-            VersionedStrategy::JumboAutoCompounder(compounder) => {
-                for farm in compounder.farms.iter() {
-                    if farm.state == JumboAutoCompounderState::Running {
-                        return true;
-                    }
-                }
-            }
-            VersionedStrategy::PembrockAutoCompounder(compounder) => {
-                if compounder.state == PembAutoCompounderState::Running {
-                    return true;
-                }
-            }
-        }
-
-        false
+        self.get_strat(&seed_id).strategy().is_running()
     }
 
     pub fn current_strat_step(&self, farm_id_str: String, strat_name: String) -> String {
         match strat_name.is_empty() {
-            false => String::from(&self.get_strat(&strat_name).pemb_get().cycle_stage),
+            false => self.get_strat(&strat_name).strategy().farm_cycle_stage(""),
             true => {
                 let (seed_id, _, farm_id) = get_ids_from_farm(farm_id_str);
-                let strat = self.get_strat(&seed_id);
-
-                match strat {
-                    VersionedStrategy::AutoCompounder(compounder) => {
-                        let farm_info = compounder.get_farm_info(&farm_id);
-
-                        String::from(&farm_info.cycle_stage)
-                    }
-                    VersionedStrategy::StableAutoCompounder(compounder) => {
-                        let farm_info = compounder.get_farm_info(&farm_id);
-
-                        String::from(&farm_info.cycle_stage)
-                    }
-                    VersionedStrategy::JumboAutoCompounder(compounder) => {
-                        let farm_info = compounder.get_jumbo_farm_info(&farm_id);
-
-                        String::from(&farm_info.cycle_stage)
-                    }
-                    _ => unimplemented!(),
-                }
+                self.get_strat(&seed_id)
+                    .strategy()
+                    .farm_cycle_stage(&farm_id)
             }
         }
     }
@@ -423,22 +319,10 @@ impl Contract {
     // }
 
     pub fn get_harvest_timestamp(&self, seed_id: String) -> String {
-        let strat = self.get_strat(&seed_id);
-
-        match strat {
-            VersionedStrategy::AutoCompounder(compounder) => {
-                compounder.harvest_timestamp.to_string()
-            }
-            VersionedStrategy::StableAutoCompounder(compounder) => {
-                compounder.harvest_timestamp.to_string()
-            }
-            VersionedStrategy::JumboAutoCompounder(compounder) => {
-                compounder.harvest_timestamp.to_string()
-            }
-            VersionedStrategy::PembrockAutoCompounder(compounder) => {
-                compounder.harvest_timestamp.to_string()
-            }
-        }
+        self.get_strat(&seed_id)
+            .strategy()
+            .harvest_timestamp()
+            .to_string()
     }
 
     pub fn get_strategy_kind(&self, seed_id: String) -> String {
@@ -4,9 +4,9 @@ use serde_json::json;
 
 mod utils;
 
+use fluxus_safe::apply_fee;
 use near_sdk::json_types::U128;
 use near_units::parse_near;
-use percentage::Percentage;
 use workspaces::{
     network::{DevAccountDeployer, Sandbox},
     Account, AccountId, Contract, Network, Worker,
@@ -408,6 +408,7 @@ async fn simulate_stake_and_withdraw() -> anyhow::Result<()> {
     .await?;
 
     utils::add_strategy(
+        &strat_creator_acc,
         &safe_contract,
         &token_reward_1,
         seed_id1.clone(),
@@ -558,20 +559,20 @@ async fn simulate_stake_and_withdraw() -> anyhow::Result<()> {
         round1_owner_shares
     );
 
-    let all_fees_amount = Percentage::from(utils::TOTAL_PROTOCOL_FEE).apply_to(amount_claimed);
+    let all_fees_amount = apply_fee(amount_claimed, utils::TOTAL_PROTOCOL_FEE);
 
     println!("Amount claimed: {}", amount_claimed);
 
     let sentry_due_fees =
-        i128::try_from(Percentage::from(utils::SENTRY_FEES_PERCENT).apply_to(all_fees_amount))
-            .unwrap();
-    let strat_creator_due_fees = i128::try_from(
-        Percentage::from(utils::STRAT_CREATOR_FEES_PERCENT).apply_to(all_fees_amount),
-    )
+        i128::try_from(apply_fee(all_fees_amount, utils::SENTRY_FEES_PERCENT)).unwrap();
+    let strat_creator_due_fees = i128::try_from(apply_fee(
+        all_fees_amount,
+        utils::STRAT_CREATOR_FEES_PERCENT,
+    ))
     .unwrap();
+    // the treasury receives what is left of the fees, including the rounding dust
     let treasury_due_fees =
-        i128::try_from(Percentage::from(utils::TREASURY_FEES_PERCENT).apply_to(all_fees_amount))
-            .unwrap();
+        i128::try_from(all_fees_amount).unwrap() - sentry_due_fees - strat_creator_due_fees;
     let balance_after_sentry = i128::try_from(
         utils::get_balance_of(&sentry_acc, &token_reward_1, true, &worker, None)
             .await?
@@ -642,15 +643,79 @@ async fn simulate_stake_and_withdraw() -> anyhow::Result<()> {
     let account1_initial_shares: String =
         utils::get_pool_shares(&farmer1, &exchange, pool_token1_token2, &worker).await?;
 
+    // a stake rejected by the farm is refunded without minting fft_shares,
+    // the farm has no seed for pool_token1_reward1
+    let unfarmed_seed_id: String = format!("{}@{}", CONTRACT_ID_REF_EXC, pool_token1_reward1);
+
+    utils::create_strategy(
+        &strat_creator_acc,
+        &safe_contract,
+        &exchange,
+        &farm,
+        &token_1,
+        &token_reward_1,
+        pool_token1_reward1,
+        "create_strategy",
+        &worker,
+    )
+    .await?;
+
+    utils::add_strategy(
+        &strat_creator_acc,
+        &safe_contract,
+        &token_reward_1,
+        unfarmed_seed_id.clone(),
+        pool_token1_reward1,
+        pool_token2_reward1,
+        farm_id0,
+        "add_farm_to_strategy",
+        &worker,
+    )
+    .await?;
+
+    let _res = safe_contract
+        .as_account()
+        .call(&worker, exchange.id(), "mft_register")
+        .args_json(serde_json::json!({
+            "token_id": format!(":{}", pool_token1_reward1),
+            "account_id": safe_contract.id() }))?
+        .deposit(parse_near!("1 N"))
+        .transact()
+        .await?;
+
+    utils::stake_rejected_by_farm(
+        &safe_contract,
+        &exchange,
+        &owner,
+        pool_token1_reward1,
+        &unfarmed_seed_id,
+        &worker,
+    )
+    .await?;
+
+    // a stake below the farm min_deposit waits on the exchange as a pending deposit
+    utils::stake_below_min_deposit(
+        &safe_contract,
+        &exchange,
+        &farmer1,
+        pool_token1_token2,
+        &seed_id1,
+        &worker,
+    )
+    .await?;
+
     let pool_id: String = format!(":{}", pool_token1_token2);
 
+    let account1_remaining_shares: String =
+        utils::get_pool_shares(&farmer1, &exchange, pool_token1_token2, &worker).await?;
+
     /* Stake */
     let res = farmer1
         .call(&worker, exchange.id(), "mft_transfer_call")
         .args_json(serde_json::json!({
             "token_id": pool_id.clone(),
             "receiver_id": safe_contract.id().to_string(),
-            "amount": account1_initial_shares,
+            "amount": account1_remaining_shares,
             "msg": ""
         }))?
         .gas(utils::TOTAL_GAS)
@@ -916,6 +981,7 @@ async fn simulate_stake_and_withdraw() -> anyhow::Result<()> {
 
     // Adds new strategy to safe
     utils::add_strategy(
+        &strat_creator_acc,
         &safe_contract,
         &token_reward_2,
         seed_id1.clone(),
@@ -929,6 +995,7 @@ async fn simulate_stake_and_withdraw() -> anyhow::Result<()> {
 
     // (token1, token2) -> token1
     utils::add_strategy(
+        &strat_creator_acc,
         &safe_contract,
         &token_1,
         seed_id1.clone(),
@@ -942,6 +1009,7 @@ async fn simulate_stake_and_withdraw() -> anyhow::Result<()> {
 
     // (token1, token2) -> token2
     utils::add_strategy(
+        &strat_creator_acc,
         &safe_contract,
         &token_2,
         seed_id1.clone(),
@@ -1027,3 +1095,96 @@ async fn simulate_stake_and_withdraw() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn stable_stake_rejected_by_farm() -> anyhow::Result<()> {
+    let worker = workspaces::sandbox().await?;
+    let owner = worker.root_account();
+
+    let exchange_id: AccountId = CONTRACT_ID_REF_EXC.parse().unwrap();
+
+    let (token_1, token_2, _token_reward_1, _token_reward_2, exchange, treasury) =
+        deploy_aux_contracts(&owner, &exchange_id, &worker).await;
+
+    let strat_creator_acc = worker.dev_create_account().await?;
+
+    utils::register_into_contracts(
+        &worker,
+        exchange.as_account(),
+        vec![token_1.id(), token_2.id()],
+    )
+    .await?;
+
+    // the farm has no seed for the stable pool, it rejects every stake
+    let farm = utils::deploy_farm(&owner, &worker).await?;
+
+    let stable_pool = utils::create_stable_pool_with_liquidity(
+        &owner,
+        &exchange,
+        &farm,
+        maplit::hashmap! {
+            token_1.id() => parse_near!("100 N"),
+            token_2.id() => parse_near!("100 N"),
+        },
+        &worker,
+    )
+    .await?;
+
+    let seed_id: String = format!("{}@{}", CONTRACT_ID_REF_EXC, stable_pool);
+
+    let safe_contract = utils::deploy_safe_contract(&strat_creator_acc, &treasury, &worker).await?;
+
+    utils::create_strategy(
+        &strat_creator_acc,
+        &safe_contract,
+        &exchange,
+        &farm,
+        &token_1,
+        &token_2,
+        stable_pool,
+        "create_stable_strategy",
+        &worker,
+    )
+    .await?;
+
+    let res = strat_creator_acc
+        .call(&worker, safe_contract.id(), "add_farm_to_stable_strategy")
+        .args_json(json!({
+            "seed_id": seed_id.clone(),
+            "token_address": token_1.id(),
+            "pool_id_token_reward": utils::POOL_ID_PLACEHOLDER,
+            "token_position": 0,
+            "reward_token": token_1.id(),
+            "available_balance": vec![0, 0],
+            "farm_id": "0",
+        }))?
+        .gas(utils::TOTAL_GAS)
+        .transact()
+        .await?;
+    println!("add stable farm {:#?}", res);
+
+    utils::register_into_contracts(&worker, safe_contract.as_account(), vec![exchange.id()])
+        .await?;
+
+    let _res = safe_contract
+        .as_account()
+        .call(&worker, exchange.id(), "mft_register")
+        .args_json(json!({
+            "token_id": format!(":{}", stable_pool),
+            "account_id": safe_contract.id() }))?
+        .deposit(parse_near!("1 N"))
+        .transact()
+        .await?;
+
+    utils::stake_rejected_by_farm(
+        &safe_contract,
+        &exchange,
+        &owner,
+        stable_pool,
+        &seed_id,
+        &worker,
+    )
+    .await?;
+
+    Ok(())
+}
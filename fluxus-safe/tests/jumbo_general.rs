@@ -12,10 +12,11 @@ use workspaces::{
 
 const TOTAL_GAS: u64 = 300_000_000_000_000;
 
-const TOTAL_PROTOCOL_FEE: u128 = 10;
-const SENTRY_FEES_PERCENT: u128 = 10;
-const STRAT_FEES_PERCENT: u128 = 10;
-const TREASURY_FEES_PERCENT: u128 = 80;
+// fees in basis points
+const TOTAL_PROTOCOL_FEE: u128 = 1_000;
+const SENTRY_FEES_PERCENT: u128 = 1_000;
+const STRAT_FEES_PERCENT: u128 = 1_000;
+const TREASURY_FEES_PERCENT: u128 = 8_000;
 /// Runs the full cycle of auto-compound and fast forward
 async fn jumbo_do_auto_compound_with_fast_forward(
     sentry_acc: &Account,
@@ -397,6 +398,7 @@ async fn test_jumbo() -> anyhow::Result<()> {
     .await?;
 
     utils::add_strategy(
+        &strat_creator_acc,
         &safe_contract,
         &token_reward_1,
         seed_id1.clone(),
@@ -552,15 +554,79 @@ async fn test_jumbo() -> anyhow::Result<()> {
     let account1_initial_shares: String =
         utils::get_pool_shares(&farmer1, &exchange, pool_token1_token2, &worker).await?;
 
+    // a stake rejected by the farm is refunded without minting fft_shares,
+    // the farm has no seed for pool_token1_reward1
+    let unfarmed_seed_id: String = format!("{}@{}", exchange.id(), pool_token1_reward1);
+
+    utils::create_strategy(
+        &strat_creator_acc,
+        &safe_contract,
+        &exchange,
+        &farm,
+        &token_1,
+        &token_reward_1,
+        pool_token1_reward1,
+        "create_jumbo_strategy",
+        &worker,
+    )
+    .await?;
+
+    utils::add_strategy(
+        &strat_creator_acc,
+        &safe_contract,
+        &token_reward_1,
+        unfarmed_seed_id.clone(),
+        pool_token1_reward1,
+        pool_token2_reward1,
+        farm_id0,
+        "add_farm_to_jumbo_strategy",
+        &worker,
+    )
+    .await?;
+
+    let _res = safe_contract
+        .as_account()
+        .call(&worker, exchange.id(), "mft_register")
+        .args_json(serde_json::json!({
+            "token_id": format!(":{}", pool_token1_reward1),
+            "account_id": safe_contract.id() }))?
+        .deposit(parse_near!("1 N"))
+        .transact()
+        .await?;
+
+    utils::stake_rejected_by_farm(
+        &safe_contract,
+        &exchange,
+        &owner,
+        pool_token1_reward1,
+        &unfarmed_seed_id,
+        &worker,
+    )
+    .await?;
+
+    // a stake below the farm min_deposit waits on the exchange as a pending deposit
+    utils::stake_below_min_deposit(
+        &safe_contract,
+        &exchange,
+        &farmer1,
+        pool_token1_token2,
+        &seed_id1,
+        &worker,
+    )
+    .await?;
+
     let pool_id: String = format!(":{}", pool_token1_token2);
 
+    let account1_remaining_shares: String =
+        utils::get_pool_shares(&farmer1, &exchange, pool_token1_token2, &worker).await?;
+
     /* Stake */
     let res = farmer1
         .call(&worker, exchange.id(), "mft_transfer_call")
         .args_json(serde_json::json!({
             "token_id": pool_id.clone(),
             "receiver_id": safe_contract.id().to_string(),
-            "amount": account1_initial_shares,
+            "amount": account1_remaining_shares,
             "msg": ""
         }))?
         .gas(TOTAL_GAS)
@@ -695,6 +761,7 @@ async fn test_jumbo() -> anyhow::Result<()> {
 
     // Adds new strategy to safe
     utils::add_strategy(
+        &strat_creator_acc,
         &safe_contract,
         &token_reward_2,
         seed_id1.clone(),
@@ -0,0 +1,105 @@
+mod utils;
+
+use fluxus_safe::AccountFee;
+use near_units::parse_near;
+use workspaces::{network::DevAccountDeployer, AccountId};
+
+pub const CONTRACT_ID_REF_EXC: &str = "ref-finance-101.testnet";
+
+/// A deposit rejected by pembrock is refunded without minting fft_shares
+#[tokio::test]
+async fn pembrock_stake_rejected_by_farm() -> anyhow::Result<()> {
+    let worker = workspaces::sandbox().await?;
+    let owner = worker.root_account();
+
+    let exchange_id: AccountId = CONTRACT_ID_REF_EXC.parse().unwrap();
+
+    let token = utils::create_custom_ft(&owner, &worker).await?;
+    let exchange = utils::deploy_exchange(&owner, &exchange_id, vec![token.id()], &worker).await?;
+    let treasury = utils::deploy_treasure(&owner, &token, &exchange, &worker).await?;
+
+    let strat_creator_acc = worker.dev_create_account().await?;
+    let farmer1 = worker.dev_create_account().await?;
+
+    // an account without code stands for the pembrock lending contract,
+    // the token refunds every ft_transfer_call to it
+    let pembrock = worker.dev_create_account().await?;
+    utils::register_into_contracts(&worker, &pembrock, vec![token.id()]).await?;
+
+    utils::transfer_tokens(
+        &owner,
+        vec![&farmer1],
+        maplit::hashmap! {
+            token.id() => parse_near!("1,000 N"),
+        },
+        &worker,
+    )
+    .await?;
+
+    let safe_contract = utils::deploy_safe_contract(&strat_creator_acc, &treasury, &worker).await?;
+    utils::register_into_contracts(&worker, safe_contract.as_account(), vec![token.id()]).await?;
+
+    let strat_creator: AccountFee = AccountFee {
+        account_id: strat_creator_acc.id().parse().unwrap(),
+        fee_percentage: utils::STRAT_CREATOR_FEES_PERCENT,
+        current_amount: 0,
+    };
+
+    let res = strat_creator_acc
+        .call(&worker, safe_contract.id(), "pembrock_create_strategy")
+        .args_json(serde_json::json!({
+            "strategy_fee": utils::TOTAL_PROTOCOL_FEE,
+            "strat_creator": strat_creator,
+            "sentry_fee": utils::SENTRY_FEES_PERCENT,
+            "exchange_contract_id": exchange.id(),
+            "pembrock_contract_id": pembrock.id(),
+            "pembrock_reward_id": pembrock.id(),
+            "token_address": token.id(),
+            "pool_id": utils::POOL_ID_PLACEHOLDER,
+            "reward_token": token.id(),
+        }))?
+        .gas(utils::TOTAL_GAS)
+        .transact()
+        .await?;
+    println!("pembrock create strategy {:#?}", res);
+
+    let strat_name: String = format!("pembrock@{}", token.id());
+    let fft_token: String =
+        utils::get_fft_token_by_seed(&safe_contract, &strat_name, &worker).await?;
+
+    let balance_before = utils::get_balance_of(&farmer1, &token, true, &worker, None)
+        .await?
+        .0;
+
+    let res = farmer1
+        .call(&worker, token.id(), "ft_transfer_call")
+        .args_json(serde_json::json!({
+            "receiver_id": safe_contract.id(),
+            "amount": parse_near!("10 N").to_string(),
+            "msg": serde_json::json!({ "strat_name": strat_name }).to_string(),
+        }))?
+        .gas(utils::TOTAL_GAS)
+        .deposit(parse_near!("1 yN"))
+        .transact()
+        .await?;
+    println!("stake rejected by pembrock {:#?}\n", res);
+
+    let balance_after = utils::get_balance_of(&farmer1, &token, true, &worker, None)
+        .await?
+        .0;
+    assert_eq!(
+        balance_after, balance_before,
+        "ERR: the tokens rejected by pembrock were not refunded"
+    );
+
+    let fft_after = utils::get_user_fft(&safe_contract, &farmer1, &fft_token, &worker).await?;
+    assert_eq!(
+        fft_after, 0,
+        "ERR: fft_shares were minted for a stake rejected by pembrock"
+    );
+
+    let seed_after = utils::get_seed_total_amount(&safe_contract, &strat_name, &worker).await?;
+    assert_eq!(seed_after, 0);
+
+    Ok(())
+}
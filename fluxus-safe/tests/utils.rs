@@ -14,10 +14,10 @@ pub const MIN_SEED_DEPOSIT: u128 = 1_000_000_000_000;
 // pub const CONTRACT_ID_FARM: &str = "boostfarm.ref-finance.testnet";
 pub const FT_CONTRACT_FILEPATH: &str = "./res/fungible_token.wasm";
 
-pub const TOTAL_PROTOCOL_FEE: u128 = 10;
-pub const SENTRY_FEES_PERCENT: u128 = 10;
-pub const STRAT_CREATOR_FEES_PERCENT: u128 = 10;
-pub const TREASURY_FEES_PERCENT: u128 = 80;
+// fees in basis points
+pub const TOTAL_PROTOCOL_FEE: u128 = 1_000;
+pub const SENTRY_FEES_PERCENT: u128 = 1_000;
+pub const STRAT_CREATOR_FEES_PERCENT: u128 = 1_000;
 pub const POOL_ID_PLACEHOLDER: u64 = 9999;
 
 type FarmId = String;
@@ -63,6 +63,7 @@ pub struct PoolInfo {
 }
 
 pub async fn add_strategy(
+    owner: &Account,
     safe_contract: &Contract,
     token_reward: &Contract,
     seed_id: String,
@@ -72,8 +73,8 @@ pub async fn add_strategy(
     function_name: &str,
     worker: &Worker<impl DevNetwork>,
 ) -> anyhow::Result<()> {
-    let res = safe_contract
-        .call(worker, function_name)
+    let res = owner
+        .call(worker, safe_contract.id(), function_name)
         .args_json(serde_json::json!({
             "seed_id": seed_id,
             "pool_id_token1_reward": pool_id_token1_reward,
@@ -106,8 +107,8 @@ pub async fn create_strategy(
         current_amount: 0,
     };
 
-    let res = safe_contract
-        .call(worker, function_name)
+    let res = strat_creator
+        .call(worker, safe_contract.id(), function_name)
         .args_json(serde_json::json!({
             "_strategy": "".to_string(),
             "strategy_fee": TOTAL_PROTOCOL_FEE,
@@ -436,6 +437,86 @@ pub async fn create_pool_with_liquidity(
     Ok(pool_id)
 }
 
+/// Creates a stable pool of the given tokens, registers the farm into its LP and adds the liquidity of owner
+pub async fn create_stable_pool_with_liquidity(
+    owner: &Account,
+    exchange: &Contract,
+    farming: &Contract,
+    tokens: HashMap<&AccountId, u128>,
+    worker: &Worker<impl Network>,
+) -> anyhow::Result<u64> {
+    let (token_ids, token_amounts): (Vec<String>, Vec<String>) = tokens
+        .iter()
+        .map(|(id, amount)| (id.to_string(), amount.to_string()))
+        .unzip();
+
+    let res = exchange
+        .call(worker, "extend_whitelisted_tokens")
+        .args_json(serde_json::json!({ "tokens": token_ids }))?
+        .deposit(parse_near!("1 yN"))
+        .gas(TOTAL_GAS)
+        .transact()
+        .await?;
+
+    println!("exchange.extend_whitelisted_tokens {:#?}\n", res);
+
+    let pool_id: u64 = exchange
+        .call(worker, "add_stable_swap_pool")
+        .args_json(serde_json::json!({
+            "tokens": token_ids,
+            "decimals": vec![18; token_ids.len()],
+            "fee": 25,
+            "amp_factor": 240
+        }))?
+        .deposit(parse_near!("1 N"))
+        .gas(TOTAL_GAS)
+        .transact()
+        .await?
+        .json()?;
+
+    let register = exchange
+        .call(worker, "mft_register")
+        .args_json(serde_json::json!({
+            "token_id": format!(":{}", pool_id),
+            "account_id": farming.id()
+        }))?
+        .deposit(parse_near!("1 N"))
+        .gas(TOTAL_GAS)
+        .transact()
+        .await?;
+
+    println!("register == {:#?}\n", register);
+
+    let res = owner
+        .call(worker, exchange.id(), "register_tokens")
+        .args_json(serde_json::json!({
+            "token_ids": token_ids,
+        }))?
+        .deposit(1)
+        .gas(TOTAL_GAS)
+        .transact()
+        .await?;
+
+    println!("register_tokens is {:#?}\n", res);
+
+    deposit_tokens(worker, owner, exchange, tokens).await?;
+
+    let res = owner
+        .call(worker, exchange.id(), "add_stable_liquidity")
+        .args_json(serde_json::json!({
+            "pool_id": pool_id,
+            "amounts": token_amounts,
+            "min_shares": "1",
+        }))?
+        .deposit(parse_near!("1 N"))
+        .gas(TOTAL_GAS)
+        .transact()
+        .await?;
+    println!("added stable liquidity: {:#?}\n", res);
+
+    Ok(pool_id)
+}
+
 pub async fn create_pools(
     owner: &Account,
     exchange: &Contract,
@@ -839,6 +920,121 @@ pub async fn get_seed_total_amount(
     Ok(seed_before_withdraw)
 }
 
+/// Stakes into a strategy whose seed the farm rejects and asserts the LP is refunded
+pub async fn stake_rejected_by_farm(
+    safe_contract: &Contract,
+    exchange: &Contract,
+    account: &Account,
+    pool_id: u64,
+    seed_id: &String,
+    worker: &Worker<impl Network>,
+) -> anyhow::Result<()> {
+    let fft_token: String = get_fft_token_by_seed(safe_contract, seed_id, worker).await?;
+
+    let shares_before = str_to_u128(&get_pool_shares(account, exchange, pool_id, worker).await?);
+    let fft_before = get_user_fft(safe_contract, account, &fft_token, worker).await?;
+    let seed_before = get_seed_total_amount(safe_contract, seed_id, worker).await?;
+
+    // enough to be sent to the farm instead of waiting as a pending deposit
+    let amount: u128 = MIN_SEED_DEPOSIT;
+    assert!(amount <= shares_before);
+
+    let res = account
+        .call(worker, exchange.id(), "mft_transfer_call")
+        .args_json(serde_json::json!({
+            "token_id": format!(":{}", pool_id),
+            "receiver_id": safe_contract.id().to_string(),
+            "amount": amount.to_string(),
+            "msg": ""
+        }))?
+        .gas(TOTAL_GAS)
+        .deposit(parse_near!("1 yN"))
+        .transact()
+        .await?;
+    println!("stake rejected by farm {:#?}\n", res);
+
+    let shares_after = str_to_u128(&get_pool_shares(account, exchange, pool_id, worker).await?);
+    assert_eq!(
+        shares_after, shares_before,
+        "ERR: the LP rejected by the farm was not refunded"
+    );
+
+    let fft_after = get_user_fft(safe_contract, account, &fft_token, worker).await?;
+    assert_eq!(
+        fft_after, fft_before,
+        "ERR: fft_shares were minted for a stake rejected by the farm"
+    );
+
+    let seed_after = get_seed_total_amount(safe_contract, seed_id, worker).await?;
+    assert_eq!(seed_after, seed_before);
+
+    Ok(())
+}
+
+/// Stakes less than the farm min_deposit into the safe and asserts it waits on the exchange as a pending deposit
+pub async fn stake_below_min_deposit(
+    safe_contract: &Contract,
+    exchange: &Contract,
+    account: &Account,
+    pool_id: u64,
+    seed_id: &String,
+    worker: &Worker<impl Network>,
+) -> anyhow::Result<()> {
+    let fft_token: String = get_fft_token_by_seed(safe_contract, seed_id, worker).await?;
+
+    let shares_before = str_to_u128(&get_pool_shares(account, exchange, pool_id, worker).await?);
+    let fft_before = get_user_fft(safe_contract, account, &fft_token, worker).await?;
+    let seed_before = get_seed_total_amount(safe_contract, seed_id, worker).await?;
+
+    // the farm rejects any deposit below the seed min_deposit
+    let amount: u128 = 1_000;
+    assert!(amount < MIN_SEED_DEPOSIT && amount <= shares_before);
+
+    let res = account
+        .call(worker, exchange.id(), "mft_transfer_call")
+        .args_json(serde_json::json!({
+            "token_id": format!(":{}", pool_id),
+            "receiver_id": safe_contract.id().to_string(),
+            "amount": amount.to_string(),
+            "msg": ""
+        }))?
+        .gas(TOTAL_GAS)
+        .deposit(parse_near!("1 yN"))
+        .transact()
+        .await?;
+    println!("stake below min deposit {:#?}\n", res);
+
+    let shares_after = str_to_u128(&get_pool_shares(account, exchange, pool_id, worker).await?);
+    assert_eq!(
+        shares_after,
+        shares_before - amount,
+        "ERR: the LP below the min deposit was refunded"
+    );
+
+    let fft_after = get_user_fft(safe_contract, account, &fft_token, worker).await?;
+    assert!(
+        fft_after > fft_before,
+        "ERR: fft_shares were not minted for a deposit below the min deposit"
+    );
+
+    let seed_after = get_seed_total_amount(safe_contract, seed_id, worker).await?;
+    assert_eq!(seed_after, seed_before + amount);
+
+    let pending: U128 = safe_contract
+        .view(
+            worker,
+            "get_pending_deposits",
+            serde_json::json!({ "seed_id": seed_id })
+                .to_string()
+                .into_bytes(),
+        )
+        .await?
+        .json()?;
+    assert_eq!(pending.0, amount);
+
+    Ok(())
+}
+
 ///////////////////// Jumbo
 
 pub async fn deploy_proxy_contract(
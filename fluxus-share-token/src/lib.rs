@@ -0,0 +1,484 @@
+use near_contract_standards::fungible_token::core::FungibleTokenCore;
+use near_contract_standards::fungible_token::events::{FtBurn, FtMint};
+use near_contract_standards::fungible_token::metadata::{
+    FungibleTokenMetadata, FungibleTokenMetadataProvider,
+};
+use near_contract_standards::fungible_token::resolver::FungibleTokenResolver;
+use near_contract_standards::fungible_token::FungibleToken;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::LazyOption;
+use near_sdk::json_types::U128;
+use near_sdk::{
+    assert_one_yocto, env, ext_contract, log, near_bindgen, require, AccountId, Balance,
+    BorshStorageKey, Gas, PanicOnDefault, PromiseOrValue, PromiseResult,
+};
+
+const NO_DEPOSIT: Balance = 0;
+
+/// Gas of the safe moving the fft_shares of a transfer made on this token
+const GAS_FOR_SAFE_TRANSFER: Gas = Gas(15_000_000_000_000);
+const GAS_FOR_SAFE_TRANSFER_CALLBACK: Gas = Gas(5_000_000_000_000);
+
+/// ft_resolve_transfer gives the safe the refunded tokens too
+const GAS_FOR_RESOLVE_TRANSFER: Gas = Gas(30_000_000_000_000);
+const GAS_FOR_FT_TRANSFER_CALL: Gas = Gas(60_000_000_000_000);
+
+#[ext_contract(ext_safe)]
+pub trait Safe {
+    fn on_share_token_transfer(
+        &mut self,
+        fft_share: String,
+        sender_id: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+    );
+}
+
+#[ext_contract(ext_ft_receiver)]
+pub trait FungibleTokenReceiver {
+    fn ft_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        amount: U128,
+        msg: String,
+    ) -> PromiseOrValue<U128>;
+}
+
+#[ext_contract(ext_self)]
+pub trait ShareTokenCallbacks {
+    fn ft_resolve_transfer(
+        &mut self,
+        sender_id: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+    ) -> U128;
+    fn callback_post_safe_transfer(
+        &mut self,
+        sender_id: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+    ) -> bool;
+}
+
+#[derive(BorshStorageKey, BorshSerialize)]
+enum StorageKey {
+    FungibleToken,
+    Metadata,
+}
+
+/// NEP-141 token of the fft_shares of a strategy, deployed by the safe as a sub-account.
+/// The safe mirrors every mint, burn and transfer of the fft_shares on it, and every transfer
+/// made on this token is sent to the safe, which keeps the fft_shares as the source of truth.
+#[near_bindgen]
+#[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+pub struct Contract {
+    // The safe, it mints, burns and transfers along with the fft_shares
+    owner_id: AccountId,
+
+    // fft_share of the safe represented by this token
+    fft_share: String,
+
+    token: FungibleToken,
+
+    metadata: LazyOption<FungibleTokenMetadata>,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new(owner_id: AccountId, fft_share: String, metadata: FungibleTokenMetadata) -> Self {
+        require!(!env::state_exists(), "Already initialized");
+        metadata.assert_valid();
+
+        Self {
+            owner_id,
+            fft_share,
+            token: FungibleToken::new(StorageKey::FungibleToken),
+            metadata: LazyOption::new(StorageKey::Metadata, Some(&metadata)),
+        }
+    }
+
+    /// Mints amount to account_id, which must have paid its storage with storage_deposit
+    pub fn mint(&mut self, account_id: AccountId, amount: U128) {
+        self.assert_owner();
+        self.assert_registered(&account_id);
+
+        self.token.internal_deposit(&account_id, amount.0);
+
+        FtMint {
+            owner_id: &account_id,
+            amount: &amount,
+            memo: None,
+        }
+        .emit();
+    }
+
+    /// Burns amount from account_id, panics if its balance is not enough
+    pub fn burn(&mut self, account_id: AccountId, amount: U128) {
+        self.assert_owner();
+
+        self.token.internal_withdraw(&account_id, amount.0);
+
+        FtBurn {
+            owner_id: &account_id,
+            amount: &amount,
+            memo: None,
+        }
+        .emit();
+    }
+
+    /// Moves amount from sender_id to receiver_id, mirroring a transfer of fft_shares in the safe.
+    /// receiver_id must have paid its storage with storage_deposit
+    pub fn transfer(&mut self, sender_id: AccountId, receiver_id: AccountId, amount: U128) {
+        self.assert_owner();
+        self.assert_registered(&receiver_id);
+
+        self.token
+            .internal_transfer(&sender_id, &receiver_id, amount.0, None);
+    }
+
+    /// Reverts a transfer made on this token if the safe refused to move the fft_shares.
+    /// Returns true if the safe moved them.
+    #[private]
+    pub fn callback_post_safe_transfer(
+        &mut self,
+        sender_id: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+    ) -> bool {
+        if matches!(env::promise_result(0), PromiseResult::Successful(_)) {
+            return true;
+        }
+
+        log!(
+            "ERR: the safe refused the transfer of {} from {} to {}, reverting",
+            amount.0,
+            sender_id,
+            receiver_id
+        );
+
+        let receiver_balance = self.token.ft_balance_of(receiver_id.clone()).0;
+        if receiver_balance < amount.0 {
+            log!(
+                "ERR: {} already moved the tokens, {} left",
+                receiver_id,
+                receiver_balance
+            );
+            return false;
+        }
+
+        self.token.internal_transfer(
+            &receiver_id,
+            &sender_id,
+            amount.0,
+            Some("revert".to_string()),
+        );
+
+        false
+    }
+
+    pub fn get_owner_id(&self) -> AccountId {
+        self.owner_id.clone()
+    }
+
+    pub fn get_fft_share(&self) -> String {
+        self.fft_share.clone()
+    }
+}
+
+impl Contract {
+    fn assert_owner(&self) {
+        require!(
+            env::predecessor_account_id() == self.owner_id,
+            "ERR_NOT_ALLOWED"
+        );
+    }
+
+    fn assert_registered(&self, account_id: &AccountId) {
+        require!(
+            self.token.accounts.contains_key(account_id),
+            format!(
+                "ERR: {} is not registered, storage_deposit must be called first",
+                account_id
+            )
+        );
+    }
+
+    /// Asks the safe to move the fft_shares of a transfer made on this token
+    fn internal_transfer_on_safe(
+        &self,
+        sender_id: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+    ) {
+        ext_safe::on_share_token_transfer(
+            self.fft_share.clone(),
+            sender_id.clone(),
+            receiver_id.clone(),
+            amount,
+            self.owner_id.clone(),
+            NO_DEPOSIT,
+            GAS_FOR_SAFE_TRANSFER,
+        )
+        .then(ext_self::callback_post_safe_transfer(
+            sender_id,
+            receiver_id,
+            amount,
+            env::current_account_id(),
+            NO_DEPOSIT,
+            GAS_FOR_SAFE_TRANSFER_CALLBACK,
+        ));
+    }
+
+    fn on_account_closed(&mut self, account_id: AccountId, balance: Balance) {
+        log!("Closed @{} with {}", account_id, balance);
+    }
+
+    fn on_tokens_burned(&mut self, account_id: AccountId, amount: Balance) {
+        log!("Account @{} burned {}", account_id, amount);
+    }
+}
+
+#[near_bindgen]
+impl FungibleTokenCore for Contract {
+    #[payable]
+    fn ft_transfer(&mut self, receiver_id: AccountId, amount: U128, memo: Option<String>) {
+        let sender_id = env::predecessor_account_id();
+        self.token.ft_transfer(receiver_id.clone(), amount, memo);
+        self.internal_transfer_on_safe(sender_id, receiver_id, amount);
+    }
+
+    #[payable]
+    fn ft_transfer_call(
+        &mut self,
+        receiver_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+        msg: String,
+    ) -> PromiseOrValue<U128> {
+        assert_one_yocto();
+        require!(
+            env::prepaid_gas() > GAS_FOR_FT_TRANSFER_CALL,
+            "More gas is required"
+        );
+        let sender_id = env::predecessor_account_id();
+
+        self.token
+            .internal_transfer(&sender_id, &receiver_id, amount.0, memo);
+        self.internal_transfer_on_safe(sender_id.clone(), receiver_id.clone(), amount);
+
+        ext_ft_receiver::ft_on_transfer(
+            sender_id.clone(),
+            amount,
+            msg,
+            receiver_id.clone(),
+            NO_DEPOSIT,
+            env::prepaid_gas() - GAS_FOR_FT_TRANSFER_CALL,
+        )
+        .then(ext_self::ft_resolve_transfer(
+            sender_id,
+            receiver_id,
+            amount,
+            env::current_account_id(),
+            NO_DEPOSIT,
+            GAS_FOR_RESOLVE_TRANSFER,
+        ))
+        .into()
+    }
+
+    fn ft_total_supply(&self) -> U128 {
+        self.token.ft_total_supply()
+    }
+
+    fn ft_balance_of(&self, account_id: AccountId) -> U128 {
+        self.token.ft_balance_of(account_id)
+    }
+}
+
+#[near_bindgen]
+impl FungibleTokenResolver for Contract {
+    /// Refunds the unused tokens and gives the safe the fft_shares refunded
+    #[private]
+    fn ft_resolve_transfer(
+        &mut self,
+        sender_id: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+    ) -> U128 {
+        let (used_amount, burned_amount) =
+            self.token
+                .internal_ft_resolve_transfer(&sender_id, receiver_id.clone(), amount);
+        if burned_amount > 0 {
+            self.on_tokens_burned(sender_id.clone(), burned_amount);
+        }
+
+        let refunded = amount.0 - used_amount - burned_amount;
+        if refunded > 0 {
+            self.internal_transfer_on_safe(receiver_id, sender_id, U128(refunded));
+        }
+
+        used_amount.into()
+    }
+}
+
+near_contract_standards::impl_fungible_token_storage!(Contract, token, on_account_closed);
+
+#[near_bindgen]
+impl FungibleTokenMetadataProvider for Contract {
+    fn ft_metadata(&self) -> FungibleTokenMetadata {
+        self.metadata.get().unwrap()
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use near_contract_standards::fungible_token::core::FungibleTokenCore;
+    use near_contract_standards::fungible_token::metadata::FT_METADATA_SPEC;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::{testing_env, RuntimeFeesConfig, VMConfig};
+    use std::collections::HashMap;
+
+    pub fn to_account_id(value: &str) -> AccountId {
+        value.parse().unwrap()
+    }
+
+    fn get_context(predecessor_id: &str) -> VMContextBuilder {
+        let mut builder = VMContextBuilder::new();
+        builder
+            .current_account_id(to_account_id("fft-1.safe.near"))
+            .predecessor_account_id(to_account_id(predecessor_id));
+        builder
+    }
+
+    fn create_contract() -> Contract {
+        testing_env!(get_context("safe.near").build());
+
+        Contract::new(
+            to_account_id("safe.near"),
+            "fft_share_1".to_string(),
+            FungibleTokenMetadata {
+                spec: FT_METADATA_SPEC.to_string(),
+                name: "Fluxus exchange.near@1".to_string(),
+                symbol: "FFT".to_string(),
+                icon: None,
+                reference: None,
+                reference_hash: None,
+                decimals: 24,
+            },
+        )
+    }
+
+    /// Pays the storage of account_id on the token
+    fn register(contract: &mut Contract, account_id: &str) {
+        let min = contract.storage_balance_bounds().min.0;
+        testing_env!(get_context(account_id).attached_deposit(min).build());
+        contract.storage_deposit(None, None);
+        testing_env!(get_context("safe.near").build());
+    }
+
+    #[test]
+    fn test_mint_and_burn() {
+        let mut contract = create_contract();
+        register(&mut contract, "user.near");
+
+        contract.mint(to_account_id("user.near"), U128(100));
+        assert_eq!(
+            contract.ft_balance_of(to_account_id("user.near")),
+            U128(100)
+        );
+        assert_eq!(contract.ft_total_supply(), U128(100));
+
+        contract.burn(to_account_id("user.near"), U128(40));
+        assert_eq!(contract.ft_balance_of(to_account_id("user.near")), U128(60));
+        assert_eq!(contract.ft_total_supply(), U128(60));
+
+        assert_eq!(contract.get_fft_share(), "fft_share_1");
+        assert_eq!(contract.ft_metadata().symbol, "FFT");
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "ERR: user.near is not registered, storage_deposit must be called first"
+    )]
+    fn test_mint_unregistered() {
+        let mut contract = create_contract();
+
+        contract.mint(to_account_id("user.near"), U128(100));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_ALLOWED")]
+    fn test_mint_not_owner() {
+        let mut contract = create_contract();
+        register(&mut contract, "user.near");
+
+        testing_env!(get_context("user.near").build());
+        contract.mint(to_account_id("user.near"), U128(100));
+    }
+
+    #[test]
+    #[should_panic(expected = "The account doesn't have enough balance")]
+    fn test_burn_above_balance() {
+        let mut contract = create_contract();
+        register(&mut contract, "user.near");
+
+        contract.mint(to_account_id("user.near"), U128(10));
+        contract.burn(to_account_id("user.near"), U128(11));
+    }
+
+    #[test]
+    fn test_transfer_mirrors_safe() {
+        let mut contract = create_contract();
+        register(&mut contract, "user.near");
+        register(&mut contract, "user2.near");
+
+        contract.mint(to_account_id("user.near"), U128(100));
+        contract.transfer(
+            to_account_id("user.near"),
+            to_account_id("user2.near"),
+            U128(30),
+        );
+
+        assert_eq!(contract.ft_balance_of(to_account_id("user.near")), U128(70));
+        assert_eq!(
+            contract.ft_balance_of(to_account_id("user2.near")),
+            U128(30)
+        );
+        assert_eq!(contract.ft_total_supply(), U128(100));
+    }
+
+    #[test]
+    fn test_ft_transfer_reverted_by_safe() {
+        let mut contract = create_contract();
+        register(&mut contract, "user.near");
+        register(&mut contract, "user2.near");
+        contract.mint(to_account_id("user.near"), U128(100));
+
+        testing_env!(get_context("user.near").attached_deposit(1).build());
+        contract.ft_transfer(to_account_id("user2.near"), U128(30), None);
+        assert_eq!(
+            contract.ft_balance_of(to_account_id("user2.near")),
+            U128(30)
+        );
+
+        // the safe refused to move the fft_shares
+        testing_env!(
+            get_context("fft-1.safe.near").build(),
+            VMConfig::test(),
+            RuntimeFeesConfig::test(),
+            HashMap::default(),
+            vec![PromiseResult::Failed],
+        );
+        assert!(!contract.callback_post_safe_transfer(
+            to_account_id("user.near"),
+            to_account_id("user2.near"),
+            U128(30),
+        ));
+        assert_eq!(
+            contract.ft_balance_of(to_account_id("user.near")),
+            U128(100)
+        );
+        assert_eq!(contract.ft_balance_of(to_account_id("user2.near")), U128(0));
+    }
+}
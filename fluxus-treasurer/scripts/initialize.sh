@@ -20,7 +20,7 @@ near call $token_out storage_deposit '{"account_id": "'$CONTRACT_NAME'", "regist
 # Make tokens available to swap on exchange (calls storage_deposit and register_tokens)
 near call $CONTRACT_NAME register_token '{ "token" : "'$token_in'", "pool_id": '$pool_token_in' }' --accountId $CONTRACT_NAME --gas $total_gas 
 
-# Add stakeholder account
+# Add stakeholder account, fee in basis points (1000 is 10%)
 near call $CONTRACT_NAME add_stakeholder '{ "account_id": '$username', "fee": '$fee' }' --accountId $CONTRACT_NAME
 
 # Get contracts stakeholders
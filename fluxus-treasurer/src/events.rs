@@ -0,0 +1,122 @@
+use crate::*;
+
+/// Name of the NEP-297 standard implemented by the events below, shared with the safe
+pub const EVENT_STANDARD: &str = "fluxus";
+
+/// Must be bumped whenever the data of an existing event changes
+pub const EVENT_STANDARD_VERSION: &str = "1.0.0";
+
+/// Events are logged as `EVENT_JSON:{"standard":"fluxus","version":"1.0.0","event":"...","data":[...]}`
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(tag = "event", content = "data")]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Event<'a> {
+    /// One entry per stakeholder credited by `internal_distribute`
+    TreasurerDistribution(&'a [TreasurerTransferData<'a>]),
+    TreasurerWithdraw(&'a [TreasurerTransferData<'a>]),
+    TreasurerStateUpdated(&'a [TreasurerStateUpdatedData]),
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct TreasurerTransferData<'a> {
+    pub account_id: &'a AccountId,
+    pub token_id: &'a AccountId,
+    pub amount: U128,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct TreasurerStateUpdatedData {
+    pub state: String,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct EventLog<'a> {
+    standard: &'static str,
+    version: &'static str,
+    #[serde(flatten)]
+    event: &'a Event<'a>,
+}
+
+impl Event<'_> {
+    pub(crate) fn emit(&self) {
+        let log = EventLog {
+            standard: EVENT_STANDARD,
+            version: EVENT_STANDARD_VERSION,
+            event: self,
+        };
+
+        env::log_str(&format!(
+            "EVENT_JSON:{}",
+            near_sdk::serde_json::to_string(&log).expect("TREASURER::ERR_SERIALIZE_EVENT")
+        ));
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::{get_logs, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    pub fn to_account_id(value: &str) -> AccountId {
+        value.parse().unwrap()
+    }
+
+    fn create_contract() -> Contract {
+        Contract::new(
+            to_account_id("auto_compounder.near"),
+            to_account_id("wnear.near"),
+            to_account_id("exchange.near"),
+        )
+    }
+
+    #[test]
+    fn test_distribution_and_withdraw_events() {
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(to_account_id("treasurer.near"))
+            .predecessor_account_id(to_account_id("treasurer.near"))
+            .build());
+
+        let mut contract = create_contract();
+        contract.add_stakeholder(to_account_id("fluxus1.near"), 30);
+        contract.add_stakeholder(to_account_id("fluxus0.near"), 70);
+
+        contract.internal_distribute(Ok(U128(1_000)), U128(1_000));
+        contract.callback_withdraw(Ok(()), to_account_id("fluxus0.near"));
+
+        let events: Vec<String> = get_logs()
+            .into_iter()
+            .filter(|log| log.starts_with("EVENT_JSON:"))
+            .collect();
+
+        assert_eq!(
+            events,
+            vec![
+                r#"EVENT_JSON:{"standard":"fluxus","version":"1.0.0","event":"treasurer_distribution","data":[{"account_id":"fluxus0.near","token_id":"wnear.near","amount":"700"},{"account_id":"fluxus1.near","token_id":"wnear.near","amount":"300"}]}"#,
+                r#"EVENT_JSON:{"standard":"fluxus","version":"1.0.0","event":"treasurer_withdraw","data":[{"account_id":"fluxus0.near","token_id":"wnear.near","amount":"700"}]}"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_state_updated_event() {
+        testing_env!(VMContextBuilder::new()
+            .current_account_id(to_account_id("treasurer.near"))
+            .predecessor_account_id(to_account_id("auto_compounder.near"))
+            .build());
+
+        let mut contract = create_contract();
+        contract.update_contract_state(RunningState::Paused);
+
+        assert_eq!(
+            get_logs(),
+            vec![
+                r#"EVENT_JSON:{"standard":"fluxus","version":"1.0.0","event":"treasurer_state_updated","data":[{"state":"Paused"}]}"#
+            ]
+        );
+    }
+}
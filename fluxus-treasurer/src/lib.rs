@@ -10,12 +10,15 @@ use std::collections::HashMap;
 use std::convert::Into;
 use std::fmt;
 
-use percentage::Percentage;
-
+mod events;
+use events::*;
 mod external_contracts;
 use external_contracts::*;
 mod managed_tokens;
+mod migration;
+use migration::*;
 mod stakeholders;
+use stakeholders::*;
 
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Eq, PartialEq, Clone)]
 #[serde(crate = "near_sdk::serde")]
@@ -39,7 +42,7 @@ pub struct ContractData {
     // Account address that have authority to update the contract state
     owner_id: AccountId,
 
-    // Keeps track of stakeholders addresses and the percentage they have over the fees
+    // Keeps track of stakeholders addresses and the share they have over the fees, in basis points
     stakeholders_fees: HashMap<AccountId, u128>,
 
     stakeholders_amount_available: HashMap<AccountId, u128>,
@@ -55,6 +58,9 @@ pub struct ContractData {
 
     // State is used to update the contract to a Paused/Running state
     state: RunningState,
+
+    /// Block timestamp, in ms, of the latest state migration
+    last_migration_timestamp: u64,
 }
 // Functions that we need to call like a callback.
 #[ext_contract(ext_self)]
@@ -237,21 +243,38 @@ impl Contract {
         // keeps the total value distributed to check if it is not greater than total_amount
         let mut total_distributed: u128 = 0;
 
-        // let mut stakeholders_amounts: Vec<Stakeholder> = Vec::new();
+        // sorted, so the distribution does not depend on the HashMap order
+        let mut stakeholders_fees: Vec<(AccountId, u128)> =
+            self.data().stakeholders_fees.clone().into_iter().collect();
+        stakeholders_fees.sort();
+
         let mut stakeholders_amounts: HashMap<AccountId, u128> = HashMap::new();
 
-        for (account, perc) in self.data().stakeholders_fees.clone() {
-            let percent = Percentage::from(perc);
-            let amount_received: u128 = percent.apply_to(total_amount);
+        for (account, fee) in stakeholders_fees.iter() {
+            let amount_received: u128 = apply_fee(total_amount, *fee);
 
             assert!(
                 amount_received > 0u128,
                 "TREASURER::ERR_CANNOT_GET_PERCENTAGE"
             );
 
-            total_distributed += amount_received.clone();
+            total_distributed += amount_received;
+
+            stakeholders_amounts.insert(account.clone(), amount_received);
+        }
+
+        // when the whole amount is shared, the rounding dust goes to the largest stakeholder
+        let total_fees: u128 = stakeholders_fees.iter().map(|(_, fee)| fee).sum();
+        if total_fees == FEE_DENOMINATOR && total_distributed < total_amount {
+            // ties go to the first account in order
+            let (largest, _) = stakeholders_fees
+                .iter()
+                .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(&a.0)))
+                .unwrap();
 
-            stakeholders_amounts.insert(account, amount_received);
+            let dust = total_amount - total_distributed;
+            *stakeholders_amounts.get_mut(largest).unwrap() += dust;
+            total_distributed += dust;
         }
 
         // TODO: if this goes wrong, the value was already withdraw and there is no way to distribute again
@@ -274,6 +297,21 @@ impl Contract {
                 .insert(acc, current_amount);
         }
 
+        // sorted, so the event does not depend on the HashMap order
+        let mut distribution: Vec<(AccountId, u128)> = stakeholders_amounts.into_iter().collect();
+        distribution.sort();
+
+        let token_out = self.data().token_out.clone();
+        let distribution_data: Vec<TreasurerTransferData> = distribution
+            .iter()
+            .map(|(account_id, amount)| TreasurerTransferData {
+                account_id,
+                token_id: &token_out,
+                amount: U128(*amount),
+            })
+            .collect();
+        Event::TreasurerDistribution(&distribution_data).emit();
+
         PromiseOrValue::Value("Stakeholders can already withdraw from Treasurer".to_string())
     }
 
@@ -322,9 +360,18 @@ impl Contract {
             "TREASURER::ERR_WITHDRAW_FROM_CONTRACT_FAILED"
         );
 
-        self.data_mut()
+        let amount: u128 = self
+            .data_mut()
             .stakeholders_amount_available
-            .insert(account_id.clone(), 0u128);
+            .insert(account_id.clone(), 0u128)
+            .unwrap_or_default();
+
+        Event::TreasurerWithdraw(&[TreasurerTransferData {
+            account_id: &account_id,
+            token_id: &self.data().token_out,
+            amount: U128(amount),
+        }])
+        .emit();
 
         format!("The withdraw from {} was successfully", account_id)
     }
@@ -355,6 +402,12 @@ impl Contract {
 
     pub fn update_contract_state(&mut self, state: RunningState) -> String {
         self.data_mut().state = state;
+
+        Event::TreasurerStateUpdated(&[TreasurerStateUpdatedData {
+            state: self.data().state.to_string(),
+        }])
+        .emit();
+
         format!("{} is {}", env::current_account_id(), self.data().state)
     }
 
@@ -369,13 +422,13 @@ pub struct Contract {
 }
 
 /// Versioned contract data. Allows to easily upgrade contracts.
+/// Older layouts are kept only to be read by `migrate`, new variants must always be appended.
 #[derive(BorshSerialize, BorshDeserialize)]
 pub enum VersionedContractData {
-    V0001(ContractData),
+    V0001(ContractDataV0001),
+    V0002(ContractData),
 }
 
-impl VersionedContractData {}
-
 #[near_bindgen]
 impl Contract {
     /// Function that initialize the contract.
@@ -391,7 +444,7 @@ impl Contract {
         let allowed_accounts: Vec<AccountId> = vec![env::current_account_id()];
 
         Self {
-            data: VersionedContractData::V0001(ContractData {
+            data: VersionedContractData::V0002(ContractData {
                 owner_id,
                 stakeholders_fees: HashMap::new(),
                 stakeholders_amount_available: HashMap::new(),
@@ -399,6 +452,7 @@ impl Contract {
                 token_to_pool: HashMap::new(),
                 state: RunningState::Running,
                 exchange_contract_id,
+                last_migration_timestamp: 0u64,
             }),
         }
     }
@@ -407,15 +461,15 @@ impl Contract {
 impl Contract {
     fn data(&self) -> &ContractData {
         match &self.data {
-            VersionedContractData::V0001(data) => data,
-            _ => unimplemented!(),
+            VersionedContractData::V0002(data) => data,
+            _ => env::panic_str("TREASURER::STATE_NOT_MIGRATED"),
         }
     }
 
     fn data_mut(&mut self) -> &mut ContractData {
         match &mut self.data {
-            VersionedContractData::V0001(data) => data,
-            _ => unimplemented!(),
+            VersionedContractData::V0002(data) => data,
+            _ => env::panic_str("TREASURER::STATE_NOT_MIGRATED"),
         }
     }
 
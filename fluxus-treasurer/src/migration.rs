@@ -0,0 +1,184 @@
+use crate::*;
+
+/// Layout of ContractData stored as VersionedContractData::V0001.
+/// Must not be changed, it is only used to read the state written by previous versions.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct ContractDataV0001 {
+    pub(crate) owner_id: AccountId,
+    pub(crate) stakeholders_fees: HashMap<AccountId, u128>,
+    pub(crate) stakeholders_amount_available: HashMap<AccountId, u128>,
+    pub(crate) token_out: AccountId,
+    pub(crate) token_to_pool: HashMap<AccountId, u64>,
+    pub(crate) exchange_contract_id: AccountId,
+    pub(crate) state: RunningState,
+}
+
+impl From<ContractDataV0001> for ContractData {
+    /// Converts the stakeholders fees from percent to basis points
+    fn from(data: ContractDataV0001) -> Self {
+        ContractData {
+            owner_id: data.owner_id,
+            stakeholders_fees: data
+                .stakeholders_fees
+                .into_iter()
+                .map(|(account_id, fee)| (account_id, fee * 100))
+                .collect(),
+            stakeholders_amount_available: data.stakeholders_amount_available,
+            token_out: data.token_out,
+            token_to_pool: data.token_to_pool,
+            exchange_contract_id: data.exchange_contract_id,
+            state: data.state,
+            last_migration_timestamp: env::block_timestamp_ms(),
+        }
+    }
+}
+
+impl VersionedContractData {
+    /// Returns the version of the layout used to store the data
+    pub fn version(&self) -> &str {
+        match self {
+            VersionedContractData::V0001(_) => "V0001",
+            VersionedContractData::V0002(_) => "V0002",
+        }
+    }
+
+    fn owner_id(&self) -> &AccountId {
+        match self {
+            VersionedContractData::V0001(data) => &data.owner_id,
+            VersionedContractData::V0002(data) => &data.owner_id,
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Converts the stored state to the latest ContractData layout.
+    /// Should be called right after deploying the new code, either by the owner or by the contract itself.
+    #[init(ignore_state)]
+    pub fn migrate() -> Self {
+        let contract: Contract = env::state_read().expect("TREASURER::NOT_INITIALIZED");
+
+        let (caller_acc_id, contract_id) =
+            (env::predecessor_account_id(), env::current_account_id());
+        require!(
+            caller_acc_id == contract_id || caller_acc_id == *contract.data.owner_id(),
+            "TREASURER::ERR_NOT_ALLOWED"
+        );
+
+        let data: ContractData = match contract.data {
+            VersionedContractData::V0001(data) => data.into(),
+            VersionedContractData::V0002(data) => data,
+        };
+
+        Self {
+            data: VersionedContractData::V0002(data),
+        }
+    }
+
+    /// Returns the code version followed by the layout version of the stored data
+    pub fn contract_version(&self) -> String {
+        format!("{} ({})", env!("CARGO_PKG_VERSION"), self.data.version())
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::testing_env;
+
+    fn get_context() -> VMContextBuilder {
+        let mut builder = VMContextBuilder::new();
+        builder
+            .current_account_id(to_account_id("treasurer.near"))
+            .signer_account_id(to_account_id("fluxus.near"))
+            .predecessor_account_id(to_account_id("fluxus.near"));
+        builder
+    }
+
+    pub fn to_account_id(value: &str) -> AccountId {
+        value.parse().unwrap()
+    }
+
+    /// Writes a V0001 state, as stored by the contracts deployed before V0002
+    fn write_v0001_state() {
+        let mut data = ContractDataV0001 {
+            owner_id: to_account_id("fluxus.near"),
+            stakeholders_fees: HashMap::new(),
+            stakeholders_amount_available: HashMap::new(),
+            token_out: to_account_id("wrap.near"),
+            token_to_pool: HashMap::new(),
+            exchange_contract_id: to_account_id("exchange.near"),
+            state: RunningState::Paused,
+        };
+
+        data.stakeholders_fees
+            .insert(to_account_id("stakeholder.near"), 60u128);
+        data.stakeholders_amount_available
+            .insert(to_account_id("stakeholder.near"), 1_000u128);
+        data.token_to_pool.insert(to_account_id("usn.near"), 10u64);
+
+        env::state_write(&Contract {
+            data: VersionedContractData::V0001(data),
+        });
+    }
+
+    #[test]
+    fn test_migrate_from_v0001() {
+        testing_env!(get_context().block_timestamp(42_000_000).build());
+        write_v0001_state();
+
+        let contract = Contract::migrate();
+        env::state_write(&contract);
+
+        // read the migrated state back, as the next call would
+        let contract: Contract = env::state_read().unwrap();
+
+        assert_eq!(contract.data.version(), "V0002");
+        assert_eq!(
+            contract.contract_version(),
+            format!("{} (V0002)", env!("CARGO_PKG_VERSION"))
+        );
+        assert_eq!(contract.data().owner_id, to_account_id("fluxus.near"));
+        assert_eq!(contract.data().token_out, to_account_id("wrap.near"));
+        assert_eq!(
+            contract.data().exchange_contract_id,
+            to_account_id("exchange.near")
+        );
+        assert_eq!(contract.data().state, RunningState::Paused);
+        assert_eq!(contract.data().last_migration_timestamp, 42);
+        assert_eq!(
+            contract.get_stakeholders(),
+            HashMap::from([(to_account_id("stakeholder.near"), 6_000u128)])
+        );
+        assert_eq!(
+            contract.data().stakeholders_amount_available[&to_account_id("stakeholder.near")],
+            1_000u128
+        );
+        assert_eq!(
+            contract.get_registered_tokens(),
+            HashMap::from([(to_account_id("usn.near"), 10u64)])
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "TREASURER::ERR_NOT_ALLOWED")]
+    fn test_migrate_not_allowed() {
+        testing_env!(get_context()
+            .predecessor_account_id(to_account_id("user.near"))
+            .build());
+        write_v0001_state();
+
+        Contract::migrate();
+    }
+
+    #[test]
+    #[should_panic(expected = "TREASURER::STATE_NOT_MIGRATED")]
+    fn test_data_before_migration() {
+        testing_env!(get_context().build());
+        write_v0001_state();
+
+        let contract: Contract = env::state_read().unwrap();
+        contract.get_contract_state();
+    }
+}
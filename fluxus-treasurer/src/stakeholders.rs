@@ -1,9 +1,17 @@
 use crate::*;
 
+/// Stakeholders fees are expressed in basis points, 10_000 is 100%
+pub const FEE_DENOMINATOR: u128 = 10_000;
+
+/// Returns fee_bps of amount, rounded down, without overflowing for any amount
+pub(crate) fn apply_fee(amount: u128, fee_bps: u128) -> u128 {
+    (amount / FEE_DENOMINATOR) * fee_bps + (amount % FEE_DENOMINATOR) * fee_bps / FEE_DENOMINATOR
+}
+
 #[near_bindgen]
 impl Contract {
-    /// Adds account_id and fee percentage to stakeholders_fees if it is not already present
-    /// `fee` should be between 0 and 1, otherwise panics if total_fee exceeds 1
+    /// Adds account_id and fee to stakeholders_fees if it is not already present
+    /// `fee` is in basis points, panics if the total fees exceed 10_000
     pub fn add_stakeholder(&mut self, account_id: AccountId, fee: u128) -> String {
         self.is_owner();
 
@@ -20,7 +28,7 @@ impl Contract {
         total_fees += fee;
 
         assert!(
-            total_fees <= 100u128,
+            total_fees <= FEE_DENOMINATOR,
             "TREASURER::ERR_FEE_EXCEEDS_MAXIMUM_VALUE"
         );
 
@@ -43,6 +51,7 @@ impl Contract {
         self.data_mut().stakeholders_fees.remove(&account_id);
     }
 
+    /// Updates the fee of account_id, `new_percentage` is in basis points
     pub fn update_stakeholder_percentage(
         &mut self,
         account_id: AccountId,
@@ -62,7 +71,7 @@ impl Contract {
         }
 
         assert!(
-            total_fees <= 100u128,
+            total_fees <= FEE_DENOMINATOR,
             "TREASURER::ERR_FEE_EXCEEDS_MAXIMUM_VALUE"
         );
 
@@ -119,8 +128,8 @@ mod tests {
         let acc0: AccountId = to_account_id("fluxus0");
         let acc1: AccountId = to_account_id("fluxus1");
 
-        let fee0: u128 = 40;
-        let fee1: u128 = 50;
+        let fee0: u128 = 4_000;
+        let fee1: u128 = 5_000;
 
         contract.add_stakeholder(acc0.clone(), fee0);
         contract.add_stakeholder(acc1.clone(), fee1);
@@ -140,7 +149,7 @@ mod tests {
 
         let acc0: AccountId = to_account_id("fluxus0");
 
-        let fee0: u128 = 40;
+        let fee0: u128 = 4_000;
 
         contract.add_stakeholder(acc0.clone(), fee0);
         let stakeholders_fees: HashMap<AccountId, u128> = contract.get_stakeholders();
@@ -162,12 +171,12 @@ mod tests {
         let acc0: AccountId = to_account_id("fluxus0");
         let acc1: AccountId = to_account_id("fluxus1");
 
-        let fee0: u128 = 50;
-        let fee1: u128 = 60;
+        let fee0: u128 = 5_000;
+        let fee1: u128 = 6_000;
 
         contract.add_stakeholder(acc0.clone(), fee0);
 
-        // panics because the fee will be above 1
+        // panics because the fees will be above 100%
         contract.add_stakeholder(acc1.clone(), fee1);
     }
 
@@ -181,8 +190,8 @@ mod tests {
         let acc0: AccountId = to_account_id("fluxus0");
         let acc1: AccountId = to_account_id("fluxus1");
 
-        let fee0: u128 = 50;
-        let fee1: u128 = 40;
+        let fee0: u128 = 5_000;
+        let fee1: u128 = 4_000;
 
         contract.add_stakeholder(acc0.clone(), fee0);
         contract.add_stakeholder(acc1.clone(), fee1);
@@ -191,7 +200,7 @@ mod tests {
 
         assert_eq!(stakeholders_fees.get(&acc0), Some(&fee0));
 
-        let new_fee_percentage = 60u128;
+        let new_fee_percentage = 6_000u128;
 
         contract.update_stakeholder_percentage(acc0.clone(), new_fee_percentage);
 
@@ -202,7 +211,7 @@ mod tests {
             total_fees += perc;
         }
 
-        assert_eq!(total_fees, 100u128, "ERR_WRONG_FEE_TOTAL_AMOUNT");
+        assert_eq!(total_fees, FEE_DENOMINATOR, "ERR_WRONG_FEE_TOTAL_AMOUNT");
 
         assert_eq!(
             stakeholders_fees.get(&acc0),
@@ -210,4 +219,47 @@ mod tests {
             "ERR_UPDATE_PERCENTAGE"
         );
     }
+
+    #[test]
+    fn test_apply_fee() {
+        assert_eq!(apply_fee(1_000_000, 2_500), 250_000);
+        assert_eq!(apply_fee(9_999, 3_333), 3_332);
+        assert_eq!(apply_fee(u128::MAX, FEE_DENOMINATOR), u128::MAX);
+    }
+
+    #[test]
+    fn test_distribute_adds_up_to_amount() {
+        let context = get_context();
+        testing_env!(context.build());
+
+        let mut contract = create_contract();
+
+        contract.add_stakeholder(to_account_id("fluxus0"), 3_333);
+        contract.add_stakeholder(to_account_id("fluxus1"), 3_334);
+        contract.add_stakeholder(to_account_id("fluxus2"), 3_333);
+
+        contract.internal_distribute(Ok(U128(1_000)), U128(1_000));
+
+        let available = &contract.data().stakeholders_amount_available;
+        assert_eq!(available[&to_account_id("fluxus0")], 333);
+        // 333 plus the rounding dust
+        assert_eq!(available[&to_account_id("fluxus1")], 334);
+        assert_eq!(available[&to_account_id("fluxus2")], 333);
+    }
+
+    #[test]
+    fn test_distribute_keeps_unallocated_amount() {
+        let context = get_context();
+        testing_env!(context.build());
+
+        let mut contract = create_contract();
+
+        contract.add_stakeholder(to_account_id("fluxus0"), 2_500);
+        contract.internal_distribute(Ok(U128(1_001)), U128(1_001));
+
+        assert_eq!(
+            contract.data().stakeholders_amount_available[&to_account_id("fluxus0")],
+            250
+        );
+    }
 }